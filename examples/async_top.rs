@@ -1,6 +1,6 @@
-use hn_api::{nonblocking::HnClient, Item, Result, User};
+use hn_api::{nonblocking::HnClient, Item, ItemId, Result, User};
 
-async fn get_items(api: &HnClient, items: &[u32]) -> Result<Vec<(Item, Option<User>)>> {
+async fn get_items(api: &HnClient, items: &[ItemId]) -> Result<Vec<(Item, Option<User>)>> {
     let items = api.get_items(items).await?;
     // To convert a form that try_get_authers accepts
     let items: Vec<Option<Item>> = items.into_iter().map(Some).collect();
@@ -8,19 +8,19 @@ async fn get_items(api: &HnClient, items: &[u32]) -> Result<Vec<(Item, Option<Us
     // Convert back to the original form
     let items = items.into_iter().map(Option::unwrap);
 
-    let items_and_authors: Vec<_> = items.zip(authors.into_iter()).collect();
+    let items_and_authors: Vec<_> = items.zip(authors).collect();
 
     Ok(items_and_authors)
 }
 
-async fn print(api: &HnClient, items: &[u32]) {
+async fn print(api: &HnClient, items: &[ItemId]) {
     let items = get_items(api, items).await.expect("Can not retrive items");
 
     for (item, user) in items {
         print!("- {}: {}", item.id(), item.title().unwrap_or("?"),);
 
         if let Some(user) = user {
-            print!(" (by {})", format!("{}, karma {}", user.id, user.karma));
+            print!(" (by {}, karma {})", user.id, user.karma);
         }
 
         println!();
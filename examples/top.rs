@@ -1,18 +1,21 @@
-use hn_api::HnClient;
+use hn_api::{HnClient, Item, ItemId};
+
+fn print_item(api: &HnClient, item: &Item) {
+    let author = item.author().map(|username| {
+        let user = api.get_user(username).unwrap().unwrap();
+        format!("{}, karma {}", username, user.karma)
+    });
+    println!(
+        "- {}: {} (by {})",
+        item.id(),
+        item.title().unwrap_or("?"),
+        author.unwrap_or_else(|| "?".to_string()),
+    )
+}
 
-fn print(api: &HnClient, items: &[u32]) {
+fn print(api: &HnClient, items: &[ItemId]) {
     for id in items {
-        let item = api.get_item(*id).unwrap().unwrap();
-        let author = item.author().map(|username| {
-            let user = api.get_user(username).unwrap().unwrap();
-            format!("{}, karma {}", username, user.karma)
-        });
-        println!(
-            "- {}: {} (by {})",
-            item.id(),
-            item.title().unwrap_or("?"),
-            author.unwrap_or_else(|| "?".to_string()),
-        )
+        print_item(api, &api.get_item(*id).unwrap().unwrap());
     }
 }
 
@@ -21,14 +24,16 @@ fn main() {
 
     let api = HnClient::init().unwrap();
 
-    let top = api.get_top_stories().unwrap();
+    let count = 3;
+
+    let top = api.get_top_stories_with_items(count).unwrap();
     let new = api.get_new_stories().unwrap();
     let best = api.get_best_stories().unwrap();
 
-    let count = 3;
-
     println!("Top {} stories:", count);
-    print(&api, &top[..count]);
+    for item in &top {
+        print_item(&api, item);
+    }
 
     println!("\nNewest count stories:");
     print(&api, &new[..count]);
@@ -0,0 +1,227 @@
+//! Alerts for items matching keywords, domains or authors.
+//!
+//! Register rules, then feed the alerts a stream of items, e.g. from
+//! [`HnClient::recent_items_stream`], and receive an [`Alert`] for every
+//! matching item over a channel.
+//!
+//! ```rust,no_run
+//! use futures::StreamExt;
+//! use hn_api::{
+//!     alerts::{Alerts, Rule},
+//!     nonblocking::HnClient,
+//! };
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let mut alerts = Alerts::new();
+//! alerts
+//!     .add("rust", Rule::Keyword("rust".into()))
+//!     .add("github", Rule::Domain("github.com".into()));
+//! let (sender, mut receiver) = tokio::sync::mpsc::channel(16);
+//! let items = client.recent_items_stream().take(1000);
+//! tokio::join!(alerts.run(items, sender), async {
+//!     while let Some(alert) = receiver.recv().await {
+//!         println!("{:?} matched {}", alert.rules, alert.item.id());
+//!     }
+//! });
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`HnClient::recent_items_stream`]: crate::nonblocking::HnClient::recent_items_stream
+
+use futures::{Stream, StreamExt};
+use tokio::sync::mpsc::Sender;
+
+use super::{
+    domain, html, mentions,
+    types::{Item, Username},
+    Result,
+};
+
+/// A condition an item can match.
+#[derive(Debug, Clone)]
+pub enum Rule {
+    /// The title or text contains the word, ignoring case.
+    Keyword(String),
+    /// The title or text matches the regular expression. Only with the
+    /// `regex` feature.
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+    /// The story links to the domain or one of its subdomains.
+    Domain(String),
+    /// The item was written by the user.
+    Author(Username),
+    /// The text mentions the user as `@username` or by the bare username,
+    /// ignoring case, see [`mentions`](crate::mentions).
+    Mention(Username),
+}
+
+impl Rule {
+    /// Return whether the item matches the rule.
+    pub fn matches(&self, item: &Item) -> bool {
+        match self {
+            Rule::Keyword(keyword) => contains_word(&searchable_text(item), keyword),
+            #[cfg(feature = "regex")]
+            Rule::Regex(regex) => regex.is_match(&searchable_text(item)),
+            Rule::Domain(name) => domain::is_from_domain(item, name),
+            Rule::Author(author) => item.author() == Some(author.as_str()),
+            Rule::Mention(username) => {
+                let text = html::plain_text(item.text().unwrap_or_default());
+                mentions::find_mention(&text, username.as_str()).is_some()
+            }
+        }
+    }
+}
+
+/// An item that matched one or more rules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    /// The names of the matched rules, in the order they were added.
+    pub rules: Vec<String>,
+    /// The matching item.
+    pub item: Item,
+}
+
+/// A set of named rules.
+#[derive(Debug, Clone, Default)]
+pub struct Alerts {
+    rules: Vec<(String, Rule)>,
+}
+
+impl Alerts {
+    /// Create alerts without any rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule under the specified name.
+    pub fn add(&mut self, name: impl Into<String>, rule: Rule) -> &mut Self {
+        self.rules.push((name.into(), rule));
+        self
+    }
+
+    /// Return the alert for an item, or `None` if it matches no rule. Dead
+    /// and deleted items never match.
+    pub fn check(&self, item: &Item) -> Option<Alert> {
+        if item.is_dead() || item.is_deleted() {
+            return None;
+        }
+        let rules: Vec<String> = self
+            .rules
+            .iter()
+            .filter(|(_, rule)| rule.matches(item))
+            .map(|(name, _)| name.clone())
+            .collect();
+        if rules.is_empty() {
+            return None;
+        }
+        Some(Alert {
+            rules,
+            item: item.clone(),
+        })
+    }
+
+    /// Check every item of the stream and send the alerts to `sender`.
+    ///
+    /// Returns when the stream ends or the receiver is dropped. Failed
+    /// fetches are skipped.
+    pub async fn run<S>(&self, items: S, sender: Sender<Alert>)
+    where
+        S: Stream<Item = Result<Item>>,
+    {
+        futures::pin_mut!(items);
+        while let Some(item) = items.next().await {
+            let alert = match item {
+                Ok(item) => self.check(&item),
+                Err(_) => continue,
+            };
+            if let Some(alert) = alert {
+                if sender.send(alert).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Return the title and text of an item as plain text.
+fn searchable_text(item: &Item) -> String {
+    let title = item.title().unwrap_or_default();
+    let text = html::plain_text(item.text().unwrap_or_default());
+    format!("{}\n{}", title, text)
+}
+
+/// Return whether `word` occurs in `text` on word boundaries, ignoring case.
+fn contains_word(text: &str, word: &str) -> bool {
+    let text = text.to_lowercase();
+    let word = word.to_lowercase();
+    if word.is_empty() {
+        return false;
+    }
+    text.match_indices(&word).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + word.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn story(title: &str, by: &str) -> Item {
+        serde_json::from_value(serde_json::json!({
+            "by": by, "id": 1, "time": 0, "title": title,
+            "url": "https://github.com/x", "type": "story"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_alerts() {
+        let mut alerts = Alerts::new();
+        alerts
+            .add("rust", Rule::Keyword("Rust".into()))
+            .add("github", Rule::Domain("github.com".into()))
+            .add("pg", Rule::Author("pg".into()));
+
+        let alert = alerts.check(&story("Why I trust Rust", "a")).unwrap();
+        assert_eq!(alert.rules, vec!["rust", "github"]);
+
+        let alert = alerts.check(&story("Trustworthy", "pg")).unwrap();
+        assert_eq!(alert.rules, vec!["github", "pg"]);
+
+        let mut alerts = Alerts::new();
+        alerts.add("rust", Rule::Keyword("rust".into()));
+        assert!(alerts.check(&story("Trustworthy", "pg")).is_none());
+
+        #[cfg(feature = "regex")]
+        {
+            let mut alerts = Alerts::new();
+            alerts.add(
+                "version",
+                Rule::Regex(regex::Regex::new(r"\d+\.\d+").unwrap()),
+            );
+            assert!(alerts.check(&story("Rust 2.0", "a")).is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run() {
+        let mut alerts = Alerts::new();
+        alerts.add("rust", Rule::Keyword("rust".into()));
+        let items = futures::stream::iter(vec![
+            Ok(story("Rust 2.0", "a")),
+            Err(crate::HnClientError::TimeoutError),
+            Ok(story("Go 2.0", "a")),
+        ]);
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(4);
+        alerts.run(items, sender).await;
+        assert_eq!(
+            receiver.recv().await.unwrap().item.title(),
+            Some("Rust 2.0")
+        );
+        assert!(receiver.recv().await.is_none());
+    }
+}
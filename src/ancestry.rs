@@ -0,0 +1,168 @@
+//! Resolve the parents of comments up to the story they belong to.
+//!
+//! The parent of an item never changes, so every client remembers the
+//! parents it has seen. Resolving the same thread again then needs fewer
+//! round trips: [`HnClient::get_root_story`] skips straight to the highest
+//! known ancestor, and [`HnClient::get_parent_chain`] fetches all known
+//! ancestors at once.
+//!
+//! ```rust,no_run
+//! use hn_api::nonblocking::HnClient;
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let story = client.get_root_story(8863).await?;
+//! println!("{}", story.title().unwrap_or_default());
+//! for parent in client.get_parent_chain(8863).await? {
+//!     println!("{} by {}", parent.id(), parent.author().unwrap_or("?"));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::HashMap, sync::Mutex};
+
+use super::{
+    nonblocking::HnClient,
+    types::{Item, ItemId},
+    HnClientError, Result,
+};
+
+/// The most parents walked up from an item.
+pub(crate) const MAX_DEPTH: usize = 100;
+
+/// The most parent links remembered by a client. The cache is cleared when
+/// it grows larger.
+const MAX_CACHED: usize = 100_000;
+
+/// The parents of the items a client has seen, by item id.
+#[derive(Debug, Default)]
+pub(crate) struct ParentCache(Mutex<HashMap<ItemId, ItemId>>);
+
+impl ParentCache {
+    /// Remember the parent of `item`, if it has one.
+    pub(crate) fn record(&self, item: &Item) {
+        if let Some(parent) = item.parent() {
+            let mut parents = self.0.lock().unwrap();
+            if parents.len() >= MAX_CACHED {
+                parents.clear();
+            }
+            parents.insert(item.id(), parent);
+        }
+    }
+
+    /// Return the known ancestors of `id`, nearest first.
+    fn known_ancestors(&self, id: ItemId) -> Vec<ItemId> {
+        let parents = self.0.lock().unwrap();
+        let mut ancestors = Vec::new();
+        let mut id = id;
+        while let Some(parent) = parents.get(&id) {
+            if ancestors.len() >= MAX_DEPTH {
+                break;
+            }
+            ancestors.push(*parent);
+            id = *parent;
+        }
+        ancestors
+    }
+}
+
+impl HnClient {
+    /// Return the item at the top of the thread of an item, usually a story.
+    /// An item without a parent is returned itself.
+    ///
+    /// May return error if item id is invalid or not exist, or an item of
+    /// the thread does not exist.
+    /// Fails if any of the request failed.
+    pub async fn get_root_story(&self, id: impl Into<ItemId>) -> Result<Item> {
+        let start = id.into();
+        let mut id = start;
+        for _ in 0..MAX_DEPTH {
+            id = self.parent_cache().known_ancestors(id).pop().unwrap_or(id);
+            let item = self.get_item(id).await?;
+            self.parent_cache().record(&item);
+            match item.parent() {
+                Some(parent) => id = parent,
+                None => return Ok(item),
+            }
+        }
+        Err(too_deep(start))
+    }
+
+    /// Return the ancestors of an item, from its parent up to the item at the
+    /// top of the thread. Empty if the item has no parent.
+    ///
+    /// May return error if item id is invalid or not exist, or an item of
+    /// the thread does not exist.
+    /// Fails if any of the request failed.
+    pub async fn get_parent_chain(&self, id: impl Into<ItemId>) -> Result<Vec<Item>> {
+        let start = id.into();
+        let mut chain: Vec<Item> = Vec::new();
+        let mut parent = match self.parent_cache().known_ancestors(start).first() {
+            Some(parent) => Some(*parent),
+            None => {
+                let item = self.get_item(start).await?;
+                self.parent_cache().record(&item);
+                item.parent()
+            }
+        };
+        while let Some(id) = parent {
+            if chain.len() >= MAX_DEPTH {
+                return Err(too_deep(start));
+            }
+            let mut ids = vec![id];
+            ids.extend(self.parent_cache().known_ancestors(id));
+            let items = self.get_items(&ids).await?;
+            for item in &items {
+                self.parent_cache().record(item);
+            }
+            parent = items.last().and_then(Item::parent);
+            chain.extend(items);
+        }
+        Ok(chain)
+    }
+}
+
+fn too_deep(id: ItemId) -> HnClientError {
+    HnClientError::BackendError(format!(
+        "thread of item {} is deeper than {} items",
+        id, MAX_DEPTH
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cassette::Cassette;
+
+    #[tokio::test]
+    async fn test_parent_chain() {
+        let api = "https://hacker-news.firebaseio.com/v0";
+        let mut cassette = Cassette::new();
+        cassette
+            .push(
+                format!("{}/item/1.json", api),
+                r#"{"id": 1, "score": 1, "time": 0, "type": "story"}"#,
+            )
+            .push(
+                format!("{}/item/2.json", api),
+                r#"{"by": "a", "id": 2, "parent": 1, "text": "", "time": 0, "type": "comment"}"#,
+            )
+            .push(
+                format!("{}/item/3.json", api),
+                r#"{"by": "a", "id": 3, "parent": 2, "text": "", "time": 0, "type": "comment"}"#,
+            );
+        let client = HnClient::builder().replay(cassette).build().unwrap();
+
+        let chain = client.get_parent_chain(3).await.unwrap();
+        let ids: Vec<_> = chain.iter().map(Item::id).collect();
+        assert_eq!(ids, vec![ItemId(2), ItemId(1)]);
+        assert_eq!(
+            client.parent_cache().known_ancestors(ItemId(3)),
+            vec![ItemId(2), ItemId(1)]
+        );
+        assert_eq!(client.get_root_story(3).await.unwrap().id(), ItemId(1));
+        assert_eq!(client.get_root_story(1).await.unwrap().id(), ItemId(1));
+        assert!(client.get_parent_chain(1).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,413 @@
+//! An object-safe interface to the API, for dependency injection.
+//!
+//! [`HnApi`] is implemented by the async [`HnClient`], by [`ArchiveClient`]
+//! and, with the `store-sqlite` feature, by
+//! [`StoredHnClient`](crate::sqlite::StoredHnClient), and can be implemented
+//! by caching decorators or mocks.
+//! Application code can hold an `Arc<dyn HnApi>` and stay independent of
+//! the actual implementation.
+//!
+//! ```rust,no_run
+//! use std::sync::Arc;
+//!
+//! use hn_api::{api::HnApi, nonblocking::HnClient, StoryList};
+//!
+//! async fn newest_title(api: &dyn HnApi) -> hn_api::Result<String> {
+//!     let ids = api.get_stories(StoryList::New).await?;
+//!     let item = api.get_item(ids[0]).await?;
+//!     Ok(item.title().unwrap_or_default().to_string())
+//! }
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let api: Arc<dyn HnApi> = Arc::new(HnClient::init()?);
+//! println!("{}", newest_title(api.as_ref()).await?);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`CachedHnClient`] wraps any implementation with a read-through cache in
+//! a [`CacheStore`].
+//!
+//! [`HnClient`]: crate::nonblocking::HnClient
+//! [`ArchiveClient`]: crate::archive::ArchiveClient
+
+use std::{collections::HashMap, time::Duration};
+
+use bytes::Bytes;
+use futures::future::{BoxFuture, FutureExt};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{
+    archive::ArchiveClient,
+    cache::CacheStore,
+    nonblocking::HnClient,
+    types::{Item, ItemId, StoryList, Timestamp, Updates, User},
+    HnClientError, Result,
+};
+
+/// The API endpoints, with boxed futures so that the trait can be used as
+/// `dyn HnApi`.
+pub trait HnApi: Send + Sync {
+    /// Return the item with the specified id.
+    ///
+    /// May return `None` if item id is invalid.
+    fn try_get_item(&self, id: ItemId) -> BoxFuture<'_, Result<Option<Item>>>;
+
+    /// Return the user with the specified username.
+    ///
+    /// May return `None` if username is invalid.
+    fn try_get_user<'a>(&'a self, username: &'a str) -> BoxFuture<'a, Result<Option<User>>>;
+
+    /// Return the id of the newest item.
+    fn get_max_item_id(&self) -> BoxFuture<'_, Result<ItemId>>;
+
+    /// Return the item ids of the specified story listing, in ranked order.
+    fn get_stories(&self, list: StoryList) -> BoxFuture<'_, Result<Vec<ItemId>>>;
+
+    /// Return a list of items and users that have been updated recently.
+    fn get_updates(&self) -> BoxFuture<'_, Result<Updates>>;
+
+    /// Return the item with the specified id.
+    ///
+    /// May return error if item id is invalid or not exist.
+    fn get_item(&self, id: ItemId) -> BoxFuture<'_, Result<Item>> {
+        async move {
+            self.try_get_item(id)
+                .await?
+                .ok_or(HnClientError::ItemNotFoundError(id))
+        }
+        .boxed()
+    }
+
+    /// Return the user with the specified username.
+    ///
+    /// May return error if username is invalid or not exist.
+    fn get_user<'a>(&'a self, username: &'a str) -> BoxFuture<'a, Result<User>> {
+        async move {
+            self.try_get_user(username)
+                .await?
+                .ok_or_else(|| HnClientError::UserNotFoundError(username.to_string()))
+        }
+        .boxed()
+    }
+
+    /// Return the items with the specified ids, in order. Missing items are
+    /// skipped.
+    ///
+    /// Fails if any of the request failed.
+    fn get_items<'a>(&'a self, ids: &'a [ItemId]) -> BoxFuture<'a, Result<Vec<Item>>> {
+        async move {
+            let mut items = Vec::with_capacity(ids.len());
+            for id in ids {
+                items.extend(self.try_get_item(*id).await?);
+            }
+            Ok(items)
+        }
+        .boxed()
+    }
+}
+
+impl HnApi for HnClient {
+    fn try_get_item(&self, id: ItemId) -> BoxFuture<'_, Result<Option<Item>>> {
+        HnClient::try_get_item(self, id).boxed()
+    }
+
+    fn try_get_user<'a>(&'a self, username: &'a str) -> BoxFuture<'a, Result<Option<User>>> {
+        HnClient::try_get_user(self, username).boxed()
+    }
+
+    fn get_max_item_id(&self) -> BoxFuture<'_, Result<ItemId>> {
+        HnClient::get_max_item_id(self).boxed()
+    }
+
+    fn get_stories(&self, list: StoryList) -> BoxFuture<'_, Result<Vec<ItemId>>> {
+        HnClient::get_stories(self, list).boxed()
+    }
+
+    fn get_updates(&self) -> BoxFuture<'_, Result<Updates>> {
+        HnClient::get_updates(self).boxed()
+    }
+
+    fn get_items<'a>(&'a self, ids: &'a [ItemId]) -> BoxFuture<'a, Result<Vec<Item>>> {
+        self.get_items_filtered(ids, crate::types::ItemFilter::ALL)
+            .boxed()
+    }
+}
+
+impl HnApi for ArchiveClient {
+    fn try_get_item(&self, id: ItemId) -> BoxFuture<'_, Result<Option<Item>>> {
+        ArchiveClient::try_get_item(self, id).boxed()
+    }
+
+    fn try_get_user<'a>(&'a self, username: &'a str) -> BoxFuture<'a, Result<Option<User>>> {
+        ArchiveClient::try_get_user(self, username).boxed()
+    }
+
+    fn get_max_item_id(&self) -> BoxFuture<'_, Result<ItemId>> {
+        ArchiveClient::get_max_item_id(self).boxed()
+    }
+
+    fn get_stories(&self, list: StoryList) -> BoxFuture<'_, Result<Vec<ItemId>>> {
+        ArchiveClient::get_stories(self, list).boxed()
+    }
+
+    fn get_updates(&self) -> BoxFuture<'_, Result<Updates>> {
+        async {
+            Err(HnClientError::BackendError(
+                "updates not included in archive".into(),
+            ))
+        }
+        .boxed()
+    }
+}
+
+#[cfg(feature = "store-sqlite")]
+impl HnApi for crate::sqlite::StoredHnClient {
+    fn try_get_item(&self, id: ItemId) -> BoxFuture<'_, Result<Option<Item>>> {
+        crate::sqlite::StoredHnClient::try_get_item(self, id).boxed()
+    }
+
+    fn try_get_user<'a>(&'a self, username: &'a str) -> BoxFuture<'a, Result<Option<User>>> {
+        crate::sqlite::StoredHnClient::try_get_user(self, username).boxed()
+    }
+
+    fn get_max_item_id(&self) -> BoxFuture<'_, Result<ItemId>> {
+        crate::sqlite::StoredHnClient::get_max_item_id(self).boxed()
+    }
+
+    fn get_stories(&self, _list: StoryList) -> BoxFuture<'_, Result<Vec<ItemId>>> {
+        async { Err(HnClientError::BackendError("listings not stored".into())) }.boxed()
+    }
+
+    fn get_updates(&self) -> BoxFuture<'_, Result<Updates>> {
+        async { Err(HnClientError::BackendError("updates not stored".into())) }.boxed()
+    }
+}
+
+/// An [`HnApi`] that looks items and users up in a [`CacheStore`] first,
+/// and stores what it fetches from the inner implementation.
+///
+/// Items stop changing once they are old enough that they can no longer be
+/// voted on or replied to, so items older than two weeks are cached for a
+/// year, and newer items for one minute. Users are cached for five minutes.
+/// Missing items and users, listings, the newest item id and the updates are
+/// never cached. Entries use the same keys as the client's own cache, e.g.
+/// `item/8863`, so a store can be shared with clients built with
+/// [`HnClientBuilder::cache`](crate::nonblocking::HnClientBuilder::cache).
+///
+/// ```rust,no_run
+/// use hn_api::{
+///     api::{CachedHnClient, HnApi},
+///     cache::MemoryStore,
+///     nonblocking::HnClient,
+/// };
+///
+/// # async fn run() -> hn_api::Result<()> {
+/// let api = CachedHnClient::new(HnClient::init()?, MemoryStore::new());
+/// let story = api.get_item(8863.into()).await?;
+/// // Served from the store:
+/// let story = api.get_item(8863.into()).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct CachedHnClient<A, S> {
+    inner: A,
+    store: S,
+    settled_after: Duration,
+    settled_ttl: Duration,
+    recent_ttl: Duration,
+    users_ttl: Duration,
+}
+
+impl<A: HnApi, S: CacheStore> CachedHnClient<A, S> {
+    /// Wrap `inner` with a cache in `store`.
+    pub fn new(inner: A, store: S) -> Self {
+        Self {
+            inner,
+            store,
+            settled_after: Duration::from_secs(14 * 24 * 60 * 60),
+            settled_ttl: Duration::from_secs(365 * 24 * 60 * 60),
+            recent_ttl: Duration::from_secs(60),
+            users_ttl: Duration::from_secs(5 * 60),
+        }
+    }
+
+    /// Set the age after which items are considered immutable, and how long
+    /// they are cached then.
+    pub fn settled_items(mut self, after: Duration, ttl: Duration) -> Self {
+        self.settled_after = after;
+        self.settled_ttl = ttl;
+        self
+    }
+
+    /// Set how long items younger than the settled age are cached. Zero
+    /// disables caching them.
+    pub fn recent_items(mut self, ttl: Duration) -> Self {
+        self.recent_ttl = ttl;
+        self
+    }
+
+    /// Set how long users are cached. Zero disables caching them.
+    pub fn users(mut self, ttl: Duration) -> Self {
+        self.users_ttl = ttl;
+        self
+    }
+
+    /// Return the wrapped implementation.
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+
+    /// Return the cache store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Return how long an item is cached.
+    fn item_ttl(&self, item: &Item) -> Duration {
+        let age = Timestamp::now()
+            .as_secs()
+            .saturating_sub(item.time().as_secs());
+        if Duration::from_secs(age) >= self.settled_after {
+            self.settled_ttl
+        } else {
+            self.recent_ttl
+        }
+    }
+
+    async fn lookup<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let body = self.store.get(key).await?;
+        serde_json::from_slice(&body).ok()
+    }
+
+    async fn write_back<T: Serialize>(&self, key: &str, value: &T, ttl: Duration) {
+        if ttl.is_zero() {
+            return;
+        }
+        if let Ok(body) = serde_json::to_vec(value) {
+            self.store.put(key, Bytes::from(body), ttl).await;
+        }
+    }
+
+    async fn write_back_item(&self, item: &Item) {
+        let key = format!("item/{}", item.id());
+        self.write_back(&key, item, self.item_ttl(item)).await;
+    }
+}
+
+impl<A: HnApi, S: CacheStore> HnApi for CachedHnClient<A, S> {
+    fn try_get_item(&self, id: ItemId) -> BoxFuture<'_, Result<Option<Item>>> {
+        async move {
+            if let Some(item) = self.lookup(&format!("item/{}", id)).await {
+                return Ok(Some(item));
+            }
+            let item = self.inner.try_get_item(id).await?;
+            if let Some(item) = &item {
+                self.write_back_item(item).await;
+            }
+            Ok(item)
+        }
+        .boxed()
+    }
+
+    fn try_get_user<'a>(&'a self, username: &'a str) -> BoxFuture<'a, Result<Option<User>>> {
+        async move {
+            let key = format!("user/{}", username);
+            if let Some(user) = self.lookup(&key).await {
+                return Ok(Some(user));
+            }
+            let user = self.inner.try_get_user(username).await?;
+            if let Some(user) = &user {
+                self.write_back(&key, user, self.users_ttl).await;
+            }
+            Ok(user)
+        }
+        .boxed()
+    }
+
+    fn get_max_item_id(&self) -> BoxFuture<'_, Result<ItemId>> {
+        self.inner.get_max_item_id()
+    }
+
+    fn get_stories(&self, list: StoryList) -> BoxFuture<'_, Result<Vec<ItemId>>> {
+        self.inner.get_stories(list)
+    }
+
+    fn get_updates(&self) -> BoxFuture<'_, Result<Updates>> {
+        self.inner.get_updates()
+    }
+
+    fn get_items<'a>(&'a self, ids: &'a [ItemId]) -> BoxFuture<'a, Result<Vec<Item>>> {
+        async move {
+            let mut found: HashMap<ItemId, Item> = HashMap::new();
+            let mut missing = Vec::new();
+            for id in ids {
+                match self.lookup(&format!("item/{}", id)).await {
+                    Some(item) => {
+                        found.insert(*id, item);
+                    }
+                    None => missing.push(*id),
+                }
+            }
+            if !missing.is_empty() {
+                for item in self.inner.get_items(&missing).await? {
+                    self.write_back_item(&item).await;
+                    found.insert(item.id(), item);
+                }
+            }
+            Ok(ids.iter().filter_map(|id| found.remove(id)).collect())
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::Archive;
+
+    #[tokio::test]
+    async fn test_dyn_api() {
+        let mut archive = Archive::default();
+        archive.add_items(vec![serde_json::from_str(
+            r#"{"by": "pg", "id": 1, "time": 0, "title": "Y", "type": "story"}"#,
+        )
+        .unwrap()]);
+        let api: Box<dyn HnApi> = Box::new(ArchiveClient::from(archive));
+
+        assert_eq!(api.get_max_item_id().await.unwrap(), 1);
+        let items = api.get_items(&[ItemId(1), ItemId(2)]).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert!(api.get_item(ItemId(2)).await.unwrap_err().is_not_found());
+        assert!(api.get_user("pg").await.unwrap_err().is_not_found());
+    }
+
+    #[tokio::test]
+    async fn test_cached_client() {
+        let item = |id: u32, time: u64| -> Item {
+            serde_json::from_value(serde_json::json!({
+                "id": id, "score": 1, "time": time, "type": "story"
+            }))
+            .unwrap()
+        };
+        let mut archive = Archive::default();
+        archive.add_items(vec![item(1, 0), item(2, Timestamp::now().as_secs())]);
+        let store = std::sync::Arc::new(crate::cache::MemoryStore::new());
+        let api = CachedHnClient::new(ArchiveClient::from(archive), store.clone())
+            .recent_items(Duration::ZERO);
+
+        let items = api
+            .get_items(&[ItemId(2), ItemId(3), ItemId(1)])
+            .await
+            .unwrap();
+        let ids: Vec<_> = items.iter().map(Item::id).collect();
+        assert_eq!(ids, vec![ItemId(2), ItemId(1)]);
+        assert!(store.get("item/1").await.is_some());
+        assert!(store.get("item/2").await.is_none());
+
+        let empty = CachedHnClient::new(ArchiveClient::from(Archive::default()), store);
+        assert_eq!(empty.get_item(ItemId(1)).await.unwrap(), item(1, 0));
+        assert!(empty.get_item(ItemId(2)).await.unwrap_err().is_not_found());
+    }
+}
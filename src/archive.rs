@@ -0,0 +1,413 @@
+//! Offline snapshots of Hacker News data.
+//!
+//! [`HnClient::snapshot`] collects a slice of the data into an [`Archive`],
+//! which can be saved to a single JSON file or to a directory. An
+//! [`ArchiveClient`] later serves the same read methods as the API client
+//! from a loaded archive, without network access, e.g. for reproducible
+//! analysis.
+//!
+//! A directory archive contains `items.jsonl` and `users.jsonl` with one item
+//! or user per line, and `meta.json` with the snapshot time and listings.
+//!
+//! ```rust,no_run
+//! use hn_api::{archive::{Archive, ArchiveClient, SnapshotSpec}, nonblocking::HnClient};
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = HnClient::init().map_err(|e| format!("{:?}", e))?;
+//! let archive = client
+//!     .snapshot(&SnapshotSpec::top_stories(30))
+//!     .await
+//!     .map_err(|e| format!("{:?}", e))?;
+//! archive.save_file("top.json")?;
+//!
+//! let offline = ArchiveClient::open_file("top.json")?;
+//! let top = offline.get_top_stories().await.map_err(|e| format!("{:?}", e))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter},
+    ops::RangeInclusive,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    export,
+    nonblocking::HnClient,
+    tree::CommentTree,
+    types::{Item, ItemFilter, ItemId, StoryList, Timestamp, User, Username},
+    HnClientError::{self, *},
+    Result,
+};
+
+/// Selects the data to include in a snapshot.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapshotSpec {
+    /// The listings to include.
+    pub lists: Vec<StoryList>,
+    /// How many stories of each listing to include.
+    pub list_limit: usize,
+    /// Whether to include the full comment trees of the included stories.
+    pub comments: bool,
+    /// A range of item ids to include.
+    pub id_range: Option<RangeInclusive<u32>>,
+    /// Whether to include the authors of all included items.
+    pub authors: bool,
+}
+
+impl SnapshotSpec {
+    /// Include the first `count` top stories with their comment trees.
+    pub fn top_stories(count: usize) -> Self {
+        Self {
+            lists: vec![StoryList::Top],
+            list_limit: count,
+            comments: true,
+            ..Self::default()
+        }
+    }
+
+    /// Include all items with ids in `range`.
+    pub fn id_range(range: RangeInclusive<u32>) -> Self {
+        Self {
+            id_range: Some(range),
+            ..Self::default()
+        }
+    }
+}
+
+/// The data of a snapshot.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Archive {
+    /// When the snapshot was taken.
+    pub created: Timestamp,
+    /// The archived listings, with all of their ids.
+    pub lists: BTreeMap<StoryList, Vec<ItemId>>,
+    /// The archived items, keyed by id.
+    pub items: BTreeMap<ItemId, Item>,
+    /// The archived users, keyed by username.
+    pub users: BTreeMap<Username, User>,
+}
+
+/// The contents of `meta.json` in a directory archive.
+#[derive(Deserialize, Serialize)]
+struct ArchiveMeta {
+    created: Timestamp,
+    lists: BTreeMap<StoryList, Vec<ItemId>>,
+}
+
+impl Archive {
+    /// Add items to the archive, replacing items with the same id.
+    pub fn add_items(&mut self, items: impl IntoIterator<Item = Item>) {
+        self.items
+            .extend(items.into_iter().map(|item| (item.id(), item)));
+    }
+
+    /// Add users to the archive, replacing users with the same username.
+    pub fn add_users(&mut self, users: impl IntoIterator<Item = User>) {
+        self.users
+            .extend(users.into_iter().map(|user| (user.id.clone(), user)));
+    }
+
+    /// Save the archive as a single JSON file.
+    pub fn save_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(writer, self)?;
+        Ok(())
+    }
+
+    /// Load an archive saved with [`Archive::save_file`].
+    pub fn load_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Save the archive into a directory, which is created if necessary.
+    pub fn save_dir(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        fs::create_dir_all(path)?;
+        let meta = ArchiveMeta {
+            created: self.created,
+            lists: self.lists.clone(),
+        };
+        serde_json::to_writer(BufWriter::new(File::create(path.join("meta.json"))?), &meta)?;
+        let items: Vec<_> = self.items.values().cloned().collect();
+        export::write_items_jsonl(
+            BufWriter::new(File::create(path.join("items.jsonl"))?),
+            &items,
+        )?;
+        let users: Vec<_> = self.users.values().cloned().collect();
+        export::write_users_jsonl(
+            BufWriter::new(File::create(path.join("users.jsonl"))?),
+            &users,
+        )
+    }
+
+    /// Load an archive saved with [`Archive::save_dir`].
+    pub fn load_dir(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let meta: ArchiveMeta =
+            serde_json::from_reader(BufReader::new(File::open(path.join("meta.json"))?))?;
+        let mut archive = Archive {
+            created: meta.created,
+            lists: meta.lists,
+            ..Archive::default()
+        };
+        archive.add_items(read_jsonl(&path.join("items.jsonl"))?);
+        archive.add_users(read_jsonl(&path.join("users.jsonl"))?);
+        Ok(archive)
+    }
+}
+
+fn read_jsonl<T: serde::de::DeserializeOwned>(path: &Path) -> io::Result<Vec<T>> {
+    BufReader::new(File::open(path)?)
+        .lines()
+        .filter(|line| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+        .map(|line| Ok(serde_json::from_str(&line?)?))
+        .collect()
+}
+
+impl HnClient {
+    /// Collect the data selected by `spec` into an archive.
+    ///
+    /// Missing items are skipped.
+    /// Fails if any of the request failed.
+    pub async fn snapshot(&self, spec: &SnapshotSpec) -> Result<Archive> {
+        let mut archive = Archive {
+            created: Timestamp::now(),
+            ..Archive::default()
+        };
+
+        for list in &spec.lists {
+            let ids = self.get_stories(*list).await?;
+            let included = &ids[..spec.list_limit.min(ids.len())];
+            if spec.comments {
+                for id in included {
+                    if let Some(item) = self.try_get_item(*id).await? {
+                        let tree = self.get_comment_tree(item.id()).await?;
+                        archive.add_items(tree.iter().cloned());
+                    }
+                }
+            } else {
+                archive.add_items(self.get_items_filtered(included, ItemFilter::ALL).await?);
+            }
+            archive.lists.insert(*list, ids);
+        }
+
+        if let Some(range) = &spec.id_range {
+            let ids: Vec<ItemId> = range.clone().map(ItemId).collect();
+            let mut chunks = Box::pin(self.try_get_items_chunked(&ids));
+            while let Some(chunk) = futures::StreamExt::next(&mut chunks).await {
+                archive.add_items(chunk?.into_iter().flatten());
+            }
+        }
+
+        if spec.authors {
+            let authors: Vec<&str> = archive.items.values().filter_map(Item::author).collect();
+            let users = self.get_users(&authors).await?;
+            archive.add_users(users.into_values());
+        }
+        Ok(archive)
+    }
+}
+
+/// A client serving reads from an archive instead of the API.
+///
+/// The methods mirror those of [`HnClient`]. Listings and items that are not
+/// part of the archive are reported as missing.
+pub struct ArchiveClient {
+    archive: Archive,
+}
+
+impl From<Archive> for ArchiveClient {
+    fn from(archive: Archive) -> Self {
+        Self { archive }
+    }
+}
+
+impl ArchiveClient {
+    /// Open an archive saved with [`Archive::save_file`].
+    pub fn open_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Archive::load_file(path).map(Self::from)
+    }
+
+    /// Open an archive saved with [`Archive::save_dir`].
+    pub fn open_dir(path: impl AsRef<Path>) -> io::Result<Self> {
+        Archive::load_dir(path).map(Self::from)
+    }
+
+    /// Return the underlying archive.
+    pub fn archive(&self) -> &Archive {
+        &self.archive
+    }
+
+    /// Return the item with the specified id.
+    ///
+    /// May return error if item id is not archived.
+    pub async fn get_item(&self, id: impl Into<ItemId>) -> Result<Item> {
+        let id = id.into();
+        self.try_get_item(id).await?.ok_or(ItemNotFoundError(id))
+    }
+
+    /// Return the item with the specified id.
+    ///
+    /// May return `None` if item id is not archived.
+    pub async fn try_get_item(&self, id: impl Into<ItemId>) -> Result<Option<Item>> {
+        Ok(self.archive.items.get(&id.into()).cloned())
+    }
+
+    /// Return the items with the specified ids.
+    ///
+    /// May return error if an item id is not archived.
+    pub async fn get_items(&self, items: &[impl Into<ItemId> + Copy]) -> Result<Vec<Item>> {
+        items
+            .iter()
+            .map(|id| {
+                let id = (*id).into();
+                self.archive
+                    .items
+                    .get(&id)
+                    .cloned()
+                    .ok_or(ItemNotFoundError(id))
+            })
+            .collect()
+    }
+
+    /// Return the user with the specified username.
+    ///
+    /// May return error if username is not archived.
+    pub async fn get_user(&self, username: &str) -> Result<User> {
+        self.try_get_user(username)
+            .await?
+            .ok_or_else(|| UserNotFoundError(username.to_string()))
+    }
+
+    /// Return the user with the specified username.
+    ///
+    /// May return `None` if username is not archived.
+    pub async fn try_get_user(&self, username: &str) -> Result<Option<User>> {
+        Ok(self.archive.users.get(username).cloned())
+    }
+
+    /// Return the item with the specified id together with all of its
+    /// archived comments, recursively.
+    pub async fn get_comment_tree(&self, id: impl Into<ItemId>) -> Result<CommentTree> {
+        fn build(archive: &Archive, item: &Item) -> CommentTree {
+            CommentTree {
+                item: item.clone(),
+                replies: item
+                    .kids()
+                    .iter()
+                    .filter_map(|kid| archive.items.get(kid))
+                    .map(|reply| build(archive, reply))
+                    .collect(),
+            }
+        }
+        let id = id.into();
+        let item = self.archive.items.get(&id).ok_or(ItemNotFoundError(id))?;
+        Ok(build(&self.archive, item))
+    }
+
+    /// Return the id of the newest archived item.
+    pub async fn get_max_item_id(&self) -> Result<ItemId> {
+        self.archive
+            .items
+            .keys()
+            .next_back()
+            .copied()
+            .ok_or_else(|| not_archived("items"))
+    }
+
+    /// Return a list of top story item ids.
+    pub async fn get_top_stories(&self) -> Result<Vec<ItemId>> {
+        self.get_stories(StoryList::Top).await
+    }
+
+    /// Return a list of new story item ids.
+    pub async fn get_new_stories(&self) -> Result<Vec<ItemId>> {
+        self.get_stories(StoryList::New).await
+    }
+
+    /// Return a list of best story item ids.
+    pub async fn get_best_stories(&self) -> Result<Vec<ItemId>> {
+        self.get_stories(StoryList::Best).await
+    }
+
+    /// Return up to 200 latest Ask HN story item ids.
+    pub async fn get_ask_stories(&self) -> Result<Vec<ItemId>> {
+        self.get_stories(StoryList::Ask).await
+    }
+
+    /// Return up to 200 latest Show HN story item ids.
+    pub async fn get_show_stories(&self) -> Result<Vec<ItemId>> {
+        self.get_stories(StoryList::Show).await
+    }
+
+    /// Return up to 200 latest Job story item ids.
+    pub async fn get_job_stories(&self) -> Result<Vec<ItemId>> {
+        self.get_stories(StoryList::Job).await
+    }
+
+    /// Return the item ids of the specified story listing, in ranked order.
+    pub async fn get_stories(&self, list: StoryList) -> Result<Vec<ItemId>> {
+        self.archive
+            .lists
+            .get(&list)
+            .cloned()
+            .ok_or_else(|| not_archived(&format!("{:?} stories", list)))
+    }
+}
+
+fn not_archived(what: &str) -> HnClientError {
+    BackendError(format!("{} not included in archive", what))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(id: u32, parent: u32, kids: &[u32]) -> Item {
+        serde_json::from_str(&format!(
+            r#"{{"by": "a", "id": {}, "kids": {:?}, "parent": {}, "text": "", "time": 0, "type": "comment"}}"#,
+            id, kids, parent
+        ))
+        .unwrap()
+    }
+
+    fn archive() -> Archive {
+        let mut archive = Archive::default();
+        archive.add_items(vec![
+            comment(1, 0, &[2, 3]),
+            comment(2, 1, &[4]),
+            comment(4, 2, &[]),
+        ]);
+        archive.lists.insert(StoryList::Top, vec![ItemId(1)]);
+        archive
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("hn_api_archive_{}", std::process::id()));
+        archive().save_dir(&dir).unwrap();
+        assert_eq!(Archive::load_dir(&dir).unwrap(), archive());
+        let file = dir.join("archive.json");
+        archive().save_file(&file).unwrap();
+        assert_eq!(Archive::load_file(&file).unwrap(), archive());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_archive_client() {
+        let client = ArchiveClient::from(archive());
+        let tree = client.get_comment_tree(1).await.unwrap();
+        assert_eq!(tree.iter().map(Item::id).collect::<Vec<_>>(), vec![1, 2, 4]);
+        assert_eq!(client.get_top_stories().await.unwrap(), vec![1]);
+        assert!(client.get_new_stories().await.is_err());
+        assert_eq!(client.get_max_item_id().await.unwrap(), 4);
+        assert!(client.try_get_item(3).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,267 @@
+//! Authenticated actions on the Hacker News website.
+//!
+//! The API is read-only. This module logs into the website itself, using its
+//! HTML forms and the session cookie, to perform actions on behalf of a user.
+//! It is independent of the API clients and requires the `auth` feature.
+//!
+//! ```rust,no_run
+//! use hn_api::auth::HnSession;
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let session = HnSession::login("username", "password").await?;
+//! session.upvote(8863).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{sync::Arc, time::Duration};
+
+use reqwest::{cookie::CookieStore, cookie::Jar, Client, Url};
+
+use super::{html, permalink::SITE_BASE_URL, types::ItemId, HnClientError, Result};
+
+/// The content of a story submission.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoryContent {
+    /// A link to an external page.
+    Url(String),
+    /// A text post, e.g. for Ask HN.
+    Text(String),
+}
+
+/// A logged in website session.
+pub struct HnSession {
+    client: Client,
+    cookies: Arc<Jar>,
+    username: String,
+}
+
+impl HnSession {
+    /// Log into the website with the specified credentials.
+    ///
+    /// Fails with `AuthError` if the credentials are rejected.
+    pub async fn login(username: &str, password: &str) -> Result<Self> {
+        let cookies = Arc::new(Jar::default());
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .cookie_provider(Arc::clone(&cookies));
+        #[cfg(feature = "rustls-tls")]
+        let client = client.use_rustls_tls();
+        let client = client.build()?;
+        client
+            .post(format!("{}/login", SITE_BASE_URL))
+            .form(&[("acct", username), ("pw", password), ("goto", "news")])
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let session = Self {
+            client,
+            cookies,
+            username: username.to_string(),
+        };
+        if !session.is_logged_in() {
+            return Err(HnClientError::AuthError(format!(
+                "login as {} was rejected",
+                username
+            )));
+        }
+        Ok(session)
+    }
+
+    /// Return the username of the logged in user.
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Return whether the session cookie is present.
+    pub fn is_logged_in(&self) -> bool {
+        let url = Url::parse(SITE_BASE_URL).expect("valid base url");
+        self.cookies
+            .cookies(&url)
+            .and_then(|header| header.to_str().map(|h| h.contains("user=")).ok())
+            .unwrap_or(false)
+    }
+
+    /// Upvote the item with the specified id.
+    pub async fn upvote(&self, id: impl Into<ItemId>) -> Result<()> {
+        let id = id.into();
+        self.item_action(id, &format!("vote?id={}&how=up", id))
+            .await
+    }
+
+    /// Remove the vote from the item with the specified id.
+    pub async fn unvote(&self, id: impl Into<ItemId>) -> Result<()> {
+        let id = id.into();
+        self.item_action(id, &format!("vote?id={}&how=un", id))
+            .await
+    }
+
+    /// Add the item with the specified id to the user's favorites.
+    pub async fn favorite(&self, id: impl Into<ItemId>) -> Result<()> {
+        let id = id.into();
+        self.item_action(id, &format!("fave?id={}", id)).await
+    }
+
+    /// Flag the item with the specified id.
+    pub async fn flag(&self, id: impl Into<ItemId>) -> Result<()> {
+        let id = id.into();
+        self.item_action(id, &format!("flag?id={}", id)).await
+    }
+
+    /// Return the ids of the items the user hid, on the specified page of the
+    /// hidden items, starting at 1.
+    pub async fn get_hidden(&self, page: u32) -> Result<Vec<ItemId>> {
+        let page = self.get_page(&format!("hidden?p={}", page.max(1))).await?;
+        Ok(html::parse_listing_ids(&page))
+    }
+
+    /// Submit a story.
+    ///
+    /// Fails with `AuthError` if the submission form is not available.
+    pub async fn submit_story(&self, title: &str, content: StoryContent) -> Result<()> {
+        let page = self.get_page("submit").await?;
+        let fnid = form_token(&page, "fnid", "submission")?;
+        let fnop = find_input_value(&page, "fnop").unwrap_or_else(|| "submit-page".to_string());
+        let (url, text) = match &content {
+            StoryContent::Url(url) => (url.as_str(), ""),
+            StoryContent::Text(text) => ("", text.as_str()),
+        };
+        self.post_form(
+            "r",
+            &[
+                ("fnid", &fnid),
+                ("fnop", &fnop),
+                ("title", title),
+                ("url", url),
+                ("text", text),
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Reply to the item with the specified id.
+    ///
+    /// Fails with `AuthError` if the reply form is not available, e.g.
+    /// because the thread is locked.
+    pub async fn reply(&self, parent_id: impl Into<ItemId>, text: &str) -> Result<()> {
+        let parent_id = parent_id.into();
+        let page = self.get_page(&format!("reply?id={}", parent_id)).await?;
+        let hmac = form_token(&page, "hmac", "reply")?;
+        let parent = parent_id.to_string();
+        let goto = format!("item?id={}", parent_id);
+        self.post_form(
+            "comment",
+            &[
+                ("parent", &parent),
+                ("goto", &goto),
+                ("hmac", &hmac),
+                ("text", text),
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Return the HTML of a website page, e.g. `item?id=8863`.
+    pub(crate) async fn get_page(&self, path: &str) -> Result<String> {
+        Ok(self
+            .client
+            .get(format!("{}/{}", SITE_BASE_URL, path))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?)
+    }
+
+    /// Submit a form of the website.
+    async fn post_form(&self, path: &str, form: &[(&str, &str)]) -> Result<String> {
+        Ok(self
+            .client
+            .post(format!("{}/{}", SITE_BASE_URL, path))
+            .form(form)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?)
+    }
+
+    /// Follow the link starting with `action` on the item's page, which
+    /// carries the per-item auth token.
+    async fn item_action(&self, id: ItemId, action: &str) -> Result<()> {
+        let page = self.get_page(&format!("item?id={}", id)).await?;
+        let auth = find_auth_token(&page, action).ok_or_else(|| {
+            HnClientError::AuthError(format!("no {} link on item {}", action, id))
+        })?;
+        self.get_page(&format!("{}&auth={}", action, auth)).await?;
+        Ok(())
+    }
+}
+
+/// Return the value of the hidden form input `name`, or an `AuthError`
+/// naming the form.
+fn form_token(html: &str, name: &str, form: &str) -> Result<String> {
+    find_input_value(html, name)
+        .ok_or_else(|| HnClientError::AuthError(format!("no {} token in {} form", name, form)))
+}
+
+/// Find the value of the form input with the specified name.
+fn find_input_value(html: &str, name: &str) -> Option<String> {
+    let start = html
+        .find(&format!("name=\"{}\"", name))
+        .or_else(|| html.find(&format!("name='{}'", name)))?;
+    let tag = &html[start..];
+    let tag = &tag[..tag.find('>')?];
+    let value = &tag[tag.find("value=")? + "value=".len()..];
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = &value[1..];
+    Some(value[..value.find(quote)?].replace("&amp;", "&"))
+}
+
+/// Find the `auth` parameter of the first link starting with `action`.
+fn find_auth_token(html: &str, action: &str) -> Option<String> {
+    let html = html.replace("&amp;", "&");
+    let start = html
+        .find(&format!("href='{}", action))
+        .or_else(|| html.find(&format!("href=\"{}", action)))?;
+    let link = &html[start + "href='".len()..];
+    let link = &link[..link.find(['\'', '"'])?];
+    link.split(['?', '&'])
+        .find_map(|param| param.strip_prefix("auth="))
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_auth_token() {
+        let html = "<a id='up_8863' href='vote?id=8863&amp;how=up&amp;auth=abc123&amp;goto=item%3Fid%3D8863'>\
+                    <a href=\"fave?id=8863&amp;auth=def456\">favorite</a>";
+        assert_eq!(
+            find_auth_token(html, "vote?id=8863&how=up").as_deref(),
+            Some("abc123")
+        );
+        assert_eq!(
+            find_auth_token(html, "fave?id=8863").as_deref(),
+            Some("def456")
+        );
+        assert_eq!(find_auth_token(html, "flag?id=8863"), None);
+    }
+
+    #[test]
+    fn test_find_input_value() {
+        let html = r#"<form action="r"><input type="hidden" name="fnid" value="Xy7&amp;z">
+                      <input type='hidden' name='fnop' value='submit-page'>"#;
+        assert_eq!(find_input_value(html, "fnid").as_deref(), Some("Xy7&z"));
+        assert_eq!(
+            find_input_value(html, "fnop").as_deref(),
+            Some("submit-page")
+        );
+        assert_eq!(find_input_value(html, "hmac"), None);
+    }
+}
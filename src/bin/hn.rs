@@ -0,0 +1,250 @@
+//! A command line client for Hacker News.
+//!
+//! Requires the `cli` feature:
+//!
+//! ```shell
+//! cargo install hn_api --features cli
+//! hn top -n 20
+//! hn item 8863 --comments
+//! hn user pg --json
+//! hn watch new
+//! ```
+
+use std::{collections::HashSet, env, process, time::Duration};
+
+use hn_api::{nonblocking::HnClient, CommentTree, Item, ItemId, StoryList};
+
+const USAGE: &str = "\
+Usage:
+    hn (top|new|best|ask|show|jobs) [-n COUNT] [--json]
+    hn item ID [--comments] [--json]
+    hn user USERNAME [--json]
+    hn watch (top|new|best|ask|show|jobs) [--interval SECONDS] [--json]
+
+Options:
+    -n COUNT              Number of stories to show [default: 10]
+    --comments            Include all comments of the item
+    --interval SECONDS    Seconds between polls [default: 60]
+    --json                Print JSON instead of plain text";
+
+enum Command {
+    List(StoryList, usize),
+    Item(ItemId, bool),
+    User(String),
+    Watch(StoryList, Duration),
+}
+
+struct Args {
+    command: Command,
+    json: bool,
+}
+
+fn parse_list(name: &str) -> Option<StoryList> {
+    match name {
+        "top" => Some(StoryList::Top),
+        "new" => Some(StoryList::New),
+        "best" => Some(StoryList::Best),
+        "ask" => Some(StoryList::Ask),
+        "show" => Some(StoryList::Show),
+        "jobs" => Some(StoryList::Job),
+        _ => None,
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut positional = Vec::new();
+    let mut count: u64 = 10;
+    let mut interval: u64 = 60;
+    let mut comments = false;
+    let mut json = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| format!("{} requires a number", name))
+        };
+        match arg.as_str() {
+            "-n" => count = value("-n")?,
+            "--interval" => interval = value("--interval")?,
+            "--comments" => comments = true,
+            "--json" => json = true,
+            "-h" | "--help" => return Err(String::new()),
+            flag if flag.starts_with('-') => return Err(format!("unknown option {}", flag)),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    let command = match positional.as_slice() {
+        ["item", id] => Command::Item(id.parse().map_err(|_| "invalid item id")?, comments),
+        ["user", username] => Command::User(username.to_string()),
+        ["watch", list] => Command::Watch(
+            parse_list(list).ok_or("unknown listing")?,
+            Duration::from_secs(interval),
+        ),
+        [list] => Command::List(parse_list(list).ok_or("unknown command")?, count as usize),
+        _ => return Err(String::new()),
+    };
+    Ok(Args { command, json })
+}
+
+/// Convert item HTML to plain text.
+fn plain_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.replace("<p>", "\n\n").chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&#x27;", "'")
+        .replace("&#x2F;", "/")
+        .replace("&quot;", "\"")
+        .replace("&gt;", ">")
+        .replace("&lt;", "<")
+        .replace("&amp;", "&")
+}
+
+fn print_story(rank: Option<usize>, item: &Item) {
+    if let Some(rank) = rank {
+        print!("{:>3}. ", rank);
+    }
+    println!("{} [{}]", item.title().unwrap_or("?"), item.id());
+    let details = [
+        item.score().map(|score| format!("{} points", score)),
+        item.author().map(|author| format!("by {}", author)),
+        item.descendants()
+            .map(|count| format!("{} comments", count)),
+        item.url().map(str::to_string),
+    ];
+    let details: Vec<_> = details.iter().flatten().cloned().collect();
+    if !details.is_empty() {
+        println!("     {}", details.join(" | "));
+    }
+}
+
+fn print_tree(tree: &CommentTree, depth: usize) {
+    let indent = "    ".repeat(depth);
+    match &tree.item {
+        Item::Comment(_) => {
+            println!(
+                "{}{} [{}]",
+                indent,
+                tree.item.author().unwrap_or("[deleted]"),
+                tree.item.id()
+            );
+            for line in plain_text(tree.item.text().unwrap_or_default()).lines() {
+                println!("{}  {}", indent, line);
+            }
+            println!();
+        }
+        item => {
+            print_story(None, item);
+            if let Some(text) = item.text() {
+                println!("\n{}", plain_text(text));
+            }
+            println!();
+        }
+    }
+    for reply in &tree.replies {
+        print_tree(reply, depth + usize::from(tree.item.parent().is_some()));
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).expect("serializable value")
+}
+
+async fn run(args: Args) -> hn_api::Result<()> {
+    let api = HnClient::init()?;
+    match args.command {
+        Command::List(list, count) => {
+            let ids = api.get_stories(list).await?;
+            let items = api.get_items(&ids[..count.min(ids.len())]).await?;
+            if args.json {
+                println!("{}", to_json(&items));
+            } else {
+                for (rank, item) in items.iter().enumerate() {
+                    print_story(Some(rank + 1), item);
+                }
+            }
+        }
+        Command::Item(id, false) => {
+            let item = api.get_item(id).await?;
+            if args.json {
+                println!("{}", to_json(&item));
+            } else {
+                print_story(None, &item);
+                if let Some(text) = item.text() {
+                    println!("\n{}", plain_text(text));
+                }
+            }
+        }
+        Command::Item(id, true) => {
+            let tree = api.get_comment_tree(id).await?;
+            if args.json {
+                println!("{}", to_json(&tree));
+            } else {
+                print_tree(&tree, 0);
+            }
+        }
+        Command::User(username) => {
+            let user = api.get_user(&username).await?;
+            if args.json {
+                println!("{}", to_json(&user));
+            } else {
+                println!("{}", user.id);
+                println!("karma:     {}", user.karma);
+                println!("created:   {}", user.created.as_secs());
+                println!("submitted: {}", user.submitted.len());
+                if let Some(about) = &user.about {
+                    println!("\n{}", plain_text(about));
+                }
+            }
+        }
+        Command::Watch(list, interval) => {
+            // Only report stories that appear after the watch started.
+            let mut seen: HashSet<ItemId> = api.get_stories(list).await?.into_iter().collect();
+            eprintln!("Watching for new stories, press Ctrl-C to stop.");
+            loop {
+                tokio::time::sleep(interval).await;
+                let ids = api.get_stories(list).await?;
+                let unseen: Vec<ItemId> = ids.into_iter().filter(|id| seen.insert(*id)).collect();
+                for item in api.get_items_filtered(&unseen, Default::default()).await? {
+                    if args.json {
+                        println!(
+                            "{}",
+                            serde_json::to_string(&item).expect("serializable value")
+                        );
+                    } else {
+                        print_story(None, &item);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args = match parse_args(&args) {
+        Ok(args) => args,
+        Err(error) => {
+            if !error.is_empty() {
+                eprintln!("error: {}\n", error);
+            }
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(error) = run(args).await {
+        eprintln!("error: {:?}", error);
+        process::exit(1);
+    }
+}
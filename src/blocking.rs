@@ -1,6 +1,11 @@
 //! A simple synchronous Hacker News API (v0) client library based on reqwest
 //! and serde.
 //!
+//! With the `blocking-lite` feature instead of `blocking`, the client is
+//! based on ureq, which does not depend on Tokio and builds much faster,
+//! e.g. for command line tools. If both features are enabled, reqwest is
+//! used.
+//!
 //! The library currently implements no caching. It simply exposes endpoints as
 //! methods.
 //!
@@ -12,7 +17,7 @@
 //!
 //! ## Usage
 //!
-//! ```rust
+//! ```rust,no_run
 //! use hn_api::blocking::HnClient;
 //!
 //! // Initialize HTTP client
@@ -32,119 +37,643 @@
 
 #![deny(missing_docs)]
 
-use std::time::Duration;
+use std::{
+    io::Read,
+    time::{Duration, Instant},
+};
+
+use serde::de::DeserializeOwned;
 
-use super::{types, HnClientError, Result};
+use super::{types, Endpoint, HnClientError, RequestError, Result};
 
 static API_BASE_URL: &str = "https://hacker-news.firebaseio.com/v0";
 
 /// The API client.
+///
+/// The client is `Send + Sync`, and cloning it is cheap: clones share the
+/// connection pool.
+#[derive(Debug, Clone)]
 pub struct HnClient {
+    #[cfg(feature = "blocking")]
     client: reqwest::blocking::Client,
+    /// The overall timeout, checked between reads of the body.
+    timeout: Option<Duration>,
+    #[cfg(not(feature = "blocking"))]
+    agent: ureq::Agent,
+    default_headers: Vec<(String, String)>,
+    max_response_size: u64,
+    debug_http: bool,
+}
+
+/// A builder to configure an [`HnClient`].
+#[derive(Debug, Clone)]
+pub struct HnClientBuilder {
+    timeout: Duration,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    default_headers: Vec<(String, String)>,
+    invalid_header: Option<String>,
+    max_response_size: u64,
+    debug_http: bool,
+}
+
+impl Default for HnClientBuilder {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            connect_timeout: None,
+            read_timeout: None,
+            default_headers: Vec::new(),
+            invalid_header: None,
+            max_response_size: super::DEFAULT_MAX_RESPONSE_SIZE,
+            debug_http: false,
+        }
+    }
+}
+
+impl HnClientBuilder {
+    /// Set the overall timeout for a single request, from connecting to
+    /// reading the last byte of the response. Defaults to 10 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the timeout for establishing a connection, including the DNS
+    /// lookup and TLS handshake. Only limited by the overall timeout by
+    /// default.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the longest wait for the next read from the connection. Only
+    /// limited by the overall timeout by default.
+    ///
+    /// It also limits the wait for the response headers, and the overall
+    /// timeout is then only checked between reads.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Add a header to every request, e.g. `X-App-Name` or a tracing
+    /// propagation header. Can override the `User-Agent`.
+    ///
+    /// An invalid name or value makes [`HnClientBuilder::build`] fail.
+    pub fn default_header(mut self, name: &str, value: &str) -> Self {
+        if is_valid_header(name, value) {
+            self.default_headers
+                .push((name.to_string(), value.to_string()));
+        } else {
+            self.invalid_header = Some(name.to_string());
+        }
+        self
+    }
+
+    /// Fail with `ResponseTooLarge` instead of reading a response body
+    /// larger than `bytes`. Defaults to 16 MiB.
+    pub fn max_response_size(mut self, bytes: u64) -> Self {
+        self.max_response_size = bytes;
+        self
+    }
+
+    /// Log every exchange with the server at debug level with the `log`
+    /// crate, target `hn_api::http`: URL, latency and the start of the body
+    /// or the error.
+    ///
+    /// Also enabled by setting the `HN_API_DEBUG_HTTP` environment variable
+    /// to anything but `0`. Disabled by default.
+    pub fn debug_http(mut self, enabled: bool) -> Self {
+        self.debug_http = enabled;
+        self
+    }
+
+    fn check_headers(&self) -> Result<()> {
+        match &self.invalid_header {
+            Some(name) => Err(HnClientError::BackendError(format!(
+                "invalid default header {}",
+                name
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Create the configured `HnClient` instance.
+    #[cfg(feature = "blocking")]
+    pub fn build(self) -> Result<HnClient> {
+        self.check_headers()?;
+        // reqwest applies this to every wait: for the headers and for each
+        // read of the body.
+        let wait = self
+            .read_timeout
+            .map_or(self.timeout, |read| read.min(self.timeout));
+        let mut client = reqwest::blocking::Client::builder().timeout(wait);
+        if let Some(timeout) = self.connect_timeout {
+            client = client.connect_timeout(timeout);
+        }
+        #[cfg(feature = "rustls-tls")]
+        let client = client.use_rustls_tls();
+        let client = client.build()?;
+        Ok(HnClient {
+            client,
+            timeout: Some(self.timeout),
+            default_headers: self.default_headers,
+            max_response_size: self.max_response_size,
+            debug_http: self.debug_http || super::debug_http_from_env(),
+        })
+    }
+
+    /// Create the configured `HnClient` instance.
+    #[cfg(not(feature = "blocking"))]
+    pub fn build(self) -> Result<HnClient> {
+        self.check_headers()?;
+        let mut agent = match self.read_timeout {
+            // ureq ignores the read timeout if there is an overall timeout.
+            Some(read) => ureq::AgentBuilder::new()
+                .timeout_read(read.min(self.timeout))
+                .timeout_write(self.timeout),
+            None => ureq::AgentBuilder::new().timeout(self.timeout),
+        };
+        if let Some(timeout) = self.connect_timeout {
+            agent = agent.timeout_connect(timeout);
+        }
+        Ok(HnClient {
+            agent: agent.build(),
+            timeout: Some(self.timeout),
+            default_headers: self.default_headers,
+            max_response_size: self.max_response_size,
+            debug_http: self.debug_http || super::debug_http_from_env(),
+        })
+    }
 }
 
 impl HnClient {
     /// Create a new `HnClient` instance.
     pub fn init() -> Result<Self> {
-        let client = reqwest::blocking::Client::builder()
-            .timeout(Duration::from_secs(10))
-            .build()?;
-        Ok(Self { client })
+        Self::builder().build()
+    }
+
+    /// Return a builder to configure a new `HnClient` instance.
+    pub fn builder() -> HnClientBuilder {
+        HnClientBuilder::default()
+    }
+
+    /// Create a new `HnClient` instance sharing an existing reqwest client,
+    /// including its connection pool, proxy and TLS settings.
+    #[cfg(feature = "blocking")]
+    pub fn init_with_client(client: reqwest::blocking::Client) -> Self {
+        Self {
+            client,
+            timeout: None,
+            default_headers: Vec::new(),
+            max_response_size: super::DEFAULT_MAX_RESPONSE_SIZE,
+            debug_http: super::debug_http_from_env(),
+        }
+    }
+
+    /// Create a new `HnClient` instance sharing an existing ureq agent,
+    /// including its connection pool, proxy and TLS settings.
+    #[cfg(not(feature = "blocking"))]
+    pub fn init_with_agent(agent: ureq::Agent) -> Self {
+        Self {
+            agent,
+            timeout: None,
+            default_headers: Vec::new(),
+            max_response_size: super::DEFAULT_MAX_RESPONSE_SIZE,
+            debug_http: super::debug_http_from_env(),
+        }
+    }
+
+    /// Fetch `url` and deserialize the JSON response body.
+    fn fetch_json<T: DeserializeOwned>(&self, url: String) -> Result<T> {
+        let body = self.fetch_bytes(url.clone())?;
+        super::decode_json(&url, None, &body)
+    }
+
+    /// Fetch `url` and return the response body, failing on error statuses.
+    fn fetch_bytes(&self, url: String) -> Result<bytes::Bytes> {
+        if !self.debug_http {
+            return self.send(url).map(|(_, body)| body);
+        }
+        let sent = Instant::now();
+        let result = self.send(url.clone());
+        match &result {
+            Ok((status, body)) => super::log_response(&url, *status, sent.elapsed(), Some(body)),
+            Err(err) => super::log_failure(&url, sent.elapsed(), err),
+        }
+        result.map(|(_, body)| body)
+    }
+
+    /// Send the request for `url` and return the status and body of the
+    /// response, see [`HnClient::fetch_bytes`].
+    #[cfg(feature = "blocking")]
+    fn send(&self, url: String) -> Result<(u16, bytes::Bytes)> {
+        let mut request = self.client.get(&url);
+        for (name, value) in &self.default_headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        let sent = Instant::now();
+        let response = request.send()?;
+        let status = response.status().as_u16();
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let deadline = self.timeout.map(|timeout| sent + timeout);
+        let body = self.read_body(&url, response.content_length(), response, deadline)?;
+        if !(200..300).contains(&status) {
+            let error = RequestError::status(&url, status, &body);
+            return Err(HnClientError::HttpError(Box::new(error)));
+        }
+        super::check_json(content_type.as_deref())?;
+        Ok((status, body))
+    }
+
+    /// Send the request for `url` and return the status and body of the
+    /// response, see [`HnClient::fetch_bytes`].
+    #[cfg(not(feature = "blocking"))]
+    fn send(&self, url: String) -> Result<(u16, bytes::Bytes)> {
+        let mut request = self.agent.get(&url);
+        for (name, value) in &self.default_headers {
+            request = request.set(name, value);
+        }
+        let sent = Instant::now();
+        let response = request.call().map_err(|err| ureq_error(&url, err))?;
+        let status = response.status();
+        super::check_json(response.header("Content-Type"))?;
+        let length = response
+            .header("Content-Length")
+            .and_then(|length| length.parse().ok());
+        let deadline = self.timeout.map(|timeout| sent + timeout);
+        let body = self.read_body(&url, length, response.into_reader(), deadline)?;
+        Ok((status, body))
+    }
+
+    /// Read a response body of the declared `length`, failing with
+    /// `ResponseTooLarge` if it exceeds the size limit, and with
+    /// `TimeoutError` if it is still being read after `deadline`.
+    fn read_body(
+        &self,
+        url: &str,
+        length: Option<u64>,
+        mut body: impl Read,
+        deadline: Option<Instant>,
+    ) -> Result<bytes::Bytes> {
+        let limit = self.max_response_size;
+        if length.is_some_and(|length| length > limit) {
+            return Err(HnClientError::ResponseTooLarge(limit));
+        }
+        let mut buffer = Vec::new();
+        let mut chunk = [0; 8192];
+        loop {
+            let read = body
+                .read(&mut chunk)
+                .map_err(|err| transport_error(url, err))?;
+            if read == 0 {
+                return Ok(buffer.into());
+            }
+            if (buffer.len() + read) as u64 > limit {
+                return Err(HnClientError::ResponseTooLarge(limit));
+            }
+            if deadline.is_some_and(|deadline| Instant::now() > deadline) {
+                return Err(HnClientError::TimeoutError);
+            }
+            buffer.extend_from_slice(&chunk[..read]);
+        }
+    }
+
+    /// Return the JSON response of any API endpoint, deserialized into `T`,
+    /// e.g. to call endpoints this crate does not support yet.
+    ///
+    /// The `path` is relative to the v0 API, e.g. `item/8863` or
+    /// `topstories.json`, `.json` is appended if it is missing.
+    pub fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.fetch_json(super::api_url(API_BASE_URL, path))
+    }
+
+    /// Return the raw response body of any API endpoint, see
+    /// [`HnClient::get_json`].
+    pub fn get_bytes(&self, path: &str) -> Result<bytes::Bytes> {
+        self.fetch_bytes(super::api_url(API_BASE_URL, path))
     }
 
     /// Return the item with the specified id.
     ///
     /// May return `None` if item id is invalid.
-    pub fn get_item(&self, id: u32) -> Result<Option<types::Item>> {
-        self.client
-            .get(&format!("{}/item/{}.json", API_BASE_URL, id))
-            .send()?
-            .json()
-            .map_err(HnClientError::from)
+    pub fn get_item(&self, id: impl Into<types::ItemId>) -> Result<Option<types::Item>> {
+        let id = id.into();
+        self.fetch_json(format!("{}/item/{}.json", API_BASE_URL, id))
+    }
+
+    /// Return the item with the specified id as untyped JSON, including
+    /// fields this crate does not know about.
+    ///
+    /// Returns `Value::Null` if item id is invalid.
+    pub fn get_item_raw(&self, id: impl Into<types::ItemId>) -> Result<serde_json::Value> {
+        let id = id.into();
+        self.fetch_json(format!("{}/item/{}.json", API_BASE_URL, id))
     }
 
     /// Return the user with the specified username.
     ///
     /// May return `None` if username is invalid.
     pub fn get_user(&self, username: &str) -> Result<Option<types::User>> {
-        self.client
-            .get(&format!("{}/user/{}.json", API_BASE_URL, username))
-            .send()?
-            .json()
-            .map_err(HnClientError::from)
+        self.fetch_json(format!("{}/user/{}.json", API_BASE_URL, username))
     }
 
     /// Return the id of the newest item.
     ///
-    /// To get the 10 latest items, you can decrement the id 10 times.
-    pub fn get_max_item_id(&self) -> Result<u32> {
-        self.client
-            .get(&format!("{}/maxitem.json", API_BASE_URL))
-            .send()?
-            .json()
-            .map_err(HnClientError::from)
+    /// To get the 10 latest items, you can decrement the id 10 times, or use
+    /// [`HnClient::iter_recent_items`].
+    pub fn get_max_item_id(&self) -> Result<types::ItemId> {
+        self.fetch_json(format!("{}/maxitem.json", API_BASE_URL))
+    }
+
+    /// Return an iterator over the `count` newest items, walking backwards
+    /// through the ids.
+    ///
+    /// Items are fetched lazily, one at a time. Missing and deleted items are
+    /// skipped and do not count towards `count`. A failed request yields an
+    /// error without ending the iteration.
+    pub fn iter_recent_items(
+        &self,
+        count: usize,
+    ) -> impl Iterator<Item = Result<types::Item>> + '_ {
+        let mut next_id = None;
+        std::iter::from_fn(move || loop {
+            let id = match next_id {
+                Some(0) => return None,
+                Some(id) => id,
+                None => match self.get_max_item_id() {
+                    Ok(types::ItemId(0)) => {
+                        next_id = Some(0);
+                        return None;
+                    }
+                    Ok(id) => id.0,
+                    Err(err) => {
+                        next_id = Some(0);
+                        return Some(Err(err));
+                    }
+                },
+            };
+            next_id = Some(id - 1);
+            match self.get_item(id) {
+                Ok(Some(item)) if !item.is_deleted() => return Some(Ok(item)),
+                Ok(_) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        })
+        .take(count)
+    }
+
+    /// Fetch the items with the specified ids on up to 8 threads, in order.
+    /// Missing items are skipped.
+    fn get_items_concurrently(&self, ids: &[types::ItemId]) -> Result<Vec<types::Item>> {
+        const THREADS: usize = 8;
+        let chunk_size = ids.len().div_ceil(THREADS).max(1);
+        let chunks: Vec<Result<Vec<Option<types::Item>>>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = ids
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || chunk.iter().map(|id| self.get_item(*id)).collect())
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("fetch thread panicked"))
+                .collect()
+        });
+        let mut items = Vec::with_capacity(ids.len());
+        for chunk in chunks {
+            items.extend(chunk?.into_iter().flatten());
+        }
+        Ok(items)
+    }
+
+    /// Return the item ids of the specified story listing, in ranked order.
+    pub fn get_stories(&self, list: types::StoryList) -> Result<Vec<types::ItemId>> {
+        self.fetch_json(format!("{}/{}.json", API_BASE_URL, list.api_name()))
     }
 
     /// Return a list of top story item ids.
-    pub fn get_top_stories(&self) -> Result<Vec<u32>> {
-        self.client
-            .get(&format!("{}/topstories.json", API_BASE_URL))
-            .send()?
-            .json()
-            .map_err(HnClientError::from)
+    pub fn get_top_stories(&self) -> Result<Vec<types::ItemId>> {
+        self.get_stories(types::StoryList::Top)
+    }
+
+    /// Return the first `count` top stories.
+    ///
+    /// The items are fetched concurrently, on up to 8 threads. Missing items
+    /// are skipped.
+    /// Fails if any of the request failed.
+    pub fn get_top_stories_with_items(&self, count: usize) -> Result<Vec<types::Item>> {
+        let mut ids = self.get_top_stories()?;
+        ids.truncate(count);
+        self.get_items_concurrently(&ids)
     }
 
     /// Return a list of new story item ids.
-    pub fn get_new_stories(&self) -> Result<Vec<u32>> {
-        self.client
-            .get(&format!("{}/newstories.json", API_BASE_URL))
-            .send()?
-            .json()
-            .map_err(HnClientError::from)
+    pub fn get_new_stories(&self) -> Result<Vec<types::ItemId>> {
+        self.get_stories(types::StoryList::New)
     }
 
     /// Return a list of best story item ids.
-    pub fn get_best_stories(&self) -> Result<Vec<u32>> {
-        self.client
-            .get(&format!("{}/beststories.json", API_BASE_URL))
-            .send()?
-            .json()
-            .map_err(HnClientError::from)
+    pub fn get_best_stories(&self) -> Result<Vec<types::ItemId>> {
+        self.get_stories(types::StoryList::Best)
     }
 
     /// Return up to 200 latest Ask HN story item ids.
-    pub fn get_ask_stories(&self) -> Result<Vec<u32>> {
-        self.client
-            .get(&format!("{}/askstories.json", API_BASE_URL))
-            .send()?
-            .json()
-            .map_err(HnClientError::from)
+    pub fn get_ask_stories(&self) -> Result<Vec<types::ItemId>> {
+        self.get_stories(types::StoryList::Ask)
     }
 
     /// Return up to 200 latest Show HN story item ids.
-    pub fn get_show_stories(&self) -> Result<Vec<u32>> {
-        self.client
-            .get(&format!("{}/showstories.json", API_BASE_URL))
-            .send()?
-            .json()
-            .map_err(HnClientError::from)
+    pub fn get_show_stories(&self) -> Result<Vec<types::ItemId>> {
+        self.get_stories(types::StoryList::Show)
     }
 
     /// Return up to 200 latest Job story item ids.
-    pub fn get_job_stories(&self) -> Result<Vec<u32>> {
-        self.client
-            .get(&format!("{}/jobstories.json", API_BASE_URL))
-            .send()?
-            .json()
-            .map_err(HnClientError::from)
+    pub fn get_job_stories(&self) -> Result<Vec<types::ItemId>> {
+        self.get_stories(types::StoryList::Job)
     }
 
     /// Return a list of items and users that have been updated recently.
     pub fn get_updates(&self) -> Result<types::Updates> {
-        self.client
-            .get(&format!("{}/updates.json", API_BASE_URL))
-            .send()?
-            .json()
-            .map_err(HnClientError::from)
+        self.fetch_json(format!("{}/updates.json", API_BASE_URL))
+    }
+}
+
+/// Convert the error of a ureq request to `url`.
+#[cfg(not(feature = "blocking"))]
+fn ureq_error(url: &str, err: ureq::Error) -> HnClientError {
+    match err {
+        ureq::Error::Status(status, response) => {
+            let mut body = Vec::new();
+            // The body is only used for the error message.
+            let _ = response.into_reader().take(4096).read_to_end(&mut body);
+            HnClientError::HttpError(Box::new(RequestError::status(url, status, &body)))
+        }
+        ureq::Error::Transport(transport) => {
+            let timeout = std::error::Error::source(&transport)
+                .and_then(|source| source.downcast_ref::<std::io::Error>())
+                .is_some_and(is_timeout);
+            HnClientError::HttpError(Box::new(RequestError {
+                endpoint: Endpoint::from_url(url),
+                url: url.to_string(),
+                status: None,
+                body: None,
+                source: Some(std::sync::Arc::new(transport)),
+                timeout,
+            }))
+        }
+    }
+}
+
+/// Return whether a header has a valid name, a token, and a value of
+/// visible ASCII characters, spaces and tabs.
+fn is_valid_header(name: &str, value: &str) -> bool {
+    let is_token = |byte: u8| byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte);
+    !name.is_empty()
+        && name.bytes().all(is_token)
+        && value
+            .bytes()
+            .all(|byte| byte == b'\t' || (b' '..=b'~').contains(&byte))
+}
+
+/// Convert an error reading the response body of a request to `url`.
+fn transport_error(url: &str, err: std::io::Error) -> HnClientError {
+    HnClientError::HttpError(Box::new(RequestError {
+        endpoint: Endpoint::from_url(url),
+        url: url.to_string(),
+        status: None,
+        body: None,
+        timeout: is_timeout(&err),
+        source: Some(std::sync::Arc::new(err)),
+    }))
+}
+
+/// Return whether an I/O error is a timeout.
+fn is_timeout(err: &std::io::Error) -> bool {
+    #[cfg(feature = "blocking")]
+    if let Some(err) = err
+        .get_ref()
+        .and_then(|err| err.downcast_ref::<reqwest::Error>())
+    {
+        return err.is_timeout();
+    }
+    matches!(
+        err.kind(),
+        std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answer one request with `response` and return the URL to request.
+    fn serve(response: &'static str) -> String {
+        use std::io::Write;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let _ = socket.read(&mut [0; 1024]);
+            let _ = socket.write_all(response.as_bytes());
+        });
+        format!("http://{}/item/1.json", addr)
+    }
+
+    #[test]
+    fn test_read_timeout() {
+        use std::io::Write;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/item/1.json", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let _ = socket.read(&mut [0; 1024]);
+            let _ = socket.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 10\r\n\r\n{");
+            std::thread::sleep(Duration::from_secs(5));
+        });
+        let client = HnClient::builder()
+            .read_timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let sent = Instant::now();
+        let err = client.fetch_bytes(url).unwrap_err();
+        assert!(err.is_timeout(), "{:?}", err);
+        assert!(sent.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_default_headers() {
+        let client = HnClient::builder()
+            .default_header("X-App-Name", "demo (v1)")
+            .build()
+            .unwrap();
+        assert_eq!(client.default_headers.len(), 1);
+        for (name, value) in [("X App", "demo"), ("", "demo"), ("X-App-Name", "a\r\nb")] {
+            let builder = HnClient::builder().default_header(name, value);
+            assert!(builder.build().is_err(), "{:?}: {:?}", name, value);
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_status_error() {
+        let client = HnClient::init().unwrap();
+        let url = serve("HTTP/1.1 503 Service Unavailable\r\ncontent-length: 4\r\n\r\nbusy");
+        match client.fetch_bytes(url) {
+            Err(HnClientError::HttpError(err)) => {
+                assert_eq!(err.status, Some(503));
+                assert_eq!(err.body.as_deref(), Some("busy"));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_response_checks() {
+        let client = HnClient::builder().max_response_size(4).build().unwrap();
+        let url = serve(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 4\r\n\r\nnull",
+        );
+        assert_eq!(client.fetch_bytes(url).unwrap(), "null");
+
+        let url =
+            serve("HTTP/1.1 200 OK\r\ncontent-type: text/html\r\ncontent-length: 4\r\n\r\n<hr>");
+        assert!(matches!(
+            client.fetch_bytes(url),
+            Err(HnClientError::UnexpectedContentType(content_type)) if content_type == "text/html"
+        ));
+
+        let url = serve("HTTP/1.1 200 OK\r\ncontent-length: 6\r\n\r\n[1, 2]");
+        assert!(matches!(
+            client.fetch_bytes(url),
+            Err(HnClientError::ResponseTooLarge(4))
+        ));
+        // Without a declared length, the body is cut off while reading.
+        let url = serve("HTTP/1.1 200 OK\r\nconnection: close\r\n\r\n[1, 2]");
+        assert!(matches!(
+            client.fetch_bytes(url),
+            Err(HnClientError::ResponseTooLarge(4))
+        ));
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[test]
+    fn test_ureq_error() {
+        let url = "https://hacker-news.firebaseio.com/v0/item/1.json";
+        let response = ureq::Response::new(404, "Not Found", "not found").unwrap();
+        let err = ureq_error(url, ureq::Error::Status(404, response));
+        assert!(err.is_not_found());
+        assert!(!err.is_timeout());
+        assert_eq!(
+            err.to_string(),
+            format!("request to {} failed with status 404 (not found)", url)
+        );
     }
 }
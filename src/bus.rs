@@ -0,0 +1,248 @@
+//! Share the activity of Hacker News between several consumers.
+//!
+//! [`HnClient::subscribe`] returns a broadcast receiver of [`HnEvent`]s.
+//! Every kind of subscription is produced by a single background task per
+//! client, which polls the API every
+//! [`subscription_interval`](crate::nonblocking::HnClientBuilder::subscription_interval),
+//! no matter how many components subscribed to it. The task stops once all of
+//! its receivers are dropped, or the client is
+//! [shut down](crate::shutdown).
+//!
+//! ```rust,no_run
+//! use hn_api::{
+//!     bus::{HnEvent, SubscriptionKind},
+//!     nonblocking::HnClient,
+//! };
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let mut stories = client.subscribe(SubscriptionKind::NewStories);
+//! let mut comments = client.subscribe(SubscriptionKind::NewComments);
+//! while let Ok(event) = stories.recv().await {
+//!     if let HnEvent::NewStory(story) = event? {
+//!         println!("{}", story.title().unwrap_or_default());
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use futures::future::{self, Either};
+use tokio::sync::broadcast::{self, Receiver, Sender};
+
+use super::{
+    nonblocking::HnClient,
+    runtime,
+    types::{Item, ItemId, UpdateEvent, Updates},
+    Result,
+};
+
+/// The number of events a receiver may lag behind before it misses some.
+const CAPACITY: usize = 256;
+
+/// The most items fetched by one poll for new comments. If more items were
+/// created since the previous poll, only the newest ones are fetched.
+const MAX_ITEMS_PER_POLL: u32 = 500;
+
+/// The kinds of activity to subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SubscriptionKind {
+    /// Stories appearing on the new stories listing.
+    NewStories,
+    /// Newly created comments, on any story.
+    NewComments,
+    /// Items and users reported by the updates endpoint.
+    Updates,
+}
+
+/// An event of a subscription.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HnEvent {
+    /// A story appeared on the new stories listing.
+    NewStory(Item),
+    /// A comment was created.
+    NewComment(Item),
+    /// An item or user changed.
+    Update(UpdateEvent),
+}
+
+/// The senders of the running subscription tasks of a client.
+#[derive(Debug)]
+pub(crate) struct EventBus {
+    interval: Duration,
+    senders: Mutex<HashMap<SubscriptionKind, Sender<Result<HnEvent>>>>,
+}
+
+impl EventBus {
+    pub(crate) fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            senders: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Remove the sender of `kind` if it has no receivers left, and return
+    /// whether it was removed.
+    fn close_unused(&self, kind: SubscriptionKind) -> bool {
+        let mut senders = self.senders.lock().unwrap();
+        let unused = senders
+            .get(&kind)
+            .is_none_or(|sender| sender.receiver_count() == 0);
+        if unused {
+            senders.remove(&kind);
+        }
+        unused
+    }
+}
+
+/// What a subscription task remembers between two polls.
+#[derive(Debug, Default)]
+struct PollState {
+    /// The newest item id seen so far.
+    last_id: Option<ItemId>,
+    /// The previous response of the updates endpoint.
+    updates: Option<Updates>,
+}
+
+impl HnClient {
+    /// Return a receiver of the events of a kind of activity.
+    ///
+    /// The first subscription to a kind starts a background task polling the
+    /// API, later ones share it. Only activity after the first poll is
+    /// reported. A failed poll is sent as an error, and the task keeps
+    /// polling. Receivers that fall behind by more than 256 events miss the
+    /// oldest ones, see [`broadcast::Receiver::recv`]. The receiver is
+    /// closed when the client is shut down.
+    ///
+    /// Must be called within a Tokio runtime, unless the `runtime-agnostic`
+    /// feature is enabled.
+    pub fn subscribe(&self, kind: SubscriptionKind) -> Receiver<Result<HnEvent>> {
+        let bus = self.event_bus();
+        let mut senders = bus.senders.lock().unwrap();
+        if let Some(sender) = senders.get(&kind) {
+            return sender.subscribe();
+        }
+        let (sender, receiver) = broadcast::channel(CAPACITY);
+        let token = self.shutdown_token();
+        if token.is_shutdown() {
+            return receiver;
+        }
+        senders.insert(kind, sender.clone());
+        let client = self.clone();
+        self.spawn(async move {
+            let mut state = PollState::default();
+            loop {
+                for event in client.poll_events(kind, &mut state).await {
+                    let _ = sender.send(event);
+                }
+                if client.event_bus().close_unused(kind) {
+                    break;
+                }
+                let sleep = Box::pin(runtime::sleep(client.event_bus().interval));
+                if let Either::Right(_) = future::select(sleep, Box::pin(token.wait())).await {
+                    client.event_bus().senders.lock().unwrap().remove(&kind);
+                    break;
+                }
+            }
+        });
+        receiver
+    }
+
+    /// Poll the API once for the events of `kind` since the previous poll.
+    async fn poll_events(
+        &self,
+        kind: SubscriptionKind,
+        state: &mut PollState,
+    ) -> Vec<Result<HnEvent>> {
+        let result = match kind {
+            SubscriptionKind::NewStories => self.poll_new_stories(state).await,
+            SubscriptionKind::NewComments => self.poll_new_comments(state).await,
+            SubscriptionKind::Updates => self.get_updates().await.map(|updates| {
+                let events = updates.events_since(state.updates.as_ref());
+                if state.updates.replace(updates).is_none() {
+                    return Vec::new();
+                }
+                events.into_iter().map(HnEvent::Update).collect()
+            }),
+        };
+        match result {
+            Ok(events) => events.into_iter().map(Ok).collect(),
+            Err(err) => vec![Err(err)],
+        }
+    }
+
+    /// Return the stories on the new stories listing newer than the last
+    /// seen id, oldest first.
+    async fn poll_new_stories(&self, state: &mut PollState) -> Result<Vec<HnEvent>> {
+        let mut ids = self.get_new_stories().await?;
+        let last_id = state.last_id;
+        state.last_id = ids.iter().copied().max().max(last_id);
+        let last_id = match last_id {
+            Some(last_id) => last_id,
+            None => return Ok(Vec::new()),
+        };
+        ids.retain(|id| *id > last_id);
+        ids.sort();
+        Ok(self
+            .try_get_items(&ids)
+            .await?
+            .into_iter()
+            .flatten()
+            .filter(|item| !item.is_deleted())
+            .map(HnEvent::NewStory)
+            .collect())
+    }
+
+    /// Return the comments created since the last seen id, oldest first.
+    async fn poll_new_comments(&self, state: &mut PollState) -> Result<Vec<HnEvent>> {
+        let max_id = self.get_max_item_id().await?;
+        let last_id = match state.last_id.replace(max_id) {
+            Some(last_id) => last_id.0,
+            None => return Ok(Vec::new()),
+        };
+        let first = (last_id + 1).max(max_id.0.saturating_sub(MAX_ITEMS_PER_POLL - 1));
+        let ids: Vec<_> = (first..=max_id.0).map(ItemId).collect();
+        Ok(self
+            .try_get_items(&ids)
+            .await?
+            .into_iter()
+            .flatten()
+            .filter(|item| matches!(item, Item::Comment(_)) && !item.is_deleted())
+            .map(HnEvent::NewComment)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cassette::Cassette;
+
+    #[tokio::test]
+    async fn test_subscribe() {
+        let api = "https://hacker-news.firebaseio.com/v0";
+        let mut cassette = Cassette::new();
+        cassette
+            .push(format!("{}/newstories.json", api), "[1]")
+            .push(format!("{}/newstories.json", api), "[2, 1]")
+            .push(
+                format!("{}/item/2.json", api),
+                r#"{"id": 2, "score": 1, "time": 0, "type": "story"}"#,
+            );
+        let client = HnClient::builder()
+            .replay(cassette)
+            .subscription_interval(Duration::from_millis(1))
+            .build()
+            .unwrap();
+        let mut first = client.subscribe(SubscriptionKind::NewStories);
+        let mut second = client.subscribe(SubscriptionKind::NewStories);
+        for receiver in [&mut first, &mut second] {
+            match receiver.recv().await.unwrap().unwrap() {
+                HnEvent::NewStory(story) => assert_eq!(story.id(), ItemId(2)),
+                event => panic!("unexpected event {:?}", event),
+            }
+        }
+    }
+}
@@ -0,0 +1,350 @@
+//! Pluggable response caches for the async client.
+//!
+//! A client built with [`HnClientBuilder::cache`] looks every item, user and
+//! story listing up in a [`CacheStore`] before it sends a request, and puts
+//! successful responses into it for as long as the [`CachePolicy`] allows.
+//! The newest item id and the updates are never cached.
+//!
+//! Entries are keyed by the endpoint, e.g. `item/8863`, `user/pg` or
+//! `topstories`, so that stores can be shared by several clients. This crate
+//! ships [`MemoryStore`], with the `moka` feature [`MokaStore`], and with the
+//! `cache-redis` feature [`RedisStore`], which lets several replicas of a
+//! service share one cache and so stay under the rate limits together.
+//!
+//! [`HnClientBuilder::cache`]: crate::nonblocking::HnClientBuilder::cache
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use futures::future::{self, BoxFuture, FutureExt};
+
+use super::Endpoint;
+
+/// A store of response bodies shared by clients.
+pub trait CacheStore: Send + Sync {
+    /// Return the body stored under `key`, unless it expired.
+    fn get(&self, key: &str) -> BoxFuture<'_, Option<Bytes>>;
+
+    /// Store `body` under `key` for `ttl`, replacing any previous entry.
+    fn put(&self, key: &str, body: Bytes, ttl: Duration) -> BoxFuture<'_, ()>;
+}
+
+/// How long responses of each kind of endpoint are cached. A zero duration
+/// disables caching for that kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CachePolicy {
+    /// Defaults to 5 minutes.
+    pub items: Duration,
+    /// Defaults to 15 minutes.
+    pub users: Duration,
+    /// Defaults to 1 minute.
+    pub listings: Duration,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self {
+            items: Duration::from_secs(5 * 60),
+            users: Duration::from_secs(15 * 60),
+            listings: Duration::from_secs(60),
+        }
+    }
+}
+
+impl CachePolicy {
+    /// Return the key and time to live of a response, or `None` if it is
+    /// not cached.
+    pub(crate) fn entry(&self, url: &str) -> Option<(String, Duration)> {
+        let (key, ttl) = match Endpoint::from_url(url) {
+            Endpoint::Item(id) => (format!("item/{}", id), self.items),
+            Endpoint::User(username) => (format!("user/{}", username), self.users),
+            Endpoint::Stories(list) => (list.api_name().to_string(), self.listings),
+            _ => return None,
+        };
+        Some((key, ttl)).filter(|_| !ttl.is_zero())
+    }
+}
+
+/// A cache store keeping entries in memory until they expire.
+///
+/// Expired entries are only dropped when they are looked up or replaced.
+/// Create the store [`with_capacity`](MemoryStore::with_capacity) to bound
+/// the memory use, or use [`MokaStore`] to evict the least recently used
+/// entries instead.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    entries: Mutex<HashMap<String, (Instant, Bytes)>>,
+    /// The stored keys, oldest first, if the store has a capacity. Keys
+    /// stored again are listed again.
+    order: Option<Mutex<VecDeque<(Instant, String)>>>,
+    capacity: usize,
+}
+
+impl MemoryStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty store holding up to `max_entries` responses. Above
+    /// that, the oldest entries are dropped first.
+    pub fn with_capacity(max_entries: usize) -> Self {
+        Self {
+            entries: Mutex::default(),
+            order: Some(Mutex::default()),
+            capacity: max_entries.max(1),
+        }
+    }
+}
+
+impl CacheStore for MemoryStore {
+    fn get(&self, key: &str) -> BoxFuture<'_, Option<Bytes>> {
+        let mut entries = self.entries.lock().unwrap();
+        let body = match entries.get(key) {
+            Some((expires, body)) if Instant::now() < *expires => Some(body.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        };
+        future::ready(body).boxed()
+    }
+
+    fn put(&self, key: &str, body: Bytes, ttl: Duration) -> BoxFuture<'_, ()> {
+        let expires = Instant::now() + ttl;
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(key.to_string(), (expires, body));
+        if let Some(order) = &self.order {
+            let mut order = order.lock().unwrap();
+            order.push_back((expires, key.to_string()));
+            while order.len() > self.capacity {
+                let (stored, key) = order.pop_front().expect("not empty");
+                if entries
+                    .get(&key)
+                    .is_some_and(|(expires, _)| *expires == stored)
+                {
+                    entries.remove(&key);
+                }
+            }
+        }
+        future::ready(()).boxed()
+    }
+}
+
+impl<T: CacheStore + ?Sized> CacheStore for Arc<T> {
+    fn get(&self, key: &str) -> BoxFuture<'_, Option<Bytes>> {
+        (**self).get(key)
+    }
+
+    fn put(&self, key: &str, body: Bytes, ttl: Duration) -> BoxFuture<'_, ()> {
+        (**self).put(key, body, ttl)
+    }
+}
+
+/// A cache store backed by a [moka](https://docs.rs/moka) cache, which
+/// evicts the least recently used entries above a capacity.
+#[cfg(feature = "moka")]
+#[derive(Debug, Clone)]
+pub struct MokaStore {
+    cache: MokaCache,
+}
+
+/// The moka cache behind a [`MokaStore`], mapping keys to the time to live
+/// and body of each response.
+#[cfg(feature = "moka")]
+pub type MokaCache = moka::future::Cache<String, (Duration, Bytes)>;
+
+#[cfg(feature = "moka")]
+impl MokaStore {
+    /// Create a store holding up to `max_entries` responses.
+    pub fn new(max_entries: u64) -> Self {
+        Self::from_builder(moka::future::Cache::builder().max_capacity(max_entries))
+    }
+
+    /// Create a store from a configured moka cache builder. The expiry is
+    /// overridden by the time to live of each entry.
+    pub fn from_builder(
+        builder: moka::future::CacheBuilder<String, (Duration, Bytes), MokaCache>,
+    ) -> Self {
+        Self {
+            cache: builder.expire_after(TimeToLive).build(),
+        }
+    }
+}
+
+/// Expire moka entries after the time to live they were stored with.
+#[cfg(feature = "moka")]
+struct TimeToLive;
+
+#[cfg(feature = "moka")]
+impl moka::Expiry<String, (Duration, Bytes)> for TimeToLive {
+    fn expire_after_create(
+        &self,
+        _: &String,
+        (ttl, _): &(Duration, Bytes),
+        _: Instant,
+    ) -> Option<Duration> {
+        Some(*ttl)
+    }
+
+    fn expire_after_update(
+        &self,
+        _: &String,
+        (ttl, _): &(Duration, Bytes),
+        _: Instant,
+        _: Option<Duration>,
+    ) -> Option<Duration> {
+        Some(*ttl)
+    }
+}
+
+#[cfg(feature = "moka")]
+impl CacheStore for MokaStore {
+    fn get(&self, key: &str) -> BoxFuture<'_, Option<Bytes>> {
+        let key = key.to_string();
+        async move { self.cache.get(&key).await.map(|(_, body)| body) }.boxed()
+    }
+
+    fn put(&self, key: &str, body: Bytes, ttl: Duration) -> BoxFuture<'_, ()> {
+        self.cache.insert(key.to_string(), (ttl, body)).boxed()
+    }
+}
+
+/// A cache store keeping entries in Redis, so that several processes can
+/// share them.
+///
+/// Keys are prefixed with `hn_api:` by default, e.g. `hn_api:item/8863`.
+/// Redis expires the entries after the time to live of the
+/// [`CachePolicy`]. Failed Redis commands are treated as cache misses.
+#[cfg(feature = "cache-redis")]
+#[derive(Clone)]
+pub struct RedisStore {
+    connection: redis::aio::ConnectionManager,
+    prefix: String,
+}
+
+#[cfg(feature = "cache-redis")]
+impl std::fmt::Debug for RedisStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisStore")
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "cache-redis")]
+impl RedisStore {
+    /// Create a store on top of an existing connection, which reconnects
+    /// on its own.
+    pub fn new(connection: redis::aio::ConnectionManager) -> Self {
+        Self {
+            connection,
+            prefix: "hn_api:".to_string(),
+        }
+    }
+
+    /// Connect to the Redis server at `url`, e.g. `redis://127.0.0.1/`.
+    pub async fn connect(url: &str) -> crate::Result<Self> {
+        let client = redis::Client::open(url).map_err(redis_error)?;
+        let connection = redis::aio::ConnectionManager::new(client)
+            .await
+            .map_err(redis_error)?;
+        Ok(Self::new(connection))
+    }
+
+    /// Set the prefix of all keys, to separate the entries from other data
+    /// in the same database. Defaults to `hn_api:`.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Return the Redis key of a cache key.
+    fn key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+}
+
+#[cfg(feature = "cache-redis")]
+fn redis_error(err: redis::RedisError) -> crate::HnClientError {
+    crate::HnClientError::BackendError(format!("redis: {}", err))
+}
+
+#[cfg(feature = "cache-redis")]
+impl CacheStore for RedisStore {
+    fn get(&self, key: &str) -> BoxFuture<'_, Option<Bytes>> {
+        let mut connection = self.connection.clone();
+        let command = redis::cmd("GET").arg(self.key(key)).clone();
+        async move {
+            let body: Option<Vec<u8>> = command.query_async(&mut connection).await.ok()?;
+            body.map(Bytes::from)
+        }
+        .boxed()
+    }
+
+    fn put(&self, key: &str, body: Bytes, ttl: Duration) -> BoxFuture<'_, ()> {
+        let mut connection = self.connection.clone();
+        let command = redis::cmd("SET")
+            .arg(self.key(key))
+            .arg(&body[..])
+            .arg("PX")
+            .arg(ttl.as_millis().max(1) as u64)
+            .clone();
+        async move {
+            let _ = command.query_async::<()>(&mut connection).await;
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nonblocking::HnClient;
+
+    #[tokio::test]
+    async fn test_memory_store() {
+        let store = Arc::new(MemoryStore::new());
+        let body = Bytes::from_static(br#"{"id": 1, "score": 7, "time": 0, "type": "story"}"#);
+        store.put("item/1", body, Duration::from_secs(60)).await;
+        store.put("item/2", Bytes::new(), Duration::ZERO).await;
+        assert_eq!(store.get("item/2").await, None);
+
+        let client = HnClient::builder().cache(store.clone()).build().unwrap();
+        assert_eq!(client.get_item(1).await.unwrap().score(), Some(7));
+        assert_eq!(client.stats().cache_hits, 1);
+
+        let store = MemoryStore::with_capacity(2);
+        let ttl = Duration::from_secs(60);
+        for key in ["a", "b", "a", "c"] {
+            store.put(key, Bytes::from(key), ttl).await;
+        }
+        assert_eq!(store.get("b").await, None);
+        assert_eq!(store.get("a").await, Some(Bytes::from("a")));
+        assert_eq!(store.get("c").await, Some(Bytes::from("c")));
+    }
+
+    #[test]
+    fn test_policy() {
+        let api = "https://hacker-news.firebaseio.com/v0";
+        let policy = CachePolicy {
+            users: Duration::ZERO,
+            ..CachePolicy::default()
+        };
+        assert_eq!(
+            policy.entry(&format!("{}/item/8863.json", api)),
+            Some(("item/8863".to_string(), policy.items))
+        );
+        assert_eq!(
+            policy.entry(&format!("{}/topstories.json", api)),
+            Some(("topstories".to_string(), policy.listings))
+        );
+        assert_eq!(policy.entry(&format!("{}/user/pg.json", api)), None);
+        assert_eq!(policy.entry(&format!("{}/maxitem.json", api)), None);
+    }
+}
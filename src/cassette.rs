@@ -0,0 +1,237 @@
+//! Record responses of the async client and replay them later without
+//! network access, e.g. for deterministic integration tests.
+//!
+//! ```rust,no_run
+//! use hn_api::{cassette::Cassette, nonblocking::HnClient};
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! // Record every response into `hn.json`.
+//! let client = HnClient::builder().record("hn.json").build()?;
+//! client.get_item(8863).await?;
+//! client.save_cassette()?;
+//!
+//! // Answer the same requests from the file.
+//! let cassette = Cassette::load_file("hn.json").expect("could not load cassette");
+//! let client = HnClient::builder().replay(cassette).build()?;
+//! client.get_item(8863).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+use super::{HnClientError, RequestError, Result};
+
+/// A recorded request and the response it received.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    /// The requested URL.
+    pub url: String,
+    /// The HTTP status code of the response.
+    pub status: u16,
+    /// The response body.
+    pub body: String,
+}
+
+/// A list of recorded interactions, in the order they happened.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cassette {
+    /// The recorded interactions.
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    /// Create an empty cassette.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a successful response to the cassette.
+    pub fn push(&mut self, url: impl Into<String>, body: impl Into<String>) -> &mut Self {
+        self.interactions.push(Interaction {
+            url: url.into(),
+            status: 200,
+            body: body.into(),
+        });
+        self
+    }
+
+    /// Save the cassette as a JSON file.
+    pub fn save_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    /// Load a cassette saved with [`Cassette::save_file`].
+    pub fn load_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
+
+/// How a client uses a cassette.
+pub(crate) enum CassetteMode {
+    /// Collect every response, and write them to the file at `path` on
+    /// [`CassetteMode::save`] and when dropped.
+    Record {
+        path: PathBuf,
+        cassette: Mutex<Cassette>,
+    },
+    /// Answer requests from the cassette. Counts how often every URL was
+    /// answered, so repeated requests get the recorded responses in order.
+    Replay {
+        cassette: Cassette,
+        served: Mutex<HashMap<String, usize>>,
+    },
+}
+
+impl CassetteMode {
+    pub(crate) fn record(path: PathBuf) -> Self {
+        CassetteMode::Record {
+            path,
+            cassette: Mutex::default(),
+        }
+    }
+
+    pub(crate) fn replay(cassette: Cassette) -> Self {
+        CassetteMode::Replay {
+            cassette,
+            served: Mutex::default(),
+        }
+    }
+
+    pub(crate) fn is_replay(&self) -> bool {
+        matches!(self, CassetteMode::Replay { .. })
+    }
+
+    /// Store a response in memory. Does nothing when replaying.
+    ///
+    /// Bodies that are not valid UTF-8 are stored with replacement
+    /// characters, as the cassette format only holds text.
+    pub(crate) fn store(&self, url: &str, status: u16, body: &[u8]) {
+        if let CassetteMode::Record { cassette, .. } = self {
+            let body = match std::str::from_utf8(body) {
+                Ok(body) => body.to_string(),
+                Err(_) => {
+                    log::warn!("cassette: response body of {} is not valid UTF-8", url);
+                    String::from_utf8_lossy(body).into_owned()
+                }
+            };
+            cassette.lock().unwrap().interactions.push(Interaction {
+                url: url.to_string(),
+                status,
+                body,
+            });
+        }
+    }
+
+    /// Write the responses stored so far to the cassette file. Does nothing
+    /// when replaying.
+    pub(crate) fn save(&self) -> Result<()> {
+        if let CassetteMode::Record { path, cassette } = self {
+            // Don't hold the lock while writing, requests keep storing.
+            let snapshot = cassette.lock().unwrap().clone();
+            snapshot.save_file(path).map_err(|err| {
+                HnClientError::BackendError(format!("could not write cassette: {}", err))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Return the recorded response to the next request for `url`. Once all
+    /// recorded responses were served, the last one is repeated.
+    pub(crate) fn respond(&self, url: &str) -> Result<Bytes> {
+        let (cassette, served) = match self {
+            CassetteMode::Replay { cassette, served } => (cassette, served),
+            CassetteMode::Record { .. } => unreachable!("respond called while recording"),
+        };
+        let recorded: Vec<_> = cassette
+            .interactions
+            .iter()
+            .filter(|interaction| interaction.url == url)
+            .collect();
+        let mut served = served.lock().unwrap();
+        let count = served.entry(url.to_string()).or_default();
+        let interaction = recorded
+            .get(*count)
+            .or_else(|| recorded.last())
+            .ok_or_else(|| {
+                HnClientError::BackendError(format!("no recorded response for {}", url))
+            })?;
+        *count += 1;
+        let body = Bytes::from(interaction.body.clone());
+        if (200..300).contains(&interaction.status) {
+            Ok(body)
+        } else {
+            let error = RequestError::status(url, interaction.status, &body);
+            Err(HnClientError::HttpError(Box::new(error)))
+        }
+    }
+}
+
+impl Drop for CassetteMode {
+    fn drop(&mut self) {
+        if let CassetteMode::Record { cassette, .. } = self {
+            // E.g. a builder that was never built.
+            if cassette.get_mut().unwrap().interactions.is_empty() {
+                return;
+            }
+        }
+        if let Err(err) = self.save() {
+            log::warn!("{}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nonblocking::HnClient;
+
+    #[tokio::test]
+    async fn test_replay() {
+        let url = "https://hacker-news.firebaseio.com/v0/item/1.json";
+        let mut cassette = Cassette::new();
+        cassette
+            .push(url, r#"{"id": 1, "score": 1, "time": 0, "type": "story"}"#)
+            .push(url, r#"{"id": 1, "score": 2, "time": 0, "type": "story"}"#);
+        let client = HnClient::builder().replay(cassette).build().unwrap();
+
+        assert_eq!(client.get_item(1).await.unwrap().score(), Some(1));
+        assert_eq!(client.get_item(1).await.unwrap().score(), Some(2));
+        assert_eq!(client.get_item(1).await.unwrap().score(), Some(2));
+        assert!(matches!(
+            client.get_item(2).await,
+            Err(HnClientError::BackendError(_))
+        ));
+    }
+
+    #[test]
+    fn test_record() {
+        let path =
+            std::env::temp_dir().join(format!("hn_api_cassette_{}.json", std::process::id()));
+        let url = "https://hacker-news.firebaseio.com/v0/item/1.json";
+        let mode = CassetteMode::record(path.clone());
+        mode.store(url, 200, b"null");
+        mode.store(url, 503, b"\xffbusy");
+        assert!(!path.exists());
+        drop(mode);
+
+        let cassette = Cassette::load_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(cassette.interactions.len(), 2);
+        assert_eq!(cassette.interactions[0].body, "null");
+        assert_eq!(cassette.interactions[1].status, 503);
+        assert_eq!(cassette.interactions[1].body, "\u{fffd}busy");
+    }
+}
@@ -0,0 +1,85 @@
+//! Segment stories into Ask HN, Show HN, Launch HN, jobs and regular
+//! stories, see [`Item::story_category`].
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "nonblocking")]
+//! use hn_api::{category, nonblocking::HnClient, StoryCategory};
+//!
+//! # #[cfg(feature = "nonblocking")]
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let stories = client.get_top_stories_with_items(100).await?;
+//! for story in category::filter_by_category(&stories, &[StoryCategory::ShowHn]) {
+//!     println!("{}", story.title().unwrap_or("?"));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+
+use super::types::{Item, StoryCategory};
+
+/// Return the items of any of the `categories`, in their original order.
+pub fn filter_by_category<'a>(items: &'a [Item], categories: &[StoryCategory]) -> Vec<&'a Item> {
+    items
+        .iter()
+        .filter(|item| {
+            item.story_category()
+                .is_some_and(|category| categories.contains(&category))
+        })
+        .collect()
+}
+
+/// Group the items by [`Item::story_category`], keeping their order within
+/// each group. Comments and poll options are skipped.
+pub fn group_by_category(items: &[Item]) -> BTreeMap<StoryCategory, Vec<&Item>> {
+    let mut groups: BTreeMap<StoryCategory, Vec<&Item>> = BTreeMap::new();
+    for item in items {
+        if let Some(category) = item.story_category() {
+            groups.entry(category).or_default().push(item);
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: u32, kind: &str, title: &str) -> Item {
+        serde_json::from_value(serde_json::json!({
+            "by": "a", "id": id, "time": 0, "title": title, "type": kind
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_categories() {
+        let items = vec![
+            item(1, "story", "Show HN: A thing I made"),
+            item(2, "story", " launch hn: Acme (YC W24)"),
+            item(3, "story", "Showing HN: Not a category"),
+            item(4, "job", "Acme is hiring"),
+            item(5, "poll", "Ask HN: Tabs or spaces?"),
+        ];
+        let categories: Vec<_> = items.iter().map(Item::story_category).collect();
+        assert_eq!(
+            categories,
+            [
+                Some(StoryCategory::ShowHn),
+                Some(StoryCategory::LaunchHn),
+                Some(StoryCategory::Regular),
+                Some(StoryCategory::Job),
+                Some(StoryCategory::AskHn),
+            ]
+        );
+
+        let ids: Vec<_> = filter_by_category(&items, &[StoryCategory::AskHn, StoryCategory::Job])
+            .iter()
+            .map(|item| item.id().0)
+            .collect();
+        assert_eq!(ids, [4, 5]);
+        assert_eq!(group_by_category(&items).len(), 5);
+    }
+}
@@ -0,0 +1,219 @@
+//! A circuit breaker that stops requests while the API keeps failing.
+//!
+//! After a number of consecutive failures the circuit opens, and every
+//! request fails immediately with [`HnClientError::CircuitOpen`] instead of
+//! reaching the API. Once the cooldown has passed, a single probe request is
+//! let through: if it succeeds the circuit closes again, otherwise it stays
+//! open for another cooldown. Responses from the
+//! [cache](crate::nonblocking::HnClientBuilder::cache), prefetches and
+//! cassettes are served regardless, and don't count as outcomes.
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use hn_api::nonblocking::HnClient;
+//!
+//! # fn run() -> hn_api::Result<()> {
+//! let client = HnClient::builder()
+//!     .circuit_breaker(5, Duration::from_secs(30))
+//!     .on_circuit_change(|state| println!("circuit is now {:?}", state))
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`HnClientError::CircuitOpen`]: crate::HnClientError::CircuitOpen
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// The state of a circuit breaker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests are sent normally.
+    Closed,
+    /// Requests fail immediately.
+    Open,
+    /// The cooldown has passed and a probe request decides whether the
+    /// circuit closes again.
+    HalfOpen,
+}
+
+/// A callback observing state changes of a circuit breaker.
+pub(crate) type CircuitCallback = Arc<dyn Fn(CircuitState) + Send + Sync>;
+
+struct Inner {
+    state: CircuitState,
+    failures: u32,
+    opened: Instant,
+    probing: bool,
+}
+
+pub(crate) struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    on_change: Option<CircuitCallback>,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(
+        threshold: u32,
+        cooldown: Duration,
+        on_change: Option<CircuitCallback>,
+    ) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            cooldown,
+            on_change,
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                failures: 0,
+                opened: Instant::now(),
+                probing: false,
+            }),
+        }
+    }
+
+    pub(crate) fn state(&self) -> CircuitState {
+        self.inner.lock().unwrap().state
+    }
+
+    /// Return a permit to send a request now, or `None` if the circuit is
+    /// open.
+    pub(crate) fn acquire(self: &Arc<Self>) -> Option<CircuitPermit> {
+        let mut inner = self.inner.lock().unwrap();
+        let (allowed, changed) = match inner.state {
+            CircuitState::Closed => (true, false),
+            CircuitState::Open if inner.opened.elapsed() >= self.cooldown => {
+                inner.state = CircuitState::HalfOpen;
+                inner.probing = true;
+                (true, true)
+            }
+            CircuitState::Open => (false, false),
+            CircuitState::HalfOpen if inner.probing => (false, false),
+            CircuitState::HalfOpen => {
+                inner.probing = true;
+                (true, false)
+            }
+        };
+        let probe = inner.state == CircuitState::HalfOpen;
+        drop(inner);
+        if changed {
+            self.notify(CircuitState::HalfOpen);
+        }
+        allowed.then(|| CircuitPermit {
+            breaker: Arc::clone(self),
+            probe,
+            recorded: false,
+        })
+    }
+
+    /// Record the outcome of a request that was let through.
+    fn record(&self, success: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.probing = false;
+        let next = if success {
+            inner.failures = 0;
+            CircuitState::Closed
+        } else {
+            inner.failures = inner.failures.saturating_add(1);
+            if inner.state == CircuitState::HalfOpen || inner.failures >= self.threshold {
+                CircuitState::Open
+            } else {
+                inner.state
+            }
+        };
+        // Requests sent before the circuit opened do not extend the cooldown.
+        if next == inner.state {
+            return;
+        }
+        inner.state = next;
+        if next == CircuitState::Open {
+            inner.opened = Instant::now();
+        }
+        drop(inner);
+        self.notify(next);
+    }
+
+    /// Free the probe slot of a half-open circuit whose probe was cancelled,
+    /// so that the next request probes instead.
+    fn release_probe(&self) {
+        self.inner.lock().unwrap().probing = false;
+    }
+
+    fn notify(&self, state: CircuitState) {
+        if let Some(on_change) = &self.on_change {
+            on_change(state);
+        }
+    }
+}
+
+/// Permission to send a single request, see [`CircuitBreaker::acquire`].
+///
+/// Dropping the permit without recording an outcome, e.g. because the request
+/// was cancelled, frees the probe slot of a half-open circuit.
+pub(crate) struct CircuitPermit {
+    breaker: Arc<CircuitBreaker>,
+    probe: bool,
+    recorded: bool,
+}
+
+impl CircuitPermit {
+    /// Record the outcome of the request.
+    pub(crate) fn record(mut self, success: bool) {
+        self.recorded = true;
+        self.breaker.record(success);
+    }
+}
+
+impl Drop for CircuitPermit {
+    fn drop(&mut self) {
+        if self.probe && !self.recorded {
+            self.breaker.release_probe();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circuit_breaker() {
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&changes);
+        let callback: CircuitCallback = Arc::new(move |state| recorded.lock().unwrap().push(state));
+        let breaker = Arc::new(CircuitBreaker::new(2, Duration::ZERO, Some(callback)));
+
+        breaker.acquire().unwrap().record(false);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.acquire().unwrap().record(false);
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // The cooldown has passed, so a single probe is let through.
+        let probe = breaker.acquire().unwrap();
+        assert!(breaker.acquire().is_none());
+        probe.record(false);
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // A cancelled probe lets the next request probe instead.
+        drop(breaker.acquire().unwrap());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        breaker.acquire().unwrap().record(true);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.acquire().is_some());
+
+        use CircuitState::*;
+        assert_eq!(
+            *changes.lock().unwrap(),
+            vec![Open, HalfOpen, Open, HalfOpen, Closed]
+        );
+
+        let breaker = Arc::new(CircuitBreaker::new(1, Duration::from_secs(60), None));
+        breaker.acquire().unwrap().record(false);
+        assert!(breaker.acquire().is_none());
+    }
+}
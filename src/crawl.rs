@@ -0,0 +1,248 @@
+//! Crawl ranges of item ids.
+//!
+//! [`HnClient::get_items_range`] fetches every item of an id range with
+//! bounded concurrency and an optional request rate limit, yielding the items
+//! in id order. The stream records a [`RangeCheckpoint`], which can be saved
+//! to resume an interrupted crawl later.
+//!
+//! ```rust,no_run
+//! use futures::StreamExt;
+//! use hn_api::{crawl::RangeOptions, nonblocking::HnClient};
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let options = RangeOptions {
+//!     concurrency: Some(8),
+//!     max_rate: Some(50),
+//!     ..RangeOptions::default()
+//! };
+//! let mut items = client.get_items_range(1..100_000, options);
+//! while let Some(item) = items.next().await {
+//!     match item {
+//!         Ok(item) => println!("{}", item.id()),
+//!         Err(err) => {
+//!             eprintln!("{}, resume at {:?}", err, items.checkpoint());
+//!             break;
+//!         }
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    ops::Range,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::{
+    future,
+    stream::{self, BoxStream, Stream, StreamExt, TryStreamExt},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    nonblocking::HnClient,
+    runtime,
+    types::{Item, ItemFilter, ItemId, Timestamp},
+    Result,
+};
+
+/// The position of a crawl, see [`ItemRange::checkpoint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RangeCheckpoint {
+    /// The first id that was not processed yet.
+    pub next_id: ItemId,
+}
+
+/// Options of [`HnClient::get_items_range`].
+#[derive(Debug, Clone, Default)]
+pub struct RangeOptions {
+    /// The number of requests kept outstanding at once. Defaults to the
+    /// batch size of the client.
+    pub concurrency: Option<usize>,
+    /// The most requests started per second. Unlimited by default.
+    pub max_rate: Option<u32>,
+    /// Resume a previous crawl of the same range at its checkpoint.
+    pub resume: Option<RangeCheckpoint>,
+    /// Which of the existing items are yielded. All by default.
+    pub filter: ItemFilter,
+}
+
+/// A stream of the items of an id range, see [`HnClient::get_items_range`].
+pub struct ItemRange<'a> {
+    responses: BoxStream<'a, (u32, Result<Option<Item>>)>,
+    filter: ItemFilter,
+    next_id: u32,
+    failed: bool,
+}
+
+impl ItemRange<'_> {
+    /// Return the position of the crawl: the id after the last item that
+    /// was yielded or skipped, or the id of the request that failed.
+    pub fn checkpoint(&self) -> RangeCheckpoint {
+        RangeCheckpoint {
+            next_id: ItemId(self.next_id),
+        }
+    }
+}
+
+impl Stream for ItemRange<'_> {
+    type Item = Result<Item>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.failed {
+            return Poll::Ready(None);
+        }
+        loop {
+            match self.responses.poll_next_unpin(cx) {
+                Poll::Ready(Some((id, Ok(item)))) => {
+                    self.next_id = id + 1;
+                    match item {
+                        Some(item) if self.filter.matches(&item) => {
+                            return Poll::Ready(Some(Ok(item)))
+                        }
+                        _ => continue,
+                    }
+                }
+                Poll::Ready(Some((_, Err(err)))) => {
+                    self.failed = true;
+                    return Poll::Ready(Some(Err(err)));
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl HnClient {
+    /// Return a stream of the items with ids in `range`, in id order.
+    ///
+    /// Missing items are skipped. The first failed request ends the stream
+    /// after yielding its error; the crawl can then be resumed from
+    /// [`ItemRange::checkpoint`] with [`RangeOptions::resume`].
+    pub fn get_items_range(&self, range: Range<u32>, options: RangeOptions) -> ItemRange<'_> {
+        let start = match options.resume {
+            Some(checkpoint) => checkpoint.next_id.0.clamp(range.start, range.end),
+            None => range.start,
+        };
+        let concurrency = options.concurrency.unwrap_or(self.batch_size()).max(1);
+        let delay = options
+            .max_rate
+            .map(|rate| Duration::from_secs(1) / rate.max(1));
+        let responses = stream::iter(start..range.end)
+            .then(move |id| async move {
+                if let Some(delay) = delay.filter(|_| id > start) {
+                    runtime::sleep(delay).await;
+                }
+                id
+            })
+            .map(move |id| async move { (id, self.try_get_item(id).await) })
+            .buffered(concurrency);
+        ItemRange {
+            responses: responses.boxed(),
+            filter: options.filter,
+            next_id: start,
+            failed: false,
+        }
+    }
+
+    /// Return a stream of the stories created at or after `from` and before
+    /// `to`, in id order.
+    ///
+    /// The id range of the window is found with
+    /// [`HnClient::find_item_id_at`], then crawled like
+    /// [`HnClient::get_items_range`] with the default options. Other kinds of
+    /// items are skipped, and so are stories whose time is outside the window
+    /// although their id is inside it. The first failed request ends the
+    /// stream after yielding its error.
+    pub fn get_stories_between(
+        &self,
+        from: Timestamp,
+        to: Timestamp,
+    ) -> impl Stream<Item = Result<Item>> + '_ {
+        stream::once(self.id_range_between(from, to))
+            .map_ok(move |range| self.get_items_range(range, RangeOptions::default()))
+            .try_flatten()
+            .try_filter(move |item| {
+                let time = item.time();
+                future::ready(matches!(item, Item::Story(_)) && from <= time && time < to)
+            })
+    }
+
+    /// Return the ids of the items created at or after `from` and before
+    /// `to`.
+    async fn id_range_between(&self, from: Timestamp, to: Timestamp) -> Result<Range<u32>> {
+        let max_id = self.get_max_item_id().await?.0;
+        let start = self.first_id_at(from, max_id).await?;
+        let end = self.first_id_at(to, max_id).await?;
+        Ok(start..end.max(start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cassette::Cassette;
+
+    #[tokio::test]
+    async fn test_items_range() {
+        let api = "https://hacker-news.firebaseio.com/v0";
+        let mut cassette = Cassette::new();
+        cassette
+            .push(
+                format!("{}/item/1.json", api),
+                r#"{"id": 1, "score": 1, "time": 0, "type": "story"}"#,
+            )
+            .push(format!("{}/item/2.json", api), "null")
+            .push(
+                format!("{}/item/3.json", api),
+                r#"{"id": 3, "score": 1, "time": 0, "type": "story"}"#,
+            );
+        let client = HnClient::builder().replay(cassette).build().unwrap();
+
+        let items = client.get_items_range(1..6, RangeOptions::default());
+        futures::pin_mut!(items);
+        assert_eq!(items.next().await.unwrap().unwrap().id(), ItemId(1));
+        assert_eq!(items.next().await.unwrap().unwrap().id(), ItemId(3));
+        assert!(items.next().await.unwrap().is_err());
+        assert!(items.next().await.is_none());
+        let checkpoint = items.checkpoint();
+        assert_eq!(checkpoint.next_id, ItemId(4));
+
+        let options = RangeOptions {
+            resume: Some(RangeCheckpoint { next_id: ItemId(3) }),
+            ..RangeOptions::default()
+        };
+        let items = client.get_items_range(1..4, options);
+        let ids: Vec<_> = items.map(|item| item.unwrap().id()).collect().await;
+        assert_eq!(ids, vec![ItemId(3)]);
+    }
+
+    #[tokio::test]
+    async fn test_stories_between() {
+        let api = "https://hacker-news.firebaseio.com/v0";
+        let mut cassette = Cassette::new();
+        cassette.push(format!("{}/maxitem.json", api), "5");
+        for id in 1..=5 {
+            let item = match id {
+                3 => r#"{"by": "a", "id": 3, "parent": 2, "text": "", "time": 30, "type": "comment"}"#
+                    .to_string(),
+                _ => format!(
+                    r#"{{"id": {}, "score": 1, "time": {}, "type": "story"}}"#,
+                    id,
+                    id * 10
+                ),
+            };
+            cassette.push(format!("{}/item/{}.json", api, id), item);
+        }
+        let client = HnClient::builder().replay(cassette).build().unwrap();
+
+        let stories = client.get_stories_between(Timestamp(15), Timestamp(45));
+        let ids: Vec<_> = stories.map(|item| item.unwrap().id()).collect().await;
+        assert_eq!(ids, vec![ItemId(2), ItemId(4)]);
+    }
+}
@@ -0,0 +1,91 @@
+//! Filtering and grouping of stories by the domain they link to.
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "nonblocking")]
+//! use hn_api::{domain, nonblocking::HnClient};
+//!
+//! # #[cfg(feature = "nonblocking")]
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let stories = client.get_top_stories_with_items(100).await?;
+//! for story in domain::filter_by_domain(&stories, "github.com") {
+//!     println!("{}", story.title().unwrap_or("?"));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+
+use super::types::Item;
+
+/// Return whether the item links to `domain` or one of its subdomains.
+pub fn is_from_domain(item: &Item, domain: &str) -> bool {
+    let domain = domain.trim_start_matches("www.").to_ascii_lowercase();
+    let host = match item.url().and_then(|url| url::Url::parse(url).ok()) {
+        Some(url) => url.host_str().unwrap_or_default().to_ascii_lowercase(),
+        None => return false,
+    };
+    host == domain
+        || host
+            .strip_suffix(&domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// Return the items linking to `domain` or one of its subdomains, in their
+/// original order.
+pub fn filter_by_domain<'a>(items: &'a [Item], domain: &str) -> Vec<&'a Item> {
+    items
+        .iter()
+        .filter(|item| is_from_domain(item, domain))
+        .collect()
+}
+
+/// Group the items by [`Item::domain`], keeping their order within each
+/// group. Items without a URL are skipped.
+pub fn group_by_domain(items: &[Item]) -> BTreeMap<String, Vec<&Item>> {
+    let mut groups: BTreeMap<String, Vec<&Item>> = BTreeMap::new();
+    for item in items {
+        if let Some(domain) = item.domain() {
+            groups.entry(domain).or_default().push(item);
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn story(id: u32, url: &str) -> Item {
+        serde_json::from_value(serde_json::json!({
+            "by": "a", "id": id, "time": 0, "title": "t", "url": url, "type": "story"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_domains() {
+        let items = vec![
+            story(1, "https://www.github.com/rust-lang"),
+            story(2, "https://gist.github.com/x"),
+            story(3, "https://notgithub.com/"),
+            story(4, ""),
+        ];
+        assert_eq!(items[0].domain().as_deref(), Some("github.com"));
+        assert_eq!(items[3].domain(), None);
+
+        let ids: Vec<_> = filter_by_domain(&items, "github.com")
+            .iter()
+            .map(|item| item.id().0)
+            .collect();
+        assert_eq!(ids, vec![1, 2]);
+
+        let groups = group_by_domain(&items);
+        assert_eq!(groups["notgithub.com"].len(), 1);
+        #[cfg(feature = "public-suffix")]
+        assert_eq!(groups["github.com"].len(), 2);
+        #[cfg(not(feature = "public-suffix"))]
+        assert_eq!(groups["gist.github.com"].len(), 1);
+    }
+}
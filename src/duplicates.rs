@@ -0,0 +1,119 @@
+//! Detection of stories linking to the same page.
+//!
+//! ```rust,no_run
+//! use hn_api::nonblocking::HnClient;
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! for story in client.find_duplicates("https://www.example.com/post?utm_source=hn").await? {
+//!     println!("already submitted as {}", story.id());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashSet;
+
+use url::Url;
+
+use super::{
+    nonblocking::HnClient,
+    types::{Item, ItemFilter, StoryList},
+    Result,
+};
+
+/// Query parameters that only track where a visitor came from.
+const TRACKING_PARAMS: [&str; 11] = [
+    "fbclid", "gclid", "dclid", "msclkid", "mc_cid", "mc_eid", "igshid", "ref", "ref_src",
+    "_hsenc", "_hsmi",
+];
+
+/// The listings searched by [`HnClient::find_duplicates`].
+const SEARCHED_LISTS: [StoryList; 3] = [StoryList::New, StoryList::Top, StoryList::Best];
+
+/// Normalize a URL so that links to the same page compare equal.
+///
+/// The scheme becomes `https`, the host loses a `www.` prefix, and the
+/// fragment, a trailing slash and tracking parameters like `utm_source` are
+/// removed. Returns `None` if the URL cannot be parsed.
+pub fn canonicalize_url(url: &str) -> Option<String> {
+    let mut url = Url::parse(url.trim()).ok()?;
+    if url.scheme() == "http" {
+        url.set_scheme("https").ok()?;
+    }
+    if let Some(host) = url.host_str().and_then(|host| host.strip_prefix("www.")) {
+        let host = host.to_string();
+        url.set_host(Some(&host)).ok()?;
+    }
+    if url.port() == Some(80) || url.port() == Some(443) {
+        url.set_port(None).ok()?;
+    }
+    url.set_fragment(None);
+    let query: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&key.as_ref()))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    if query.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(query);
+    }
+    let path = url.path().trim_end_matches('/').to_string();
+    url.set_path(&path);
+    let mut url = String::from(url);
+    if url.ends_with('/') {
+        url.pop();
+    }
+    Some(url)
+}
+
+impl HnClient {
+    /// Return the stories linking to the same page as `url`, oldest first.
+    ///
+    /// URLs are compared after [`canonicalize_url`]. Only the new, top and
+    /// best stories are searched, so older submissions are not found.
+    /// Fails if any of the request failed.
+    pub async fn find_duplicates(&self, url: &str) -> Result<Vec<Item>> {
+        let target = match canonicalize_url(url) {
+            Some(target) => target,
+            None => return Ok(Vec::new()),
+        };
+        let mut seen = HashSet::new();
+        let mut ids = Vec::new();
+        for list in SEARCHED_LISTS {
+            let list = self.get_stories(list).await?;
+            ids.extend(list.into_iter().filter(|id| seen.insert(*id)));
+        }
+        let mut duplicates: Vec<Item> = self
+            .get_items_filtered(&ids, ItemFilter::ALIVE)
+            .await?
+            .into_iter()
+            .filter(|item| item.url().and_then(canonicalize_url).as_ref() == Some(&target))
+            .collect();
+        duplicates.sort_by_key(|item| (item.time(), item.id()));
+        Ok(duplicates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonicalize_url() {
+        let canonical = Some("https://example.com/post?id=1".to_string());
+        for url in [
+            "https://example.com/post?id=1",
+            "http://www.example.com/post/?id=1",
+            "https://EXAMPLE.com:443/post?utm_source=hn&id=1&fbclid=x#comments",
+        ] {
+            assert_eq!(canonicalize_url(url), canonical, "{}", url);
+        }
+        assert_eq!(
+            canonicalize_url("https://example.com/"),
+            Some("https://example.com".to_string())
+        );
+        assert_eq!(canonicalize_url("not a url"), None);
+    }
+}
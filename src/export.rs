@@ -0,0 +1,441 @@
+//! Writers for archiving items and users.
+//!
+//! JSON Lines output writes one JSON document per line, in the format used
+//! by the API. CSV output requires the `csv` feature and writes a header row
+//! followed by one row per item or user, with a configurable selection of
+//! columns. Lists like `kids` are written as space separated ids.
+//!
+//! The `arrow` feature converts items and users into Apache Arrow record
+//! batches, and the `parquet` feature writes them as Parquet files, e.g. to
+//! load them with pandas or polars. Both use a fixed schema, see
+//! [`item_schema`] and [`user_schema`].
+
+use std::io::{self, Write};
+#[cfg(feature = "arrow")]
+use std::sync::Arc;
+
+#[cfg(feature = "arrow")]
+use arrow_array::{
+    builder::{ListBuilder, UInt32Builder},
+    ArrayRef, BooleanArray, RecordBatch, StringArray, TimestampSecondArray, UInt32Array,
+};
+#[cfg(feature = "arrow")]
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use serde::Serialize;
+
+use super::types::{Item, ItemId, User};
+
+/// A column of item CSV output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ItemColumn {
+    /// The item's unique id.
+    Id,
+    /// The item type, e.g. `story`.
+    Type,
+    /// The username of the item's author.
+    By,
+    /// Creation date of the item, in Unix Time.
+    Time,
+    /// The title of a story, job or poll.
+    Title,
+    /// The URL of a story or job.
+    Url,
+    /// The item text. HTML.
+    Text,
+    /// The score of a story, job, poll or poll option.
+    Score,
+    /// The total comment count of a story or poll.
+    Descendants,
+    /// The parent of a comment or poll option.
+    Parent,
+    /// The ids of the item's comments.
+    Kids,
+    /// Whether the item is dead.
+    Dead,
+    /// Whether the item was deleted.
+    Deleted,
+}
+
+impl ItemColumn {
+    /// All item columns.
+    pub const ALL: [ItemColumn; 13] = [
+        ItemColumn::Id,
+        ItemColumn::Type,
+        ItemColumn::By,
+        ItemColumn::Time,
+        ItemColumn::Title,
+        ItemColumn::Url,
+        ItemColumn::Text,
+        ItemColumn::Score,
+        ItemColumn::Descendants,
+        ItemColumn::Parent,
+        ItemColumn::Kids,
+        ItemColumn::Dead,
+        ItemColumn::Deleted,
+    ];
+
+    /// Return the header name of this column.
+    pub fn name(self) -> &'static str {
+        match self {
+            ItemColumn::Id => "id",
+            ItemColumn::Type => "type",
+            ItemColumn::By => "by",
+            ItemColumn::Time => "time",
+            ItemColumn::Title => "title",
+            ItemColumn::Url => "url",
+            ItemColumn::Text => "text",
+            ItemColumn::Score => "score",
+            ItemColumn::Descendants => "descendants",
+            ItemColumn::Parent => "parent",
+            ItemColumn::Kids => "kids",
+            ItemColumn::Dead => "dead",
+            ItemColumn::Deleted => "deleted",
+        }
+    }
+
+    /// Return the value of this column for `item`. Empty if not available.
+    pub fn value(self, item: &Item) -> String {
+        let optional = |value: Option<u32>| value.map(|v| v.to_string()).unwrap_or_default();
+        match self {
+            ItemColumn::Id => item.id().to_string(),
+            ItemColumn::Type => item.kind().to_string(),
+            ItemColumn::By => item.author().unwrap_or_default().to_string(),
+            ItemColumn::Time => item.time().as_secs().to_string(),
+            ItemColumn::Title => item.title().unwrap_or_default().to_string(),
+            ItemColumn::Url => item.url().unwrap_or_default().to_string(),
+            ItemColumn::Text => item.text().unwrap_or_default().to_string(),
+            ItemColumn::Score => optional(item.score()),
+            ItemColumn::Descendants => optional(item.descendants()),
+            ItemColumn::Parent => optional(item.parent().map(u32::from)),
+            ItemColumn::Kids => join_ids(item.kids()),
+            ItemColumn::Dead => item.is_dead().to_string(),
+            ItemColumn::Deleted => item.is_deleted().to_string(),
+        }
+    }
+}
+
+/// A column of user CSV output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UserColumn {
+    /// The user's unique username.
+    Id,
+    /// Creation date of the user, in Unix Time.
+    Created,
+    /// The user's karma.
+    Karma,
+    /// The user's comment delay in minutes.
+    Delay,
+    /// The user's self-description. HTML.
+    About,
+    /// The ids of the user's stories, polls and comments.
+    Submitted,
+}
+
+impl UserColumn {
+    /// All user columns.
+    pub const ALL: [UserColumn; 6] = [
+        UserColumn::Id,
+        UserColumn::Created,
+        UserColumn::Karma,
+        UserColumn::Delay,
+        UserColumn::About,
+        UserColumn::Submitted,
+    ];
+
+    /// Return the header name of this column.
+    pub fn name(self) -> &'static str {
+        match self {
+            UserColumn::Id => "id",
+            UserColumn::Created => "created",
+            UserColumn::Karma => "karma",
+            UserColumn::Delay => "delay",
+            UserColumn::About => "about",
+            UserColumn::Submitted => "submitted",
+        }
+    }
+
+    /// Return the value of this column for `user`. Empty if not available.
+    pub fn value(self, user: &User) -> String {
+        match self {
+            UserColumn::Id => user.id.to_string(),
+            UserColumn::Created => user.created.as_secs().to_string(),
+            UserColumn::Karma => user.karma.to_string(),
+            UserColumn::Delay => user.delay.map(|d| d.to_string()).unwrap_or_default(),
+            UserColumn::About => user.about.clone().unwrap_or_default(),
+            UserColumn::Submitted => join_ids(&user.submitted),
+        }
+    }
+}
+
+/// Write items as JSON Lines.
+pub fn write_items_jsonl<W: Write>(writer: W, items: &[Item]) -> io::Result<()> {
+    write_jsonl(writer, items)
+}
+
+/// Write users as JSON Lines.
+pub fn write_users_jsonl<W: Write>(writer: W, users: &[User]) -> io::Result<()> {
+    write_jsonl(writer, users)
+}
+
+/// Write items as CSV with the selected columns.
+#[cfg(feature = "csv")]
+pub fn write_items_csv<W: Write>(
+    writer: W,
+    items: &[Item],
+    columns: &[ItemColumn],
+) -> io::Result<()> {
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record(columns.iter().map(|column| column.name()))?;
+    for item in items {
+        csv.write_record(columns.iter().map(|column| column.value(item)))?;
+    }
+    csv.flush()
+}
+
+/// Write users as CSV with the selected columns.
+#[cfg(feature = "csv")]
+pub fn write_users_csv<W: Write>(
+    writer: W,
+    users: &[User],
+    columns: &[UserColumn],
+) -> io::Result<()> {
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record(columns.iter().map(|column| column.name()))?;
+    for user in users {
+        csv.write_record(columns.iter().map(|column| column.value(user)))?;
+    }
+    csv.flush()
+}
+
+/// Return the Arrow schema of item record batches: one column per
+/// [`ItemColumn`], with the same names. `time` is a UTC timestamp in seconds
+/// and `kids` a list of ids. Values that are not available are null.
+#[cfg(feature = "arrow")]
+pub fn item_schema() -> SchemaRef {
+    let column =
+        |column: ItemColumn, data_type, nullable| Field::new(column.name(), data_type, nullable);
+    Arc::new(Schema::new(vec![
+        column(ItemColumn::Id, DataType::UInt32, false),
+        column(ItemColumn::Type, DataType::Utf8, false),
+        column(ItemColumn::By, DataType::Utf8, true),
+        column(ItemColumn::Time, timestamp_type(), false),
+        column(ItemColumn::Title, DataType::Utf8, true),
+        column(ItemColumn::Url, DataType::Utf8, true),
+        column(ItemColumn::Text, DataType::Utf8, true),
+        column(ItemColumn::Score, DataType::UInt32, true),
+        column(ItemColumn::Descendants, DataType::UInt32, true),
+        column(ItemColumn::Parent, DataType::UInt32, true),
+        column(ItemColumn::Kids, list_type(), false),
+        column(ItemColumn::Dead, DataType::Boolean, false),
+        column(ItemColumn::Deleted, DataType::Boolean, false),
+    ]))
+}
+
+/// Return the Arrow schema of user record batches: one column per
+/// [`UserColumn`], with the same names. `created` is a UTC timestamp in
+/// seconds and `submitted` a list of ids.
+#[cfg(feature = "arrow")]
+pub fn user_schema() -> SchemaRef {
+    let column =
+        |column: UserColumn, data_type, nullable| Field::new(column.name(), data_type, nullable);
+    Arc::new(Schema::new(vec![
+        column(UserColumn::Id, DataType::Utf8, false),
+        column(UserColumn::Created, timestamp_type(), false),
+        column(UserColumn::Karma, DataType::UInt32, false),
+        column(UserColumn::Delay, DataType::UInt32, true),
+        column(UserColumn::About, DataType::Utf8, true),
+        column(UserColumn::Submitted, list_type(), false),
+    ]))
+}
+
+/// Convert items into a record batch with the [`item_schema`].
+#[cfg(feature = "arrow")]
+pub fn items_to_record_batch(items: &[Item]) -> RecordBatch {
+    let strings = |value: fn(&Item) -> Option<&str>| -> ArrayRef {
+        Arc::new(items.iter().map(value).collect::<StringArray>())
+    };
+    let numbers = |value: fn(&Item) -> Option<u32>| -> ArrayRef {
+        Arc::new(items.iter().map(value).collect::<UInt32Array>())
+    };
+    let flags = |value: fn(&Item) -> bool| -> ArrayRef {
+        Arc::new(
+            items
+                .iter()
+                .map(|item| Some(value(item)))
+                .collect::<BooleanArray>(),
+        )
+    };
+    let columns = vec![
+        numbers(|item| Some(item.id().0)),
+        strings(|item| Some(item.kind())),
+        strings(Item::author),
+        Arc::new(timestamps(items.iter().map(Item::time))) as ArrayRef,
+        strings(Item::title),
+        strings(Item::url),
+        strings(Item::text),
+        numbers(Item::score),
+        numbers(Item::descendants),
+        numbers(|item| item.parent().map(u32::from)),
+        id_lists(items.iter().map(Item::kids)),
+        flags(Item::is_dead),
+        flags(Item::is_deleted),
+    ];
+    RecordBatch::try_new(item_schema(), columns).expect("columns match the item schema")
+}
+
+/// Convert users into a record batch with the [`user_schema`].
+#[cfg(feature = "arrow")]
+pub fn users_to_record_batch(users: &[User]) -> RecordBatch {
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(
+            users
+                .iter()
+                .map(|user| Some(user.id.as_str()))
+                .collect::<StringArray>(),
+        ),
+        Arc::new(timestamps(users.iter().map(|user| user.created))),
+        Arc::new(
+            users
+                .iter()
+                .map(|user| Some(user.karma))
+                .collect::<UInt32Array>(),
+        ),
+        Arc::new(users.iter().map(|user| user.delay).collect::<UInt32Array>()),
+        Arc::new(
+            users
+                .iter()
+                .map(|user| user.about.as_deref())
+                .collect::<StringArray>(),
+        ),
+        id_lists(users.iter().map(|user| user.submitted.as_slice())),
+    ];
+    RecordBatch::try_new(user_schema(), columns).expect("columns match the user schema")
+}
+
+/// Write items as a Parquet file with the [`item_schema`].
+#[cfg(feature = "parquet")]
+pub fn write_items_parquet<W: Write + Send>(writer: W, items: &[Item]) -> io::Result<()> {
+    write_parquet(writer, items_to_record_batch(items))
+}
+
+/// Write users as a Parquet file with the [`user_schema`].
+#[cfg(feature = "parquet")]
+pub fn write_users_parquet<W: Write + Send>(writer: W, users: &[User]) -> io::Result<()> {
+    write_parquet(writer, users_to_record_batch(users))
+}
+
+#[cfg(feature = "parquet")]
+fn write_parquet<W: Write + Send>(writer: W, batch: RecordBatch) -> io::Result<()> {
+    let mut parquet = parquet::arrow::ArrowWriter::try_new(writer, batch.schema(), None)
+        .map_err(io::Error::other)?;
+    parquet.write(&batch).map_err(io::Error::other)?;
+    parquet.close().map_err(io::Error::other)?;
+    Ok(())
+}
+
+#[cfg(feature = "arrow")]
+fn timestamp_type() -> DataType {
+    DataType::Timestamp(TimeUnit::Second, Some("UTC".into()))
+}
+
+#[cfg(feature = "arrow")]
+fn list_type() -> DataType {
+    DataType::List(Arc::new(Field::new("item", DataType::UInt32, true)))
+}
+
+#[cfg(feature = "arrow")]
+fn timestamps(times: impl Iterator<Item = super::types::Timestamp>) -> TimestampSecondArray {
+    times
+        .map(|time| Some(time.as_secs() as i64))
+        .collect::<TimestampSecondArray>()
+        .with_timezone("UTC")
+}
+
+#[cfg(feature = "arrow")]
+fn id_lists<'a>(lists: impl Iterator<Item = &'a [ItemId]>) -> ArrayRef {
+    let mut builder = ListBuilder::new(UInt32Builder::new());
+    for ids in lists {
+        builder.values().extend(ids.iter().map(|id| Some(id.0)));
+        builder.append(true);
+    }
+    Arc::new(builder.finish())
+}
+
+pub(crate) fn write_jsonl<W: Write, T: Serialize>(mut writer: W, values: &[T]) -> io::Result<()> {
+    for value in values {
+        serde_json::to_writer(&mut writer, value)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+fn join_ids(ids: &[ItemId]) -> String {
+    ids.iter()
+        .map(ItemId::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn story() -> Item {
+        serde_json::from_str(
+            r#"{"by": "dhouston", "descendants": 71, "id": 8863, "kids": [9224, 8952], "score": 104, "time": 1175714200, "title": "My YC app: Dropbox", "type": "story", "url": "http://www.getdropbox.com/"}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_items_jsonl_round_trip() {
+        let mut out = Vec::new();
+        write_items_jsonl(&mut out, &[story(), story()]).unwrap();
+        let lines: Vec<_> = std::str::from_utf8(&out).unwrap().lines().collect();
+        assert_eq!(lines.len(), 2);
+        let item: Item = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(item, story());
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_items_csv() {
+        let mut out = Vec::new();
+        let columns = [
+            ItemColumn::Id,
+            ItemColumn::Title,
+            ItemColumn::Kids,
+            ItemColumn::Parent,
+        ];
+        write_items_csv(&mut out, &[story()], &columns).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "id,title,kids,parent\n8863,My YC app: Dropbox,9224 8952,\n"
+        );
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn test_items_record_batch() {
+        use arrow_array::{Array, ListArray};
+
+        let batch = items_to_record_batch(&[story()]);
+        assert_eq!(batch.schema(), item_schema());
+        assert_eq!(batch.num_rows(), 1);
+        let column = |name| batch.column_by_name(name).unwrap();
+        let ids = column("id").as_any().downcast_ref::<UInt32Array>().unwrap();
+        assert_eq!(ids.value(0), 8863);
+        assert!(column("parent").is_null(0));
+        let kids = column("kids").as_any().downcast_ref::<ListArray>().unwrap();
+        let kids = kids.value(0);
+        let kids = kids.as_any().downcast_ref::<UInt32Array>().unwrap();
+        assert_eq!(kids.values().to_vec(), vec![9224, 8952]);
+
+        #[cfg(feature = "parquet")]
+        {
+            let mut out = Vec::new();
+            write_items_parquet(&mut out, &[story()]).unwrap();
+            assert!(out.starts_with(b"PAR1"));
+        }
+    }
+}
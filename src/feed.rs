@@ -0,0 +1,203 @@
+//! RSS 2.0 and Atom feeds of story listings.
+//!
+//! Requires the `feed` feature.
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "nonblocking")]
+//! use hn_api::{feed::{self, FeedInfo}, nonblocking::HnClient, StoryList};
+//!
+//! # #[cfg(feature = "nonblocking")]
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let ids = client.get_top_stories().await?;
+//! let stories = client.get_items(&ids[..30]).await?;
+//! let rss = feed::rss(&FeedInfo::for_list(StoryList::Top), &stories);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::Write;
+
+use super::{
+    html::{escape, DateTime},
+    permalink,
+    types::{Item, StoryList, Timestamp},
+};
+
+/// Channel level information of a feed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedInfo {
+    /// The title of the feed.
+    pub title: String,
+    /// The URL of the website the feed belongs to.
+    pub link: String,
+    /// A short description of the feed.
+    pub description: String,
+}
+
+impl FeedInfo {
+    /// Return the information for a feed of a story listing.
+    pub fn for_list(list: StoryList) -> Self {
+        let name = match list {
+            StoryList::Top => "Top stories",
+            StoryList::New => "New stories",
+            StoryList::Best => "Best stories",
+            StoryList::Ask => "Ask HN",
+            StoryList::Show => "Show HN",
+            StoryList::Job => "Jobs",
+        };
+        Self {
+            title: format!("Hacker News: {}", name),
+            link: permalink::list_url(list),
+            description: format!("{} on Hacker News", name),
+        }
+    }
+}
+
+/// Return an RSS 2.0 document with one entry per item.
+///
+/// Entries link to the story URL, or to the discussion if there is none.
+pub fn rss(info: &FeedInfo, items: &[Item]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(
+        "<rss version=\"2.0\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n<channel>\n",
+    );
+    let _ = writeln!(out, "<title>{}</title>", escape(&info.title));
+    let _ = writeln!(out, "<link>{}</link>", escape(&info.link));
+    let _ = writeln!(
+        out,
+        "<description>{}</description>",
+        escape(&info.description)
+    );
+    for item in items {
+        let discussion = permalink::item_url(item.id());
+        out.push_str("<item>\n");
+        let _ = writeln!(
+            out,
+            "<title>{}</title>",
+            escape(item.title().unwrap_or_default())
+        );
+        let _ = writeln!(
+            out,
+            "<link>{}</link>",
+            escape(item.url().unwrap_or(&discussion))
+        );
+        let _ = writeln!(
+            out,
+            "<guid isPermaLink=\"true\">{}</guid>",
+            escape(&discussion)
+        );
+        let _ = writeln!(out, "<comments>{}</comments>", escape(&discussion));
+        let _ = writeln!(out, "<pubDate>{}</pubDate>", rfc822(item.time()));
+        if let Some(author) = item.author() {
+            let _ = writeln!(out, "<dc:creator>{}</dc:creator>", escape(author));
+        }
+        if let Some(text) = item.text() {
+            let _ = writeln!(out, "<description>{}</description>", escape(text));
+        }
+        out.push_str("</item>\n");
+    }
+    out.push_str("</channel>\n</rss>\n");
+    out
+}
+
+/// Return an Atom document with one entry per item.
+///
+/// Entries link to the story URL, or to the discussion if there is none.
+pub fn atom(info: &FeedInfo, items: &[Item]) -> String {
+    let updated = items.iter().map(Item::time).max().unwrap_or_default();
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    let _ = writeln!(out, "<title>{}</title>", escape(&info.title));
+    let _ = writeln!(out, "<subtitle>{}</subtitle>", escape(&info.description));
+    let _ = writeln!(out, "<link href=\"{}\"/>", escape(&info.link));
+    let _ = writeln!(out, "<id>{}</id>", escape(&info.link));
+    let _ = writeln!(out, "<updated>{}</updated>", rfc3339(updated));
+    out.push_str("<author><name>Hacker News</name></author>\n");
+    for item in items {
+        let discussion = permalink::item_url(item.id());
+        out.push_str("<entry>\n");
+        let _ = writeln!(
+            out,
+            "<title>{}</title>",
+            escape(item.title().unwrap_or_default())
+        );
+        let _ = writeln!(
+            out,
+            "<link href=\"{}\"/>",
+            escape(item.url().unwrap_or(&discussion))
+        );
+        let _ = writeln!(
+            out,
+            "<link rel=\"replies\" href=\"{}\"/>",
+            escape(&discussion)
+        );
+        let _ = writeln!(out, "<id>{}</id>", escape(&discussion));
+        let _ = writeln!(out, "<updated>{}</updated>", rfc3339(item.time()));
+        if let Some(author) = item.author() {
+            let _ = writeln!(out, "<author><name>{}</name></author>", escape(author));
+        }
+        if let Some(text) = item.text() {
+            let _ = writeln!(out, "<content type=\"html\">{}</content>", escape(text));
+        }
+        out.push_str("</entry>\n");
+    }
+    out.push_str("</feed>\n");
+    out
+}
+
+fn rfc822(timestamp: Timestamp) -> String {
+    const DAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let dt = DateTime::from(timestamp);
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} +0000",
+        DAYS[dt.weekday],
+        dt.day,
+        MONTHS[dt.month as usize - 1],
+        dt.year,
+        dt.hour,
+        dt.minute,
+        dt.second
+    )
+}
+
+fn rfc3339(timestamp: Timestamp) -> String {
+    let dt = DateTime::from(timestamp);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        dt.year, dt.month, dt.day, dt.hour, dt.minute, dt.second
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dates() {
+        assert_eq!(rfc3339(Timestamp(0)), "1970-01-01T00:00:00Z");
+        assert_eq!(rfc3339(Timestamp(1175714200)), "2007-04-04T19:16:40Z");
+        assert_eq!(
+            rfc822(Timestamp(1175714200)),
+            "Wed, 04 Apr 2007 19:16:40 +0000"
+        );
+        assert_eq!(rfc3339(Timestamp(951782400)), "2000-02-29T00:00:00Z");
+    }
+
+    #[test]
+    fn test_rss() {
+        let story: Item = serde_json::from_str(
+            r#"{"by": "pg", "id": 1, "score": 1, "time": 0, "title": "Tom & Jerry <3", "type": "story"}"#,
+        )
+        .unwrap();
+        let rss = rss(&FeedInfo::for_list(StoryList::Top), &[story]);
+        assert!(rss.contains("<title>Tom &amp; Jerry &lt;3</title>"));
+        assert!(rss.contains("<link>https://news.ycombinator.com/item?id=1</link>"));
+        assert!(rss.contains("<dc:creator>pg</dc:creator>"));
+    }
+}
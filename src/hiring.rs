@@ -0,0 +1,252 @@
+//! The monthly "Ask HN: Who is hiring?" threads.
+//!
+//! The threads are posted by the `whoishiring` account. Every top-level
+//! comment is a job posting, which by convention starts with a line like
+//! `Acme | Berlin, Germany | ONSITE or REMOTE | Senior Rust Engineer`.
+//!
+//! ```rust,no_run
+//! use hn_api::nonblocking::HnClient;
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let thread = client.get_latest_hiring_thread().await?;
+//! for posting in client.get_job_postings(thread.id()).await? {
+//!     println!("{}: {:?}", posting.company, posting.role);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{
+    html,
+    nonblocking::HnClient,
+    types::{Comment, Item, ItemFilter, ItemId, Timestamp, Username},
+    HnClientError, Result,
+};
+
+/// The account posting the monthly hiring threads.
+pub const HIRING_ACCOUNT: &str = "whoishiring";
+
+/// The title prefix of the hiring threads.
+const HIRING_TITLE: &str = "Ask HN: Who is hiring?";
+
+/// How many of the newest submissions of the account are searched.
+const SEARCHED_SUBMISSIONS: usize = 30;
+
+/// Whether a job is done on site, remotely or both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkMode {
+    /// At the office only.
+    Onsite,
+    /// Remotely only.
+    Remote,
+    /// Remotely or at the office.
+    Hybrid,
+}
+
+/// A job posting parsed from a top-level comment of a hiring thread.
+///
+/// Parsing is best-effort: postings that do not follow the convention end
+/// up with the whole first line as `company`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobPosting {
+    /// The id of the comment.
+    pub id: ItemId,
+    /// The author of the comment.
+    pub by: Username,
+    /// Creation date of the comment.
+    pub time: Timestamp,
+    /// The company name, the first field of the header line.
+    pub company: String,
+    /// The location, if the header names one.
+    pub location: Option<String>,
+    /// Whether the job is remote, if the header says so.
+    pub work_mode: Option<WorkMode>,
+    /// The field of the header naming the open positions.
+    pub role: Option<String>,
+    /// The header line, as plain text.
+    pub header: String,
+    /// The text after the header line, as plain text.
+    pub body: String,
+}
+
+impl JobPosting {
+    /// Parse a top-level comment of a hiring thread.
+    pub fn parse(comment: &Comment) -> Self {
+        let text = html::plain_text(&comment.text);
+        let (header, body) = text.split_once("\n\n").unwrap_or((&text, ""));
+        let header = header.trim().to_string();
+        let mut fields = header.split('|').map(str::trim).filter(|f| !f.is_empty());
+        let company = fields.next().unwrap_or_default().to_string();
+        let (mut location, mut work_mode, mut role) = (None, None, None);
+        for field in fields {
+            if let Some(mode) = work_mode_of(field) {
+                work_mode = work_mode.or(Some(mode));
+                if is_mode_only(field) {
+                    continue;
+                }
+            }
+            if role.is_none() && is_role(field) {
+                role = Some(field.to_string());
+            } else if location.is_none() && is_location(field) {
+                location = Some(field.to_string());
+            }
+        }
+        Self {
+            id: comment.id,
+            by: comment.by.clone(),
+            time: comment.time,
+            company,
+            location,
+            work_mode,
+            role,
+            header,
+            body: body.trim().to_string(),
+        }
+    }
+
+    /// Return whether the posting offers remote work.
+    pub fn is_remote(&self) -> bool {
+        matches!(self.work_mode, Some(WorkMode::Remote | WorkMode::Hybrid))
+    }
+}
+
+fn work_mode_of(field: &str) -> Option<WorkMode> {
+    let field = field.to_lowercase();
+    let remote = field.contains("remote");
+    let onsite = ["onsite", "on-site", "on site", "in office", "in-office"]
+        .iter()
+        .any(|word| field.contains(word));
+    match (remote, onsite || field.contains("hybrid")) {
+        (true, true) => Some(WorkMode::Hybrid),
+        (true, false) if field.contains("no remote") => Some(WorkMode::Onsite),
+        (true, false) => Some(WorkMode::Remote),
+        (false, true) if field.contains("hybrid") => Some(WorkMode::Hybrid),
+        (false, true) => Some(WorkMode::Onsite),
+        (false, false) => None,
+    }
+}
+
+/// Return whether a field only states the work mode, e.g. `REMOTE or ONSITE`.
+fn is_mode_only(field: &str) -> bool {
+    const WORDS: [&str; 12] = [
+        "remote", "onsite", "on-site", "on", "site", "hybrid", "or", "and", "/", "only", "no",
+        "(us)",
+    ];
+    field
+        .to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|word| !word.is_empty())
+        .all(|word| WORDS.contains(&word) || word.chars().all(|c| !c.is_alphanumeric()))
+}
+
+fn is_role(field: &str) -> bool {
+    const WORDS: [&str; 14] = [
+        "engineer",
+        "developer",
+        "designer",
+        "scientist",
+        "manager",
+        "lead",
+        "architect",
+        "devops",
+        "sre",
+        "analyst",
+        "researcher",
+        "intern",
+        "head of",
+        "programmer",
+    ];
+    let field = field.to_lowercase();
+    WORDS.iter().any(|word| field.contains(word))
+}
+
+fn is_location(field: &str) -> bool {
+    let field = field.to_lowercase();
+    !(field.contains("http")
+        || field.contains("www.")
+        || field.contains('$')
+        || field.contains('€')
+        || field.contains('£')
+        || field.contains("visa")
+        || field.contains("full-time")
+        || field.contains("full time")
+        || field.contains("part-time"))
+}
+
+/// Return whether an item is a hiring thread.
+pub fn is_hiring_thread(item: &Item) -> bool {
+    matches!(item, Item::Story(story) if story.title.starts_with(HIRING_TITLE))
+}
+
+impl HnClient {
+    /// Return the newest "Ask HN: Who is hiring?" thread.
+    ///
+    /// Searches the 30 newest submissions of the `whoishiring` account.
+    /// Fails with `BackendError` if none of them is a hiring thread.
+    pub async fn get_latest_hiring_thread(&self) -> Result<Item> {
+        let user = self.get_user(HIRING_ACCOUNT).await?;
+        let recent = &user.submitted[..user.submitted.len().min(SEARCHED_SUBMISSIONS)];
+        self.get_items_filtered(recent, ItemFilter::ALIVE)
+            .await?
+            .into_iter()
+            .find(is_hiring_thread)
+            .ok_or_else(|| HnClientError::BackendError("no hiring thread found".to_string()))
+    }
+
+    /// Return the job postings of a hiring thread, in ranked display order.
+    ///
+    /// Dead and deleted postings are skipped.
+    /// Fails if any of the request failed.
+    pub async fn get_job_postings(&self, thread: impl Into<ItemId>) -> Result<Vec<JobPosting>> {
+        let thread = self.get_item(thread).await?;
+        Ok(self
+            .get_items_filtered(thread.kids(), ItemFilter::ALIVE)
+            .await?
+            .iter()
+            .filter_map(|item| match item {
+                Item::Comment(comment) => Some(JobPosting::parse(comment)),
+                _ => None,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> JobPosting {
+        let comment: Comment = serde_json::from_value(serde_json::json!({
+            "by": "acme", "id": 2, "parent": 1, "time": 0, "text": text, "type": "comment"
+        }))
+        .unwrap();
+        JobPosting::parse(&comment)
+    }
+
+    #[test]
+    fn test_parse_posting() {
+        let posting = parse(
+            "Acme Corp | Senior Rust Engineer | Berlin, Germany | ONSITE or REMOTE (EU) | \
+             https:&#x2F;&#x2F;acme.example<p>We build rockets.<p>Apply at jobs@acme.example",
+        );
+        assert_eq!(posting.company, "Acme Corp");
+        assert_eq!(posting.role.as_deref(), Some("Senior Rust Engineer"));
+        assert_eq!(posting.location.as_deref(), Some("Berlin, Germany"));
+        assert_eq!(posting.work_mode, Some(WorkMode::Hybrid));
+        assert!(posting.is_remote());
+        assert_eq!(
+            posting.body,
+            "We build rockets.\n\nApply at jobs@acme.example"
+        );
+
+        let posting = parse("Initech | NYC | Onsite | $150k<p>TPS reports.");
+        assert_eq!(posting.location.as_deref(), Some("NYC"));
+        assert_eq!(posting.work_mode, Some(WorkMode::Onsite));
+        assert_eq!(posting.role, None);
+
+        let posting = parse("We are hiring!");
+        assert_eq!(posting.company, "We are hiring!");
+        assert_eq!(posting.body, "");
+    }
+}
@@ -0,0 +1,144 @@
+//! Run callbacks on new activity, e.g. for quick bots.
+//!
+//! [`HnClient::on_new_story`] subscribes to the
+//! [new stories](crate::bus::SubscriptionKind::NewStories) of a client and
+//! runs a callback for every story, several at a time, until the returned
+//! [`HookHandle`] is stopped or dropped, or the client is
+//! [shut down](crate::shutdown).
+//!
+//! ```rust,no_run
+//! use hn_api::nonblocking::HnClient;
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let hook = client.on_new_story(|story| async move {
+//!     println!("{}", story.title().unwrap_or_default());
+//! });
+//! // Later:
+//! hook.stop().await;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::future::Future;
+
+use futures::{channel::oneshot, stream, StreamExt};
+use tokio::sync::broadcast::error::RecvError;
+
+use super::{
+    bus::{HnEvent, SubscriptionKind},
+    nonblocking::HnClient,
+    types::Item,
+};
+
+/// The number of callbacks [`HnClient::on_new_story`] runs at the same time.
+const DEFAULT_CONCURRENCY: usize = 8;
+
+/// A handle to callbacks running in the background, see
+/// [`HnClient::on_new_story`].
+///
+/// Dropping the handle stops starting new callbacks. Callbacks that are
+/// already running finish.
+#[derive(Debug)]
+pub struct HookHandle {
+    stop: oneshot::Sender<()>,
+    stopped: oneshot::Receiver<()>,
+}
+
+impl HookHandle {
+    /// Stop starting new callbacks, and wait until the running ones are
+    /// finished.
+    pub async fn stop(self) {
+        let _ = self.stop.send(());
+        let _ = self.stopped.await;
+    }
+}
+
+impl HnClient {
+    /// Run `callback` for every new story in the background, at most 8 at a
+    /// time.
+    ///
+    /// Stories are those appearing on the new stories listing after the
+    /// first poll, see [`HnClient::subscribe`]. Failed polls are skipped.
+    /// Must be called within a Tokio runtime, unless the `runtime-agnostic`
+    /// feature is enabled.
+    pub fn on_new_story<F, Fut>(&self, callback: F) -> HookHandle
+    where
+        F: FnMut(Item) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.on_new_story_with_concurrency(DEFAULT_CONCURRENCY, callback)
+    }
+
+    /// Run `callback` for every new story in the background, at most
+    /// `max_concurrent` at a time, see [`HnClient::on_new_story`].
+    pub fn on_new_story_with_concurrency<F, Fut>(
+        &self,
+        max_concurrent: usize,
+        callback: F,
+    ) -> HookHandle
+    where
+        F: FnMut(Item) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let receiver = self.subscribe(SubscriptionKind::NewStories);
+        let (stop, stop_requested) = oneshot::channel();
+        let (stopped, stopped_receiver) = oneshot::channel();
+        self.spawn(async move {
+            let stories = stream::unfold(receiver, |mut receiver| async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(Ok(HnEvent::NewStory(story))) => return Some((story, receiver)),
+                        Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            });
+            stories
+                .take_until(stop_requested)
+                .for_each_concurrent(max_concurrent, callback)
+                .await;
+            let _ = stopped.send(());
+        });
+        HookHandle {
+            stop,
+            stopped: stopped_receiver,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{cassette::Cassette, types::ItemId};
+
+    #[tokio::test]
+    async fn test_on_new_story() {
+        let api = "https://hacker-news.firebaseio.com/v0";
+        let mut cassette = Cassette::new();
+        cassette
+            .push(format!("{}/newstories.json", api), "[1]")
+            .push(format!("{}/newstories.json", api), "[2, 1]")
+            .push(
+                format!("{}/item/2.json", api),
+                r#"{"id": 2, "score": 1, "time": 0, "type": "story"}"#,
+            );
+        let client = HnClient::builder()
+            .replay(cassette)
+            .subscription_interval(Duration::from_millis(1))
+            .build()
+            .unwrap();
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(1);
+        let hook = client.on_new_story(move |story| {
+            let sender = sender.clone();
+            async move {
+                let _ = sender.send(story.id()).await;
+            }
+        });
+        assert_eq!(receiver.recv().await, Some(ItemId(2)));
+        hook.stop().await;
+        assert_eq!(receiver.recv().await, None);
+    }
+}
@@ -0,0 +1,184 @@
+//! Helpers to extract data from pages of the Hacker News website, and to
+//! produce HTML and XML output.
+
+use super::types::ItemId;
+#[cfg(any(feature = "feed", feature = "render"))]
+use super::types::Timestamp;
+
+/// Return the ids of the item rows (`<tr class="athing" id="...">`) of a
+/// listing page, in page order.
+pub(crate) fn parse_listing_ids(html: &str) -> Vec<ItemId> {
+    html.split("<tr")
+        .skip(1)
+        .filter_map(|row| {
+            let tag = &row[..row.find('>')?];
+            if !tag.contains("athing") {
+                return None;
+            }
+            let id = &tag[tag.find("id=")? + "id=".len()..];
+            let id = id.trim_start_matches(['\'', '"']);
+            let end = id.find(|c: char| !c.is_ascii_digit()).unwrap_or(id.len());
+            id[..end].parse().ok()
+        })
+        .collect()
+}
+
+/// Return the decoded `href` targets of the anchors in an item text, in
+/// text order.
+pub(crate) fn link_targets(html: &str) -> Vec<String> {
+    html.split("<a ")
+        .skip(1)
+        .filter_map(|anchor| {
+            let tag = &anchor[..anchor.find('>')?];
+            let href = &tag[tag.find("href=")? + "href=".len()..];
+            let quote = href.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+            let href = &href[1..];
+            Some(decode_entities(&href[..href.find(quote)?]))
+        })
+        .collect()
+}
+
+/// Convert the HTML of an item text to plain text, separating paragraphs
+/// with blank lines.
+pub(crate) fn plain_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.replace("<p>", "\n\n").chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    decode_entities(&text)
+}
+
+/// Decode the named and numeric character references used by the API.
+pub(crate) fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => '\u{a0}',
+                _ => match entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                {
+                    Some(hex) => char::from_u32(u32::from_str_radix(hex, 16).ok()?)?,
+                    None => char::from_u32(entity.strip_prefix('#')?.parse().ok()?)?,
+                },
+            };
+            Some((c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Escape text for use in XML content and attribute values.
+#[cfg(any(feature = "feed", feature = "render"))]
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// The calendar date and time of a timestamp, in UTC.
+#[cfg(any(feature = "feed", feature = "render"))]
+#[cfg_attr(not(feature = "feed"), allow(dead_code))]
+pub(crate) struct DateTime {
+    pub(crate) year: i64,
+    pub(crate) month: u32,
+    pub(crate) day: u32,
+    pub(crate) hour: u64,
+    pub(crate) minute: u64,
+    pub(crate) second: u64,
+    pub(crate) weekday: usize,
+}
+
+#[cfg(any(feature = "feed", feature = "render"))]
+impl From<Timestamp> for DateTime {
+    fn from(timestamp: Timestamp) -> Self {
+        // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+        let secs = timestamp.as_secs();
+        let days = (secs / 86400) as i64;
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        Self {
+            year,
+            month,
+            day,
+            hour: secs % 86400 / 3600,
+            minute: secs % 3600 / 60,
+            second: secs % 60,
+            // 1970-01-01 was a Thursday.
+            weekday: ((days + 4) % 7) as usize,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(
+            decode_entities("it&#x27;s &lt;3 &amp; &#47; &bogus; &"),
+            "it's <3 & / &bogus; &"
+        );
+    }
+
+    #[test]
+    fn test_link_targets() {
+        let html = r#"See <a href="https:&#x2F;&#x2F;example.com&#x2F;a?b=1&amp;c=2" rel="nofollow">https:&#x2F;&#x2F;example.com&#x2F;a?b=1&amp;c...</a><p><a href='item?id=1'>x</a> <a name="x">"#;
+        assert_eq!(
+            link_targets(html),
+            vec!["https://example.com/a?b=1&c=2", "item?id=1"]
+        );
+    }
+
+    #[test]
+    fn test_parse_listing_ids() {
+        let html = r#"<table><tr class='athing' id='8863'><td>1.</td></tr>
+            <tr><td class="subtext">104 points</td></tr>
+            <tr class="athing submission" id="121003"><td>2.</td></tr></table>"#;
+        assert_eq!(parse_listing_ids(html), vec![8863, 121003]);
+    }
+}
@@ -0,0 +1,50 @@
+//! Replies to a user's items, which the API does not list directly.
+//!
+//! ```rust,no_run
+//! use hn_api::{nonblocking::HnClient, Timestamp};
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! for reply in client.get_replies_to("pg", Timestamp(1700000000)).await? {
+//!     println!("{} replied: {}", reply.author().unwrap_or("?"), reply.text().unwrap_or(""));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{
+    nonblocking::HnClient,
+    types::{Item, ItemFilter, ItemId, Timestamp},
+    Result,
+};
+
+/// How many of the user's newest submissions are checked for replies.
+const RECENT_SUBMISSIONS: usize = 30;
+
+impl HnClient {
+    /// Return the comments created at or after `since` that directly reply
+    /// to one of the user's 30 newest stories, comments or polls, newest
+    /// first.
+    ///
+    /// Dead and deleted replies and replies by the user are skipped.
+    /// May return error if username is invalid.
+    /// Fails if any of the request failed.
+    pub async fn get_replies_to(&self, username: &str, since: Timestamp) -> Result<Vec<Item>> {
+        let user = self.get_user(username).await?;
+        let recent = &user.submitted[..user.submitted.len().min(RECENT_SUBMISSIONS)];
+        let kids: Vec<ItemId> = self
+            .get_items_filtered(recent, ItemFilter::ALIVE)
+            .await?
+            .iter()
+            .flat_map(|item| item.kids().iter().copied())
+            .collect();
+        let mut replies: Vec<Item> = self
+            .get_items_filtered(&kids, ItemFilter::ALIVE)
+            .await?
+            .into_iter()
+            .filter(|reply| reply.time() >= since && reply.author() != Some(username))
+            .collect();
+        replies.sort_by_key(|reply| std::cmp::Reverse((reply.time(), reply.id())));
+        Ok(replies)
+    }
+}
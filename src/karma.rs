@@ -0,0 +1,164 @@
+//! Karma history of a set of users, and leaderboards built from it.
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use futures::StreamExt;
+//! use hn_api::{
+//!     karma::{leaderboard, RankBy},
+//!     nonblocking::HnClient,
+//! };
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let snapshots: Vec<_> = client
+//!     .sample_karma(&["pg", "dang", "tptacek"], Duration::from_secs(3600))
+//!     .take(24)
+//!     .filter_map(|snapshot| async { snapshot.ok() })
+//!     .collect()
+//!     .await;
+//! for entry in leaderboard(&snapshots, RankBy::Growth) {
+//!     println!("{}. {} (+{})", entry.rank, entry.username, entry.growth);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::BTreeMap, time::Duration};
+
+use futures::stream::{self, Stream};
+
+use super::{
+    nonblocking::HnClient,
+    runtime,
+    types::{Timestamp, Username},
+    Result,
+};
+
+/// The karma of a set of users at one point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KarmaSnapshot {
+    /// When the profiles were fetched.
+    pub time: Timestamp,
+    /// The karma of every user.
+    pub karma: BTreeMap<Username, u32>,
+}
+
+/// What a [`leaderboard`] ranks users by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RankBy {
+    /// The karma in the last snapshot.
+    Karma,
+    /// The karma gained between the first and the last snapshot.
+    Growth,
+}
+
+/// A user ranked by [`leaderboard`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeaderboardEntry {
+    /// The rank, starting at 1.
+    pub rank: usize,
+    /// The user.
+    pub username: Username,
+    /// The karma in the last snapshot the user appeared in.
+    pub karma: u32,
+    /// The karma gained between the first and the last snapshot the user
+    /// appeared in, negative if karma was lost.
+    pub growth: i64,
+}
+
+/// Rank the users of a series of snapshots, in chronological order.
+///
+/// Users with equal values are ordered by username.
+pub fn leaderboard(snapshots: &[KarmaSnapshot], by: RankBy) -> Vec<LeaderboardEntry> {
+    let mut window: BTreeMap<&Username, (u32, u32)> = BTreeMap::new();
+    for snapshot in snapshots {
+        for (username, karma) in &snapshot.karma {
+            window
+                .entry(username)
+                .and_modify(|(_, last)| *last = *karma)
+                .or_insert((*karma, *karma));
+        }
+    }
+    let mut entries: Vec<_> = window
+        .into_iter()
+        .map(|(username, (first, last))| LeaderboardEntry {
+            rank: 0,
+            username: username.clone(),
+            karma: last,
+            growth: i64::from(last) - i64::from(first),
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        let (a_value, b_value) = match by {
+            RankBy::Karma => (i64::from(a.karma), i64::from(b.karma)),
+            RankBy::Growth => (a.growth, b.growth),
+        };
+        b_value
+            .cmp(&a_value)
+            .then_with(|| a.username.cmp(&b.username))
+    });
+    for (i, entry) in entries.iter_mut().enumerate() {
+        entry.rank = i + 1;
+    }
+    entries
+}
+
+impl HnClient {
+    /// Return a stream of the karma of the users with the specified
+    /// usernames, fetching their profiles every `interval`, starting
+    /// immediately.
+    ///
+    /// A failed fetch, e.g. because a user does not exist, yields an error
+    /// without ending the stream.
+    pub fn sample_karma<'a, T: AsRef<str>>(
+        &'a self,
+        usernames: &'a [T],
+        interval: Duration,
+    ) -> impl Stream<Item = Result<KarmaSnapshot>> + 'a {
+        let stream = stream::unfold(false, move |started| async move {
+            if started {
+                runtime::sleep(interval).await;
+            }
+            let snapshot = self.get_users(usernames).await.map(|users| KarmaSnapshot {
+                time: Timestamp::now(),
+                karma: users
+                    .into_iter()
+                    .map(|(username, user)| (username, user.karma))
+                    .collect(),
+            });
+            Some((snapshot, true))
+        });
+        self.until_shutdown(stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leaderboard() {
+        let snapshot = |karma: &[(&str, u32)]| KarmaSnapshot {
+            time: Timestamp(0),
+            karma: karma
+                .iter()
+                .map(|(username, karma)| (Username(username.to_string()), *karma))
+                .collect(),
+        };
+        let snapshots = [
+            snapshot(&[("a", 100), ("b", 10)]),
+            snapshot(&[("a", 105), ("b", 30), ("c", 50)]),
+        ];
+        let names = |entries: Vec<LeaderboardEntry>| -> Vec<String> {
+            entries.into_iter().map(|entry| entry.username.0).collect()
+        };
+        assert_eq!(
+            names(leaderboard(&snapshots, RankBy::Karma)),
+            ["a", "c", "b"]
+        );
+        let growth = leaderboard(&snapshots, RankBy::Growth);
+        assert_eq!((growth[0].rank, growth[0].growth), (1, 20));
+        assert_eq!(names(growth), ["b", "a", "c"]);
+    }
+}
@@ -1,21 +1,645 @@
+use std::{fmt, sync::Arc};
+
+#[cfg(feature = "nonblocking")]
+pub mod alerts;
+#[cfg(feature = "nonblocking")]
+pub mod ancestry;
+#[cfg(feature = "nonblocking")]
+pub mod api;
+#[cfg(feature = "nonblocking")]
+pub mod archive;
+#[cfg(feature = "auth")]
+pub mod auth;
+#[cfg(any(feature = "blocking", feature = "blocking-lite"))]
 pub mod blocking;
+#[cfg(feature = "nonblocking")]
+pub mod bus;
+#[cfg(feature = "nonblocking")]
+pub mod cache;
+#[cfg(feature = "nonblocking")]
+pub mod cassette;
+pub mod category;
+#[cfg(feature = "nonblocking")]
+pub mod circuit;
+#[cfg(feature = "nonblocking")]
+pub mod crawl;
+pub mod domain;
+#[cfg(feature = "nonblocking")]
+pub mod duplicates;
+pub mod export;
+#[cfg(feature = "feed")]
+pub mod feed;
+#[cfg(feature = "nonblocking")]
+pub mod hiring;
+#[cfg(feature = "nonblocking")]
+pub mod hooks;
+#[cfg_attr(not(feature = "nonblocking"), allow(dead_code))]
+mod html;
+#[cfg(feature = "nonblocking")]
+pub mod inbox;
+#[cfg(feature = "nonblocking")]
+pub mod karma;
+#[cfg(feature = "nonblocking")]
+pub mod listing;
+#[cfg(feature = "nonblocking")]
+pub mod live;
+#[cfg(feature = "nonblocking")]
+pub mod mentions;
+#[cfg(feature = "nonblocking")]
+pub mod meta;
+#[cfg(feature = "scrape")]
+pub mod moderation;
+#[cfg(feature = "nonblocking")]
 pub mod nonblocking;
+#[cfg(feature = "otel")]
+mod otel;
+pub mod permalink;
+pub mod poll;
+pub mod prelude;
+#[cfg(feature = "nonblocking")]
+pub mod query;
+#[cfg(feature = "nonblocking")]
+pub mod reader;
+#[cfg(feature = "nonblocking")]
+pub mod refresh;
+#[cfg(feature = "render")]
+pub mod render;
+#[cfg(feature = "nonblocking")]
+mod runtime;
+#[cfg(feature = "nonblocking")]
+pub mod sample;
+#[cfg(feature = "nonblocking")]
+pub mod score;
+#[cfg(feature = "scrape")]
+pub mod scrape;
+#[cfg(feature = "tower")]
+pub mod service;
+#[cfg(feature = "nonblocking")]
+pub mod shutdown;
+#[cfg(feature = "store-sqlite")]
+pub mod sqlite;
+pub mod staleness;
+#[cfg(feature = "nonblocking")]
+pub mod stats;
+#[cfg(feature = "nonblocking")]
+pub mod sync;
+#[cfg(feature = "nonblocking")]
+pub mod timeline;
+pub mod tree;
 pub mod types;
+pub mod validation;
+#[cfg(feature = "nonblocking")]
+pub mod watch;
 
+#[cfg(any(feature = "blocking", feature = "blocking-lite"))]
 pub use blocking::HnClient;
+#[cfg(feature = "nonblocking")]
+pub use circuit::CircuitState;
+#[cfg(feature = "nonblocking")]
+pub use meta::{ResponseMeta, ResponseSource, WithMeta};
+pub use permalink::{parse_hn_url, HnRef};
+pub use poll::{PollResults, PollWithOptions};
+pub use staleness::{StaleReason, Staleness, Tracked};
+#[cfg(feature = "nonblocking")]
+pub use stats::ClientStats;
+pub use tree::{
+    CommentQuery, CommentTree, Continuation, FlatComment, PartialTree, ScoredComment,
+    TraversalOrder, TreeDiff, TreeLimits, TreeStats,
+};
 pub use types::*;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum HnClientError {
-    ItemNotFoundError(u32),
+    ItemNotFoundError(ItemId),
+    ItemTypeError(ItemId),
     UserNotFoundError(String),
     BackendError(String),
+    TimeoutError,
+    AuthError(String),
+    /// An HTTP request failed or was answered with an error status.
+    HttpError(Box<RequestError>),
+    /// A response could not be decoded.
+    DecodeError(Box<InvalidResponse>),
+    /// The request was not sent because the circuit breaker is open, see
+    /// [`circuit`].
+    CircuitOpen,
+    /// An item broke an invariant, only returned in strict mode.
+    ValidationError(Box<validation::ValidationError>),
+    /// A response body exceeded the size limit in bytes, see
+    /// `HnClientBuilder::max_response_size`.
+    ResponseTooLarge(u64),
+    /// An API response was not JSON but of the contained content type, e.g.
+    /// the HTML error page of a proxy.
+    UnexpectedContentType(String),
 }
 
 pub type Result<T> = std::result::Result<T, HnClientError>;
 
+impl fmt::Display for HnClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HnClientError::ItemNotFoundError(id) => write!(f, "item {} not found", id),
+            HnClientError::ItemTypeError(id) => write!(f, "item {} has an unexpected type", id),
+            HnClientError::UserNotFoundError(username) => {
+                write!(f, "user {} not found", username)
+            }
+            HnClientError::BackendError(message) => write!(f, "backend error: {}", message),
+            HnClientError::TimeoutError => write!(f, "request timed out"),
+            HnClientError::AuthError(message) => write!(f, "authentication failed: {}", message),
+            HnClientError::HttpError(err) => err.fmt(f),
+            HnClientError::DecodeError(err) => err.fmt(f),
+            HnClientError::CircuitOpen => write!(f, "circuit breaker is open"),
+            HnClientError::ValidationError(err) => err.fmt(f),
+            HnClientError::ResponseTooLarge(limit) => {
+                write!(f, "response is larger than {} bytes", limit)
+            }
+            HnClientError::UnexpectedContentType(content_type) => {
+                write!(f, "expected JSON response, got {}", content_type)
+            }
+        }
+    }
+}
+
+impl HnClientError {
+    /// Return whether the requested item or user does not exist.
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            HnClientError::ItemNotFoundError(_) | HnClientError::UserNotFoundError(_) => true,
+            HnClientError::HttpError(err) => err.status == Some(404),
+            _ => false,
+        }
+    }
+
+    /// Return whether a request took too long.
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            HnClientError::TimeoutError => true,
+            HnClientError::HttpError(err) => err.timeout,
+            _ => false,
+        }
+    }
+
+    /// Return whether repeating the request may succeed: timeouts, network
+    /// failures, throttling and server errors.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            HnClientError::TimeoutError => true,
+            HnClientError::HttpError(err) => match err.status {
+                Some(status) => status == 429 || status >= 500,
+                None => true,
+            },
+            _ => false,
+        }
+    }
+
+    /// Return whether a response was received but could not be decoded,
+    /// including responses that were too large or not JSON.
+    pub fn is_decode(&self) -> bool {
+        matches!(
+            self,
+            HnClientError::DecodeError(_)
+                | HnClientError::ResponseTooLarge(_)
+                | HnClientError::UnexpectedContentType(_)
+        )
+    }
+}
+
+impl std::error::Error for HnClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HnClientError::HttpError(err) => Some(err.as_ref()),
+            HnClientError::DecodeError(err) => Some(err.as_ref()),
+            HnClientError::ValidationError(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// The kind of resource a request asked for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Endpoint {
+    /// An item, by id.
+    Item(ItemId),
+    /// A user, by username.
+    User(Username),
+    /// The newest item id.
+    MaxItem,
+    /// A story listing.
+    Stories(StoryList),
+    /// The recently changed items and users.
+    Updates,
+    /// A page of the website, by path.
+    Website(String),
+    /// Any other URL.
+    Other,
+}
+
+impl Endpoint {
+    /// Classify a request URL.
+    pub fn from_url(url: &str) -> Self {
+        let path = url.split(['?', '#']).next().unwrap_or_default();
+        if let Some(page) = path.strip_prefix(permalink::SITE_BASE_URL) {
+            return Endpoint::Website(page.trim_start_matches('/').to_string());
+        }
+        let resource = match path.split_once("/v0/") {
+            Some((_, resource)) => resource.trim_end_matches(".json"),
+            None => return Endpoint::Other,
+        };
+        match resource.split_once('/') {
+            Some(("item", id)) => id.parse().map_or(Endpoint::Other, Endpoint::Item),
+            Some(("user", username)) => Endpoint::User(username.into()),
+            _ => match resource {
+                "maxitem" => Endpoint::MaxItem,
+                "updates" => Endpoint::Updates,
+                _ => StoryList::ALL
+                    .iter()
+                    .copied()
+                    .find(|list| list.api_name() == resource)
+                    .map_or(Endpoint::Other, Endpoint::Stories),
+            },
+        }
+    }
+
+    /// Return a short name of the kind of resource, e.g. `item`, for use as
+    /// a metric label.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Endpoint::Item(_) => "item",
+            Endpoint::User(_) => "user",
+            Endpoint::MaxItem => "maxitem",
+            Endpoint::Stories(_) => "stories",
+            Endpoint::Updates => "updates",
+            Endpoint::Website(_) => "website",
+            Endpoint::Other => "other",
+        }
+    }
+}
+
+/// The context of a failed HTTP request.
+#[derive(Debug, Clone)]
+pub struct RequestError {
+    /// What the request asked for.
+    pub endpoint: Endpoint,
+    /// The requested URL.
+    pub url: String,
+    /// The HTTP status code, if a response was received.
+    pub status: Option<u16>,
+    /// The start of the response body, if a response was received.
+    pub body: Option<String>,
+    source: Option<Arc<dyn std::error::Error + Send + Sync>>,
+    timeout: bool,
+}
+
+#[cfg(any(
+    feature = "blocking",
+    feature = "blocking-lite",
+    feature = "nonblocking"
+))]
+impl RequestError {
+    /// Describe a response with an error status.
+    pub(crate) fn status(url: &str, status: u16, body: &[u8]) -> Self {
+        RequestError {
+            endpoint: Endpoint::from_url(url),
+            url: url.to_string(),
+            status: Some(status),
+            body: Some(body_snippet(body)),
+            source: None,
+            timeout: false,
+        }
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "request to {} failed", self.url)?;
+        if let Some(status) = self.status {
+            write!(f, " with status {}", status)?;
+        }
+        if let Some(source) = &self.source {
+            write!(f, ": {}", source)?;
+        }
+        match &self.body {
+            Some(body) if !body.is_empty() => write!(f, " ({})", body),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for RequestError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|err| err.as_ref() as &(dyn std::error::Error + 'static))
+    }
+}
+
+/// The context of a response that could not be decoded.
+#[derive(Debug, Clone)]
+pub struct InvalidResponse {
+    /// The requested URL, if the value came from a response.
+    pub url: Option<String>,
+    /// The HTTP status code, if known.
+    pub status: Option<u16>,
+    /// The start of the undecodable body, if known, e.g. of the HTML error
+    /// page of a proxy.
+    pub body_snippet: Option<String>,
+    source: Arc<dyn std::error::Error + Send + Sync>,
+}
+
+impl InvalidResponse {
+    /// Describe a decoding error without a response.
+    pub(crate) fn new(source: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Self {
+            url: None,
+            status: None,
+            body_snippet: None,
+            source: Arc::new(source),
+        }
+    }
+}
+
+impl fmt::Display for InvalidResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid response")?;
+        if let Some(url) = &self.url {
+            write!(f, " from {}", url)?;
+        }
+        if let Some(status) = self.status {
+            write!(f, " with status {}", status)?;
+        }
+        write!(f, ": {}", self.source)?;
+        match &self.body_snippet {
+            Some(body) if !body.is_empty() => write!(f, " ({})", body),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for InvalidResponse {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.source.as_ref() as &(dyn std::error::Error + 'static))
+    }
+}
+
+/// Deserialize the JSON body of a response to `url`, keeping the start of
+/// the body in the error if that fails.
+#[cfg(any(
+    feature = "blocking",
+    feature = "blocking-lite",
+    feature = "nonblocking"
+))]
+pub(crate) fn decode_json<T: serde::de::DeserializeOwned>(
+    url: &str,
+    status: Option<u16>,
+    body: &[u8],
+) -> Result<T> {
+    serde_json::from_slice(body).map_err(|err| {
+        HnClientError::DecodeError(Box::new(InvalidResponse {
+            url: Some(url.to_string()),
+            status,
+            body_snippet: Some(body_snippet(body)),
+            ..InvalidResponse::new(err)
+        }))
+    })
+}
+
+/// Return the start of a response body, for error messages.
+#[cfg(any(
+    feature = "blocking",
+    feature = "blocking-lite",
+    feature = "nonblocking"
+))]
+pub(crate) fn body_snippet(body: &[u8]) -> String {
+    const MAX_CHARS: usize = 200;
+    let text = String::from_utf8_lossy(body);
+    let text = text.trim();
+    match text.char_indices().nth(MAX_CHARS) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Return whether the `HN_API_DEBUG_HTTP` environment variable enables
+/// logging every exchange, see `debug_http` on the client builders.
+#[cfg(any(
+    feature = "blocking",
+    feature = "blocking-lite",
+    feature = "nonblocking"
+))]
+pub(crate) fn debug_http_from_env() -> bool {
+    std::env::var_os("HN_API_DEBUG_HTTP").is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+/// Log a received response at debug level, with the start of its body if it
+/// was read.
+#[cfg(any(
+    feature = "blocking",
+    feature = "blocking-lite",
+    feature = "nonblocking"
+))]
+pub(crate) fn log_response(
+    url: &str,
+    status: u16,
+    elapsed: std::time::Duration,
+    body: Option<&[u8]>,
+) {
+    match body {
+        Some(body) => log::debug!(
+            target: "hn_api::http",
+            "GET {} -> {} in {:?}, {} bytes: {}",
+            url,
+            status,
+            elapsed,
+            body.len(),
+            body_snippet(body)
+        ),
+        None => log::debug!(target: "hn_api::http", "GET {} -> {} in {:?}", url, status, elapsed),
+    }
+}
+
+/// Log a failed request at debug level.
+#[cfg(any(
+    feature = "blocking",
+    feature = "blocking-lite",
+    feature = "nonblocking"
+))]
+pub(crate) fn log_failure(url: &str, elapsed: std::time::Duration, error: &HnClientError) {
+    log::debug!(target: "hn_api::http", "GET {} failed after {:?}: {}", url, elapsed, error);
+}
+
+/// The default limit of the size of a response body, see
+/// `max_response_size` on the client builders.
+#[cfg(any(
+    feature = "blocking",
+    feature = "blocking-lite",
+    feature = "nonblocking"
+))]
+pub(crate) const DEFAULT_MAX_RESPONSE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Fail with `UnexpectedContentType` if an API response is declared as
+/// anything but JSON.
+#[cfg(any(
+    feature = "blocking",
+    feature = "blocking-lite",
+    feature = "nonblocking"
+))]
+pub(crate) fn check_json(content_type: Option<&str>) -> Result<()> {
+    let content_type = match content_type {
+        Some(content_type) => content_type,
+        None => return Ok(()),
+    };
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    if mime.eq_ignore_ascii_case("application/json") || mime.ends_with("+json") {
+        Ok(())
+    } else {
+        Err(HnClientError::UnexpectedContentType(
+            content_type.to_string(),
+        ))
+    }
+}
+
+/// Return the URL of a path of the v0 API, appending `.json` to the resource
+/// if it is missing.
+#[cfg(any(
+    feature = "blocking",
+    feature = "blocking-lite",
+    feature = "nonblocking"
+))]
+pub(crate) fn api_url(base: &str, path: &str) -> String {
+    let path = path.trim_start_matches('/');
+    let (resource, query) = path.split_once('?').unwrap_or((path, ""));
+    let extension = if resource.ends_with(".json") {
+        ""
+    } else {
+        ".json"
+    };
+    let separator = if query.is_empty() { "" } else { "?" };
+    format!("{}/{}{}{}{}", base, resource, extension, separator, query)
+}
+
+#[cfg(feature = "reqwest")]
 impl From<reqwest::Error> for HnClientError {
     fn from(err: reqwest::Error) -> Self {
-        HnClientError::BackendError(err.to_string())
+        if err.is_decode() {
+            let url = err.url().map(|url| url.to_string());
+            return HnClientError::DecodeError(Box::new(InvalidResponse {
+                url,
+                status: err.status().map(|status| status.as_u16()),
+                ..InvalidResponse::new(err)
+            }));
+        }
+        let url = err.url().map(|url| url.to_string()).unwrap_or_default();
+        HnClientError::HttpError(Box::new(RequestError {
+            endpoint: Endpoint::from_url(&url),
+            url,
+            status: err.status().map(|status| status.as_u16()),
+            body: None,
+            timeout: err.is_timeout(),
+            source: Some(Arc::new(err)),
+        }))
+    }
+}
+
+impl From<serde_json::Error> for HnClientError {
+    fn from(err: serde_json::Error) -> Self {
+        HnClientError::DecodeError(Box::new(InvalidResponse::new(err)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_from_url() {
+        let api = "https://hacker-news.firebaseio.com/v0";
+        assert_eq!(
+            Endpoint::from_url(&format!("{}/item/8863.json", api)),
+            Endpoint::Item(ItemId(8863))
+        );
+        assert_eq!(
+            Endpoint::from_url(&format!("{}/user/pg.json", api)),
+            Endpoint::User("pg".into())
+        );
+        assert_eq!(
+            Endpoint::from_url(&format!("{}/topstories.json?print=pretty", api)),
+            Endpoint::Stories(StoryList::Top)
+        );
+        assert_eq!(
+            Endpoint::from_url("https://news.ycombinator.com/favorites?id=pg"),
+            Endpoint::Website("favorites".into())
+        );
+        assert_eq!(Endpoint::from_url("https://example.com/"), Endpoint::Other);
+    }
+
+    #[test]
+    #[cfg(any(
+        feature = "blocking",
+        feature = "blocking-lite",
+        feature = "nonblocking"
+    ))]
+    fn test_check_json() {
+        assert!(check_json(Some("application/json; charset=utf-8")).is_ok());
+        assert!(check_json(Some("application/problem+json")).is_ok());
+        assert!(check_json(None).is_ok());
+        let err = check_json(Some("text/html")).unwrap_err();
+        assert!(err.is_decode());
+        assert_eq!(err.to_string(), "expected JSON response, got text/html");
+    }
+
+    #[test]
+    #[cfg(any(feature = "blocking", feature = "nonblocking"))]
+    fn test_api_url() {
+        let api = "https://hacker-news.firebaseio.com/v0";
+        assert_eq!(api_url(api, "item/1"), format!("{}/item/1.json", api));
+        assert_eq!(
+            api_url(api, "/topstories.json?limitToFirst=10"),
+            format!("{}/topstories.json?limitToFirst=10", api)
+        );
+        assert_eq!(
+            api_url(api, "maxitem?print=pretty"),
+            format!("{}/maxitem.json?print=pretty", api)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "nonblocking")]
+    fn test_request_error() {
+        let url = "https://hacker-news.firebaseio.com/v0/item/1.json";
+        let err = HnClientError::HttpError(Box::new(RequestError::status(url, 500, b"oops")));
+        assert_eq!(
+            err.to_string(),
+            format!("request to {} failed with status 500 (oops)", url)
+        );
+        assert!(!err.is_not_found());
+        assert!(err.is_retryable());
+        assert!(!err.is_decode());
+        assert!(!err.is_timeout());
+        let err = HnClientError::from(serde_json::from_str::<u32>("null").unwrap_err());
+        assert!(err.is_decode());
+        assert!(!err.is_retryable());
+        assert!(HnClientError::ItemNotFoundError(ItemId(1)).is_not_found());
+        assert_eq!(body_snippet("x".repeat(300).as_bytes()).len(), 203);
+    }
+
+    #[test]
+    #[cfg(feature = "nonblocking")]
+    fn test_decode_error() {
+        use std::error::Error;
+
+        let url = "https://hacker-news.firebaseio.com/v0/item/1.json";
+        let err = decode_json::<u32>(url, Some(200), b"<html>oops</html>").unwrap_err();
+        assert!(err.is_decode());
+        match &err {
+            HnClientError::DecodeError(response) => {
+                assert_eq!(response.url.as_deref(), Some(url));
+                assert_eq!(response.status, Some(200));
+                assert_eq!(response.body_snippet.as_deref(), Some("<html>oops</html>"));
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+        assert!(err.to_string().ends_with("(<html>oops</html>)"));
+        let source = err.source().and_then(Error::source).unwrap();
+        assert!(source.is::<serde_json::Error>());
     }
 }
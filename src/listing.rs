@@ -0,0 +1,294 @@
+//! Paginated story listings.
+//!
+//! ```rust,no_run
+//! use hn_api::nonblocking::HnClient;
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let mut listing = client.top_stories_listing().await?;
+//! println!("{} stories", listing.len());
+//! let first = listing.next_page().await?;
+//! let third = listing.page(2, 10).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use futures::future;
+
+use super::{
+    nonblocking::HnClient,
+    types::{Item, ItemFilter, ItemId, StoryList},
+    watch::RankEvent,
+    Result,
+};
+
+/// The ids of a story listing, fetched page by page.
+///
+/// The ids are loaded once, when the listing is created. Pages are fetched
+/// on demand, missing items are skipped.
+pub struct Listing<'a> {
+    client: &'a HnClient,
+    list: StoryList,
+    ids: Vec<ItemId>,
+    page_size: usize,
+    next_page: usize,
+}
+
+impl<'a> Listing<'a> {
+    /// Set the page size used by [`Listing::next_page`]. Defaults to 30, like
+    /// on the website.
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Return the listing the ids belong to.
+    pub fn list(&self) -> StoryList {
+        self.list
+    }
+
+    /// Return the ids of the listing, in ranked order.
+    pub fn ids(&self) -> &[ItemId] {
+        &self.ids
+    }
+
+    /// Return the number of stories in the listing.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Return whether the listing has no stories.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Return the number of pages of `page_size` stories.
+    pub fn page_count(&self, page_size: usize) -> usize {
+        self.ids.len().div_ceil(page_size.max(1))
+    }
+
+    /// Return the stories of the zero-based page `n` of `page_size` stories.
+    ///
+    /// Returns an empty list past the last page.
+    /// Fails if any of the request failed.
+    pub async fn page(&self, n: usize, page_size: usize) -> Result<Vec<Item>> {
+        let page_size = page_size.max(1);
+        let start = n.saturating_mul(page_size).min(self.ids.len());
+        let end = start.saturating_add(page_size).min(self.ids.len());
+        self.client
+            .get_items_filtered(&self.ids[start..end], ItemFilter::ALL)
+            .await
+    }
+
+    /// Return the stories of the next page and advance the cursor.
+    ///
+    /// Returns an empty list once all pages have been read.
+    /// Fails if any of the request failed, without advancing the cursor.
+    pub async fn next_page(&mut self) -> Result<Vec<Item>> {
+        let items = self.page(self.next_page, self.page_size).await?;
+        self.next_page += 1;
+        Ok(items)
+    }
+
+    /// Return the zero-based index of the page the next call to
+    /// [`Listing::next_page`] returns.
+    pub fn current_page(&self) -> usize {
+        self.next_page
+    }
+
+    /// Move the cursor back to the first page.
+    pub fn rewind(&mut self) {
+        self.next_page = 0;
+    }
+}
+
+/// A story of several combined listings, see
+/// [`HnClient::get_combined_stories`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CombinedStory {
+    /// The id of the story.
+    pub id: ItemId,
+    /// The rank of the story on every listing it appeared on, starting at 1.
+    pub ranks: BTreeMap<StoryList, usize>,
+}
+
+impl CombinedStory {
+    /// Return the best rank of the story on any listing.
+    pub fn best_rank(&self) -> usize {
+        self.ranks.values().copied().min().unwrap_or(usize::MAX)
+    }
+
+    /// Merge listings into one list without duplicates.
+    ///
+    /// Stories are ordered by their best rank. Stories with the same best
+    /// rank are ordered by the number of listings they appeared on, most
+    /// first, then by the order of `lists`.
+    pub fn combine(lists: &[(StoryList, Vec<ItemId>)]) -> Vec<CombinedStory> {
+        let mut stories: Vec<CombinedStory> = Vec::new();
+        let mut index: HashMap<ItemId, usize> = HashMap::new();
+        for (list, ids) in lists {
+            for (i, id) in ids.iter().enumerate() {
+                let position = *index.entry(*id).or_insert_with(|| {
+                    stories.push(CombinedStory {
+                        id: *id,
+                        ranks: BTreeMap::new(),
+                    });
+                    stories.len() - 1
+                });
+                stories[position].ranks.entry(*list).or_insert(i + 1);
+            }
+        }
+        stories.sort_by_key(|story| (story.best_rank(), std::cmp::Reverse(story.ranks.len())));
+        stories
+    }
+}
+
+/// The differences between two snapshots of the same listing. Ranks start
+/// at 1.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListingDiff {
+    /// The stories that entered the listing with their rank, in rank order.
+    pub entered: Vec<(ItemId, usize)>,
+    /// The stories that left the listing with their last rank, in the order
+    /// of the old snapshot.
+    pub exited: Vec<(ItemId, usize)>,
+    /// The stories that changed their rank with the old and the new rank, in
+    /// rank order.
+    pub moved: Vec<(ItemId, usize, usize)>,
+}
+
+impl ListingDiff {
+    /// Return the differences turning the `old` snapshot into the `new` one.
+    pub fn between(old: &[ItemId], new: &[ItemId]) -> Self {
+        let rank = |ids: &[ItemId]| -> HashMap<ItemId, usize> {
+            ids.iter().enumerate().map(|(i, id)| (*id, i + 1)).collect()
+        };
+        let (old_ranks, new_ranks) = (rank(old), rank(new));
+        let mut diff = ListingDiff::default();
+        for (i, id) in old.iter().enumerate() {
+            if !new_ranks.contains_key(id) {
+                diff.exited.push((*id, i + 1));
+            }
+        }
+        for (i, id) in new.iter().enumerate() {
+            let to = i + 1;
+            match old_ranks.get(id) {
+                None => diff.entered.push((*id, to)),
+                Some(&from) if from != to => diff.moved.push((*id, from, to)),
+                Some(_) => {}
+            }
+        }
+        diff
+    }
+
+    /// Return whether both snapshots are equal.
+    pub fn is_empty(&self) -> bool {
+        self.entered.is_empty() && self.exited.is_empty() && self.moved.is_empty()
+    }
+
+    /// Return the differences as events: first stories that left, then
+    /// stories that entered or moved, in rank order.
+    pub fn events(&self) -> Vec<RankEvent> {
+        let mut events: Vec<_> = self
+            .exited
+            .iter()
+            .map(|&(id, rank)| RankEvent::Dropped { id, rank })
+            .collect();
+        let mut ranked: Vec<_> = self
+            .entered
+            .iter()
+            .map(|&(id, rank)| (rank, RankEvent::Entered { id, rank }))
+            .chain(
+                self.moved
+                    .iter()
+                    .map(|&(id, from, to)| (to, RankEvent::Moved { id, from, to })),
+            )
+            .collect();
+        ranked.sort_by_key(|(rank, _)| *rank);
+        events.extend(ranked.into_iter().map(|(_, event)| event));
+        events
+    }
+}
+
+impl HnClient {
+    /// Fetch several listings concurrently and merge them into one list
+    /// without duplicates, see [`CombinedStory::combine`].
+    ///
+    /// Fails if any of the request failed.
+    pub async fn get_combined_stories(&self, lists: &[StoryList]) -> Result<Vec<CombinedStory>> {
+        let ids = future::try_join_all(lists.iter().map(|list| self.get_stories(*list))).await?;
+        let lists: Vec<_> = lists.iter().copied().zip(ids).collect();
+        Ok(CombinedStory::combine(&lists))
+    }
+
+    /// Load the ids of a story listing.
+    pub async fn listing(&self, list: StoryList) -> Result<Listing<'_>> {
+        Ok(Listing {
+            client: self,
+            list,
+            ids: self.get_stories(list).await?,
+            page_size: 30,
+            next_page: 0,
+        })
+    }
+
+    /// Load the top stories listing.
+    pub async fn top_stories_listing(&self) -> Result<Listing<'_>> {
+        self.listing(StoryList::Top).await
+    }
+
+    /// Load the new stories listing.
+    pub async fn new_stories_listing(&self) -> Result<Listing<'_>> {
+        self.listing(StoryList::New).await
+    }
+
+    /// Load the best stories listing.
+    pub async fn best_stories_listing(&self) -> Result<Listing<'_>> {
+        self.listing(StoryList::Best).await
+    }
+
+    /// Load the Ask HN stories listing.
+    pub async fn ask_stories_listing(&self) -> Result<Listing<'_>> {
+        self.listing(StoryList::Ask).await
+    }
+
+    /// Load the Show HN stories listing.
+    pub async fn show_stories_listing(&self) -> Result<Listing<'_>> {
+        self.listing(StoryList::Show).await
+    }
+
+    /// Load the job stories listing.
+    pub async fn job_stories_listing(&self) -> Result<Listing<'_>> {
+        self.listing(StoryList::Job).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_combine() {
+        let ids = |ids: &[u32]| -> Vec<ItemId> { ids.iter().copied().map(ItemId).collect() };
+        let combined = CombinedStory::combine(&[
+            (StoryList::Top, ids(&[1, 2, 3])),
+            (StoryList::Best, ids(&[4, 2, 1])),
+        ]);
+        let order: Vec<_> = combined.iter().map(|story| story.id.0).collect();
+        assert_eq!(order, vec![1, 4, 2, 3]);
+        assert_eq!(combined[0].ranks[&StoryList::Best], 3);
+        assert_eq!(combined[2].ranks.len(), 2);
+    }
+
+    #[test]
+    fn test_listing_diff() {
+        let ids = |ids: &[u32]| -> Vec<ItemId> { ids.iter().copied().map(ItemId).collect() };
+        let diff = ListingDiff::between(&ids(&[1, 2, 3, 5]), &ids(&[3, 1, 4, 5]));
+        assert_eq!(diff.entered, vec![(ItemId(4), 3)]);
+        assert_eq!(diff.exited, vec![(ItemId(2), 2)]);
+        assert_eq!(diff.moved, vec![(ItemId(3), 3, 1), (ItemId(1), 1, 2)]);
+        assert!(ListingDiff::between(&ids(&[1, 2]), &ids(&[1, 2])).is_empty());
+    }
+}
@@ -0,0 +1,317 @@
+//! Follow story listings in realtime through the Firebase event stream.
+//!
+//! Instead of polling, [`HnClient::stream_stories`] keeps a connection open
+//! to the streaming endpoint of a listing and yields a [`ListingUpdate`] as
+//! soon as the listing changes, e.g. to refresh the front page of a TUI
+//! client instantly.
+//!
+//! ```rust,no_run
+//! use futures::StreamExt;
+//! use hn_api::{nonblocking::HnClient, StoryList};
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let updates = client.stream_stories(StoryList::Top);
+//! futures::pin_mut!(updates);
+//! while let Some(update) = updates.next().await {
+//!     let update = update?;
+//!     println!("{} stories, {} entered", update.ids.len(), update.diff.entered.len());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    time::Duration,
+};
+
+use futures::stream::{self, Stream};
+use serde::Deserialize;
+
+use super::{
+    listing::ListingDiff,
+    nonblocking::HnClient,
+    runtime,
+    types::{ItemId, StoryList},
+    HnClientError, Result,
+};
+
+/// Firebase sends a keep-alive event every 30 seconds, a connection without
+/// any event for this long is considered dead.
+const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// How long a connection is kept open before it is replaced by a new one.
+const MAX_CONNECTION_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// The longest time to wait before reconnecting after a failure.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// A change of a listing received from the event stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListingUpdate {
+    /// The ids of the listing after the change, in ranked order.
+    pub ids: Vec<ItemId>,
+    /// The changes since the previous update, empty for the first one.
+    pub diff: ListingDiff,
+}
+
+/// A server-sent event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Event {
+    pub(crate) name: String,
+    pub(crate) data: String,
+}
+
+/// Split a byte stream into server-sent events.
+#[derive(Debug, Default)]
+pub(crate) struct EventParser {
+    buffer: Vec<u8>,
+    name: String,
+    data: Vec<String>,
+}
+
+impl EventParser {
+    /// Add the next chunk of the stream, and return the events it completed.
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> Vec<Event> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.is_empty() {
+                if !self.name.is_empty() || !self.data.is_empty() {
+                    events.push(Event {
+                        name: std::mem::take(&mut self.name),
+                        data: std::mem::take(&mut self.data).join("\n"),
+                    });
+                }
+                continue;
+            }
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "event" => self.name = value.to_string(),
+                "data" => self.data.push(value.to_string()),
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+/// The data of a `put` or `patch` event.
+#[derive(Debug, Deserialize)]
+struct Change {
+    path: String,
+    data: serde_json::Value,
+}
+
+/// The ids of a listing by position. Firebase stores lists as objects keyed
+/// by index, so positions may be removed individually.
+type Positions = BTreeMap<usize, ItemId>;
+
+/// Apply an event to the listing, and return whether it changed.
+fn apply(listing: &mut Positions, event: &Event) -> Result<bool> {
+    let (replace, change) = match event.name.as_str() {
+        "put" => (true, serde_json::from_str::<Change>(&event.data)?),
+        "patch" => (false, serde_json::from_str::<Change>(&event.data)?),
+        "keep-alive" => return Ok(false),
+        "cancel" | "auth_revoked" => {
+            let message = format!("event stream closed by the server ({})", event.name);
+            return Err(HnClientError::BackendError(message));
+        }
+        _ => return Ok(false),
+    };
+    let before = listing.clone();
+    let path = change.path.trim_matches('/');
+    if path.is_empty() {
+        if replace {
+            listing.clear();
+        }
+        let entries: Vec<(usize, serde_json::Value)> = match change.data {
+            serde_json::Value::Array(ids) => ids.into_iter().enumerate().collect(),
+            serde_json::Value::Object(ids) => ids
+                .into_iter()
+                .filter_map(|(index, id)| Some((index.parse().ok()?, id)))
+                .collect(),
+            _ => Vec::new(),
+        };
+        for (index, id) in entries {
+            set(listing, index, id)?;
+        }
+    } else if let Ok(index) = path.parse() {
+        set(listing, index, change.data)?;
+    }
+    Ok(*listing != before)
+}
+
+/// Set the id at a position, removing it if the value is `null`.
+fn set(listing: &mut Positions, index: usize, id: serde_json::Value) -> Result<()> {
+    match serde_json::from_value(id)? {
+        Some(id) => listing.insert(index, id),
+        None => listing.remove(&index),
+    };
+    Ok(())
+}
+
+/// The state of a listing stream between two updates.
+struct StreamState {
+    response: Option<reqwest::Response>,
+    parser: EventParser,
+    listing: Positions,
+    /// The ids of the last yielded update.
+    previous: Option<Vec<ItemId>>,
+    pending: VecDeque<Result<ListingUpdate>>,
+    failures: u32,
+}
+
+impl HnClient {
+    /// Return a stream of changes of the specified story listing, received
+    /// from the Firebase event stream as they happen.
+    ///
+    /// The first update contains the whole listing. The connection is
+    /// reopened when it is lost, when the server stops sending keep-alive
+    /// events, and every hour; updates are only yielded when the listing
+    /// actually changed. A failed connection yields an error without ending
+    /// the stream, the client waits up to a minute before reconnecting.
+    pub fn stream_stories(
+        &self,
+        list: StoryList,
+    ) -> impl Stream<Item = Result<ListingUpdate>> + '_ {
+        let state = StreamState {
+            response: None,
+            parser: EventParser::default(),
+            listing: Positions::new(),
+            previous: None,
+            pending: VecDeque::new(),
+            failures: 0,
+        };
+        let stream = stream::unfold(state, move |mut state| async move {
+            loop {
+                if let Some(update) = state.pending.pop_front() {
+                    return Some((update, state));
+                }
+                let response = match &mut state.response {
+                    Some(response) => response,
+                    None => {
+                        if state.failures > 0 {
+                            let delay = Duration::from_secs(1 << state.failures.min(6));
+                            runtime::sleep(delay.min(MAX_RECONNECT_DELAY)).await;
+                        }
+                        match self
+                            .open_event_stream(list.api_name(), MAX_CONNECTION_AGE)
+                            .await
+                        {
+                            Ok(response) => {
+                                state.parser = EventParser::default();
+                                state.response.insert(response)
+                            }
+                            Err(err) => {
+                                state.failures += 1;
+                                return Some((Err(err), state));
+                            }
+                        }
+                    }
+                };
+                let chunk = match runtime::timeout(KEEP_ALIVE_TIMEOUT, response.chunk()).await {
+                    Some(Ok(Some(chunk))) => chunk,
+                    Some(Ok(None)) => {
+                        state.response = None;
+                        continue;
+                    }
+                    Some(Err(err)) if err.is_timeout() => {
+                        // The connection reached its maximum age.
+                        state.response = None;
+                        continue;
+                    }
+                    Some(Err(err)) => {
+                        state.response = None;
+                        state.failures += 1;
+                        return Some((Err(err.into()), state));
+                    }
+                    None => {
+                        state.response = None;
+                        state.failures += 1;
+                        return Some((Err(HnClientError::TimeoutError), state));
+                    }
+                };
+                for event in state.parser.push(&chunk) {
+                    match apply(&mut state.listing, &event) {
+                        Ok(true) => {
+                            state.failures = 0;
+                            let ids: Vec<_> = state.listing.values().copied().collect();
+                            let diff = match &state.previous {
+                                Some(previous) => ListingDiff::between(previous, &ids),
+                                None => ListingDiff::default(),
+                            };
+                            state.previous = Some(ids.clone());
+                            state.pending.push_back(Ok(ListingUpdate { ids, diff }));
+                        }
+                        Ok(false) => state.failures = 0,
+                        Err(err) => {
+                            state.response = None;
+                            state.failures += 1;
+                            state.pending.push_back(Err(err));
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        self.until_shutdown(stream)
+    }
+
+    /// Return a stream of changes of the top stories, see
+    /// [`HnClient::stream_stories`].
+    pub fn stream_top_stories(&self) -> impl Stream<Item = Result<ListingUpdate>> + '_ {
+        self.stream_stories(StoryList::Top)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_parser() {
+        let mut parser = EventParser::default();
+        assert!(parser.push(b"event: keep-alive\ndata: null\n").is_empty());
+        let events = parser.push(b"\nevent: put\r\ndata: {\"path\"");
+        assert_eq!(
+            events,
+            vec![Event {
+                name: "keep-alive".into(),
+                data: "null".into()
+            }]
+        );
+        let events = parser.push(b": \"/\", \"data\": [1]}\n\n");
+        assert_eq!(events[0].name, "put");
+        assert_eq!(events[0].data, r#"{"path": "/", "data": [1]}"#);
+    }
+
+    #[test]
+    fn test_apply() {
+        let event = |name: &str, data: &str| Event {
+            name: name.into(),
+            data: data.into(),
+        };
+        let ids = |listing: &Positions| -> Vec<u32> { listing.values().map(|id| id.0).collect() };
+        let mut listing = Positions::new();
+        assert!(apply(
+            &mut listing,
+            &event("put", r#"{"path": "/", "data": [3, 2, 1]}"#)
+        )
+        .unwrap());
+        assert_eq!(ids(&listing), vec![3, 2, 1]);
+        let patch = event("patch", r#"{"path": "/", "data": {"0": 4, "2": null}}"#);
+        assert!(apply(&mut listing, &patch).unwrap());
+        assert_eq!(ids(&listing), vec![4, 2]);
+        assert!(apply(&mut listing, &event("put", r#"{"path": "/1", "data": 5}"#)).unwrap());
+        assert_eq!(ids(&listing), vec![4, 5]);
+        assert!(!apply(&mut listing, &event("put", r#"{"path": "/1", "data": 5}"#)).unwrap());
+        assert!(!apply(&mut listing, &event("keep-alive", "null")).unwrap());
+        assert!(apply(&mut listing, &event("cancel", "null")).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+//! Notice when users from a watchlist are mentioned in comments.
+//!
+//! A [`Mentions`] watchlist scans the text of items for `@username` or the
+//! bare username and emits a [`MentionEvent`] per mentioned user. Like
+//! [`Alerts`](crate::alerts::Alerts), it can be fed a stream of items, and a
+//! single user can be watched as an alert rule with
+//! [`Rule::Mention`](crate::alerts::Rule::Mention).
+//!
+//! ```rust,no_run
+//! use futures::StreamExt;
+//! use hn_api::{mentions::Mentions, nonblocking::HnClient};
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let mut mentions = Mentions::new();
+//! mentions.watch("dang").watch("tptacek");
+//! let (sender, mut receiver) = tokio::sync::mpsc::channel(16);
+//! let items = client.recent_items_stream().take(1000);
+//! tokio::join!(mentions.run(items, sender), async {
+//!     while let Some(mention) = receiver.recv().await {
+//!         println!("{} mentioned in {}", mention.username, mention.item.id());
+//!     }
+//! });
+//! # Ok(())
+//! # }
+//! ```
+
+use futures::{Stream, StreamExt};
+use tokio::sync::mpsc::Sender;
+
+use super::{
+    html,
+    types::{Item, Username},
+    Result,
+};
+
+/// A watched user mentioned in an item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MentionEvent {
+    /// The mentioned user.
+    pub username: Username,
+    /// Whether the user was mentioned as `@username` rather than by the bare
+    /// username.
+    pub explicit: bool,
+    /// The item mentioning the user.
+    pub item: Item,
+}
+
+/// A watchlist of users.
+#[derive(Debug, Clone, Default)]
+pub struct Mentions {
+    watchlist: Vec<Username>,
+    explicit_only: bool,
+}
+
+impl Mentions {
+    /// Create an empty watchlist.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a user to the watchlist.
+    pub fn watch(&mut self, username: impl Into<Username>) -> &mut Self {
+        self.watchlist.push(username.into());
+        self
+    }
+
+    /// Set whether only `@username` mentions count. Bare usernames are
+    /// matched too by default.
+    pub fn explicit_only(&mut self, enabled: bool) -> &mut Self {
+        self.explicit_only = enabled;
+        self
+    }
+
+    /// Return the mentions of watched users in the text of an item, in
+    /// watchlist order. Dead and deleted items and users mentioning
+    /// themselves are skipped.
+    pub fn check(&self, item: &Item) -> Vec<MentionEvent> {
+        if item.is_dead() || item.is_deleted() {
+            return Vec::new();
+        }
+        let text = html::plain_text(item.text().unwrap_or_default());
+        self.watchlist
+            .iter()
+            .filter(|username| item.author() != Some(username.as_str()))
+            .filter_map(|username| {
+                let explicit = find_mention(&text, username.as_str())?;
+                if self.explicit_only && !explicit {
+                    return None;
+                }
+                Some(MentionEvent {
+                    username: username.clone(),
+                    explicit,
+                    item: item.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Check every item of the stream and send the mentions to `sender`.
+    ///
+    /// Returns when the stream ends or the receiver is dropped. Failed
+    /// fetches are skipped.
+    pub async fn run<S>(&self, items: S, sender: Sender<MentionEvent>)
+    where
+        S: Stream<Item = Result<Item>>,
+    {
+        futures::pin_mut!(items);
+        while let Some(item) = items.next().await {
+            let item = match item {
+                Ok(item) => item,
+                Err(_) => continue,
+            };
+            for mention in self.check(&item) {
+                if sender.send(mention).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Return whether `username` is mentioned in `text`: `Some(true)` if it is
+/// mentioned as `@username`, `Some(false)` if only the bare username occurs,
+/// `None` if neither. Usernames are matched ignoring case.
+pub(crate) fn find_mention(text: &str, username: &str) -> Option<bool> {
+    if username.is_empty() {
+        return None;
+    }
+    let text = text.to_lowercase();
+    let username = username.to_lowercase();
+    let is_name_char = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    let mut found = None;
+    for (start, _) in text.match_indices(&username) {
+        let before = text[..start].chars().next_back();
+        let after = text[start + username.len()..].chars().next();
+        if before.is_some_and(is_name_char) || after.is_some_and(is_name_char) {
+            continue;
+        }
+        if before == Some('@') {
+            return Some(true);
+        }
+        found = Some(false);
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(text: &str, by: &str) -> Item {
+        serde_json::from_value(serde_json::json!({
+            "by": by, "id": 1, "parent": 0, "text": text, "time": 0, "type": "comment"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_mentions() {
+        assert_eq!(find_mention("thanks @Dang!", "dang"), Some(true));
+        assert_eq!(find_mention("as dang said", "dang"), Some(false));
+        assert_eq!(find_mention("dang_it and dangerous", "dang"), None);
+
+        let mut mentions = Mentions::new();
+        mentions.watch("dang").watch("pg");
+        let found = mentions.check(&comment("<p>pg and @dang agree", "a"));
+        let names: Vec<_> = found.iter().map(|m| m.username.as_str()).collect();
+        assert_eq!(names, ["dang", "pg"]);
+        assert!(mentions
+            .check(&comment("I, dang, agree", "dang"))
+            .is_empty());
+
+        mentions.explicit_only(true);
+        let found = mentions.check(&comment("pg and @dang agree", "a"));
+        assert_eq!(found.len(), 1);
+        assert!(found[0].explicit);
+    }
+}
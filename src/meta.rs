@@ -0,0 +1,99 @@
+//! Metadata of the responses behind returned values.
+//!
+//! The `*_with_meta` methods of the async client, e.g.
+//! [`HnClient::get_item_with_meta`], return the value together with a
+//! [`ResponseMeta`] describing where it came from and how long it took,
+//! e.g. to integrate with an external cache or to debug slow requests.
+//!
+//! ```rust,no_run
+//! use hn_api::nonblocking::HnClient;
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let item = client.get_item_with_meta(8863).await?;
+//! println!(
+//!     "{} bytes in {:?}, etag {:?}",
+//!     item.meta.bytes, item.meta.elapsed, item.meta.etag
+//! );
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`HnClient::get_item_with_meta`]: crate::nonblocking::HnClient::get_item_with_meta
+
+use std::time::Duration;
+
+/// Where a response came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseSource {
+    /// A request to the server, possibly shared with concurrent callers.
+    Network,
+    /// The cache store of the client.
+    Cache,
+    /// A response fetched ahead of time with `HnClient::prefetch`.
+    Prefetch,
+    /// A cassette replayed in tests.
+    Replay,
+}
+
+/// Describes the response a value was decoded from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseMeta {
+    /// Where the response came from.
+    pub source: ResponseSource,
+    /// The HTTP status code. 200 unless the response came from the network.
+    pub status: u16,
+    /// The `ETag` header, if any.
+    pub etag: Option<String>,
+    /// The `Date` header, if any.
+    pub date: Option<String>,
+    /// The `Content-Type` header, if any.
+    pub content_type: Option<String>,
+    /// The time from starting the request to reading the whole body,
+    /// including retries.
+    pub elapsed: Duration,
+    /// The size of the body in bytes.
+    pub bytes: usize,
+    /// How often the request was retried after being throttled.
+    pub retries: u32,
+}
+
+impl ResponseMeta {
+    /// Describe a response that was not received from the network.
+    pub(crate) fn served(source: ResponseSource, bytes: usize) -> Self {
+        Self {
+            source,
+            status: 200,
+            etag: None,
+            date: None,
+            content_type: None,
+            elapsed: Duration::ZERO,
+            bytes,
+            retries: 0,
+        }
+    }
+}
+
+/// A value together with the metadata of its response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WithMeta<T> {
+    /// The value itself.
+    pub value: T,
+    /// The metadata of the response.
+    pub meta: ResponseMeta,
+}
+
+impl<T> WithMeta<T> {
+    /// Transform the contained value, keeping the metadata.
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> WithMeta<U> {
+        WithMeta {
+            value: f(self.value),
+            meta: self.meta,
+        }
+    }
+
+    /// Discard the metadata and return the value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
@@ -0,0 +1,182 @@
+//! Tell which items and accounts were hidden by flags or moderators, by
+//! cross-referencing the API with the `[dead]` and `[flagged]` markers of
+//! the website.
+//!
+//! The API reports dead and deleted items but no flags, while the website
+//! marks flagged items and may show dead items the API still returns as
+//! alive, or the other way around. Accounts whose new posts are all dead are
+//! likely banned without notice ("shadow banned").
+//!
+//! ```rust,no_run
+//! use hn_api::nonblocking::HnClient;
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! if let Some(account) = client.account_moderation("pg", 10).await? {
+//!     println!("{} likely dead: {}", account.username, account.likely_dead());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use futures::{stream, StreamExt, TryStreamExt};
+
+use super::{
+    nonblocking::HnClient,
+    types::{ItemId, ModerationStatus, Username},
+    Result,
+};
+
+/// The number of existing, not deleted items an account needs for
+/// [`AccountModeration::likely_dead`].
+const MIN_POSTED: usize = 3;
+
+/// The moderation status of the latest submissions of an account, see
+/// [`HnClient::account_moderation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountModeration {
+    /// The username of the account.
+    pub username: Username,
+    /// The checked submissions and their status, newest first. Items neither
+    /// the API nor the website know are skipped.
+    pub items: Vec<(ItemId, ModerationStatus)>,
+}
+
+impl AccountModeration {
+    /// Return the number of checked submissions with the specified status.
+    pub fn count(&self, status: ModerationStatus) -> usize {
+        self.items
+            .iter()
+            .filter(|(_, item)| *item == status)
+            .count()
+    }
+
+    /// Return whether the account is likely dead, i.e. at least 3 of its
+    /// checked submissions weren't deleted, and all of those are dead.
+    pub fn likely_dead(&self) -> bool {
+        let posted = self.items.len() - self.count(ModerationStatus::Deleted);
+        posted >= MIN_POSTED && self.count(ModerationStatus::Dead) == posted
+    }
+}
+
+impl HnClient {
+    /// Return the moderation status of the item with the specified id, the
+    /// most severe of what the API reports and what the item page of the
+    /// website shows. `None` if neither knows the item.
+    pub async fn item_moderation_status(
+        &self,
+        id: impl Into<ItemId>,
+    ) -> Result<Option<ModerationStatus>> {
+        let id = id.into();
+        let (item, page) = futures::try_join!(self.try_get_item(id), self.scrape_item(id))?;
+        let reported = item.map(|item| item.moderation_status());
+        let shown = page.map(|page| page.status);
+        Ok(reported.max(shown))
+    }
+
+    /// Check the moderation status of the latest `limit` submissions of the
+    /// user with the specified username, see
+    /// [`HnClient::item_moderation_status`]. `None` if the user doesn't exist.
+    ///
+    /// Every submission takes two requests, one to the API and one to the
+    /// website.
+    pub async fn account_moderation(
+        &self,
+        username: &str,
+        limit: usize,
+    ) -> Result<Option<AccountModeration>> {
+        let user = match self.try_get_user(username).await? {
+            Some(user) => user,
+            None => return Ok(None),
+        };
+        let items = stream::iter(user.submitted.into_iter().take(limit))
+            .map(|id| async move {
+                let status = self.item_moderation_status(id).await?;
+                Result::Ok(status.map(|status| (id, status)))
+            })
+            .buffered(self.batch_size().max(1))
+            .try_filter_map(|item| async move { Ok(item) })
+            .try_collect()
+            .await?;
+        Ok(Some(AccountModeration {
+            username: user.id,
+            items,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cassette::Cassette;
+
+    #[tokio::test]
+    async fn test_account_moderation() {
+        let api = "https://hacker-news.firebaseio.com/v0";
+        let site = "https://news.ycombinator.com";
+        let missing = "<html><body>No such item.</body></html>";
+        let mut cassette = Cassette::new();
+        cassette
+            .push(
+                format!("{}/user/spam.json", api),
+                r#"{"id": "spam", "created": 0, "karma": 1, "submitted": [4, 3, 2, 1, 0]}"#,
+            )
+            .push(format!("{}/user/nobody.json", api), "null")
+            .push(
+                format!("{}/item/4.json", api),
+                r#"{"id": 4, "by": "spam", "dead": true, "parent": 9, "text": "", "time": 0, "type": "comment"}"#,
+            )
+            .push(format!("{}/item?id=4", site), missing)
+            // Alive according to the API, but marked on the website.
+            .push(
+                format!("{}/item/3.json", api),
+                r#"{"id": 3, "by": "spam", "parent": 9, "text": "", "time": 0, "type": "comment"}"#,
+            )
+            .push(
+                format!("{}/item?id=3", site),
+                r#"<tr class="athing" id="3"><td><span class="comhead"><a href="user?id=spam" class="hnuser">spam</a> [dead]</span><div class="commtext c00"></div></td></tr>"#,
+            )
+            .push(
+                format!("{}/item/2.json", api),
+                r#"{"id": 2, "deleted": true, "parent": 9, "time": 0, "type": "comment"}"#,
+            )
+            .push(format!("{}/item?id=2", site), missing)
+            .push(
+                format!("{}/item/1.json", api),
+                r#"{"id": 1, "by": "spam", "dead": true, "title": "Buy", "time": 0, "type": "story"}"#,
+            )
+            .push(format!("{}/item?id=1", site), missing)
+            .push(format!("{}/item/0.json", api), "null")
+            .push(format!("{}/item?id=0", site), missing)
+            .push(
+                format!("{}/item/5.json", api),
+                r#"{"id": 5, "by": "a", "score": 1, "title": "Story", "time": 0, "type": "story"}"#,
+            )
+            .push(
+                format!("{}/item?id=5", site),
+                r#"<tr class="athing submission" id="5"><td class="title"><span class="titleline"><a href="https://example.com">Story</a> [flagged]</span></td></tr>"#,
+            );
+        let client = HnClient::builder().replay(cassette).build().unwrap();
+
+        assert_eq!(
+            client.item_moderation_status(5).await.unwrap(),
+            Some(ModerationStatus::Flagged)
+        );
+        assert_eq!(client.item_moderation_status(0).await.unwrap(), None);
+
+        let account = client.account_moderation("spam", 5).await.unwrap().unwrap();
+        assert_eq!(
+            account.items,
+            [
+                (ItemId(4), ModerationStatus::Dead),
+                (ItemId(3), ModerationStatus::Dead),
+                (ItemId(2), ModerationStatus::Deleted),
+                (ItemId(1), ModerationStatus::Dead),
+            ]
+        );
+        assert!(account.likely_dead());
+        let account = client.account_moderation("spam", 2).await.unwrap().unwrap();
+        assert!(!account.likely_dead());
+        assert_eq!(client.account_moderation("nobody", 5).await.unwrap(), None);
+    }
+}
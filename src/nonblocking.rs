@@ -1,18 +1,19 @@
 //! A simple asynchronous Hacker News API (v0) client library based on reqwest
 //! and serde.
 //!
-//! The library currently implements no caching. It simply exposes endpoints as
-//! methods.
+//! The library simply exposes endpoints as methods, responses are only cached
+//! if a [`cache`](crate::cache) store is configured. Identical requests that
+//! are in flight at the same time (for example the same author fetched from
+//! several tasks) are coalesced into a single HTTP call.
 //!
-//! Furthermore, there is no realtime functionality. If you need that, you
-//! should probably use a firebase client crate and subscribe to the live
-//! endpoints directly.
+//! Story listings can be followed in realtime through the Firebase event
+//! stream, see [`live`](crate::live).
 //!
 //! API Docs: <https://github.com/HackerNews/API>
 //!
 //! ## Usage
 //!
-//! ```rust
+//! ```rust,no_run
 //! use hn_api::nonblocking::HnClient;
 //!
 //! #[tokio::main]
@@ -35,69 +36,1612 @@
 
 #![deny(missing_docs)]
 
-use std::{fmt::Display, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fmt::{self, Display},
+    future::Future,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
 
-use futures::future::{join_all, OptionFuture};
-use reqwest::{self, Client};
+use bytes::Bytes;
+use futures::{
+    future::{self, BoxFuture, FutureExt, Shared, WeakShared},
+    stream::{self, Stream, StreamExt, TryStreamExt},
+};
+use reqwest::{
+    self,
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, DATE, ETAG, RETRY_AFTER},
+    Client, Response, StatusCode, Url,
+};
+use serde::de::DeserializeOwned;
+use tokio::sync::Semaphore;
 
-use super::{types, HnClientError, HnClientError::*, Result};
+use super::{
+    ancestry::ParentCache,
+    bus::EventBus,
+    cache::{CachePolicy, CacheStore},
+    cassette::{Cassette, CassetteMode},
+    circuit::{CircuitBreaker, CircuitCallback, CircuitState},
+    html,
+    meta::{ResponseMeta, ResponseSource, WithMeta},
+    permalink::SITE_BASE_URL,
+    poll::{PollResults, PollWithOptions},
+    query::{self, ItemQuery},
+    runtime::{self, Transport},
+    shutdown::Shutdown,
+    staleness::{StaleReason, Staleness, Tracked},
+    stats::{ClientStats, StatsRecorder},
+    tree::{CommentTree, Continuation, PartialTree, TreeLimits, TreeStats, VisitedIds},
+    types, validation,
+    HnClientError::*,
+    RequestError, Result,
+};
 
 static API_BASE_URL: &str = "https://hacker-news.firebaseio.com/v0";
 
+type ResponseFuture = BoxFuture<'static, Result<(Bytes, ResponseMeta)>>;
+
+/// A response body that can be awaited by several callers at once.
+type SharedResponse = Shared<ResponseFuture>;
+
+/// The requests in flight per URL. A request is cancelled once none of its
+/// callers awaits it anymore.
+type InFlight = Arc<Mutex<HashMap<String, WeakShared<ResponseFuture>>>>;
+
+/// Response bodies per URL, with the time they were received.
+type ResponseBodies = Arc<Mutex<HashMap<String, (Instant, Bytes)>>>;
+
+/// How long a prefetched response is kept for the next request.
+const PREFETCH_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// The number of responses cached by [`HnClient::recommended`].
+const RECOMMENDED_CACHE_ENTRIES: usize = 10_000;
+
+/// The number of responses a client keeps to serve stale, see
+/// [`HnClientBuilder::serve_stale`].
+const MAX_STALE_ENTRIES: usize = 10_000;
+
+/// The last successful response body per URL, with the time it was received.
+///
+/// Responses older than the maximum age are dropped, and so are the oldest
+/// ones beyond [`MAX_STALE_ENTRIES`].
+struct StaleStore {
+    max_age: Duration,
+    entries: HashMap<String, (Instant, Bytes)>,
+    /// The stored URLs, oldest first. URLs stored again are listed again.
+    order: VecDeque<(Instant, String)>,
+}
+
+impl StaleStore {
+    fn new(max_age: Duration) -> Self {
+        Self {
+            max_age,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Return the body stored for `url` and when it was received, unless it
+    /// is too old.
+    fn get(&self, url: &str) -> Option<(Instant, Bytes)> {
+        let (received, body) = self.entries.get(url)?;
+        Some((*received, body.clone())).filter(|_| received.elapsed() <= self.max_age)
+    }
+
+    fn insert(&mut self, url: String, body: Bytes) {
+        let received = Instant::now();
+        self.order.push_back((received, url.clone()));
+        self.entries.insert(url, (received, body));
+        while self.order.front().is_some_and(|(stored, _)| {
+            self.order.len() > MAX_STALE_ENTRIES || stored.elapsed() > self.max_age
+        }) {
+            let (stored, url) = self.order.pop_front().expect("not empty");
+            if self
+                .entries
+                .get(&url)
+                .is_some_and(|(received, _)| *received == stored)
+            {
+                self.entries.remove(&url);
+            }
+        }
+    }
+}
+
+/// The longest time a throttled request waits before it is retried.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How many ids after a missing id are probed during a timestamp search.
+const MAX_PROBE_GAP: u32 = 20;
+
+/// A callback observing throttled requests.
+type BackoffCallback = Arc<dyn Fn(&Backoff) + Send + Sync>;
+
+/// A request that was throttled by the server and will be retried, see
+/// [`HnClientBuilder::on_backoff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backoff {
+    /// The throttled URL.
+    pub url: String,
+    /// The HTTP status code, 429 or 503.
+    pub status: u16,
+    /// How long the client waits before retrying.
+    pub delay: Duration,
+    /// The number of the upcoming retry, starting at 1.
+    pub attempt: u32,
+}
+
+/// A callback observing rate limited responses.
+type RateLimitCallback = Arc<dyn Fn(&RateLimited) + Send + Sync>;
+
+/// A response with status 429 or 503, see
+/// [`HnClientBuilder::on_rate_limited`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimited {
+    /// The requested URL.
+    pub url: String,
+    /// The HTTP status code, 429 or 503.
+    pub status: u16,
+    /// The delay the server asked for with a `Retry-After` header, if any.
+    pub retry_after: Option<Duration>,
+    /// Whether the request will be retried. `false` once the retries are
+    /// used up, in which case the request fails.
+    pub retrying: bool,
+}
+
 /// The API client.
+///
+/// The client is `Send + Sync`, and cloning it is cheap: clones share the
+/// connection pool, caches, limits and statistics, so a client can be put
+/// into the shared state of a web framework as is.
+#[derive(Clone)]
 pub struct HnClient {
     client: Client,
+    transport: Transport,
+    in_flight: InFlight,
+    limiter: Option<Arc<Semaphore>>,
+    stale_store: Option<Arc<Mutex<StaleStore>>>,
+    prefetched: ResponseBodies,
+    batch_size: usize,
+    throttle_retries: u32,
+    on_backoff: Option<BackoffCallback>,
+    on_retry: Option<BackoffCallback>,
+    on_rate_limited: Option<RateLimitCallback>,
+    max_response_size: u64,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    default_headers: HeaderMap,
+    debug_http: bool,
+    breaker: Option<Arc<CircuitBreaker>>,
+    stats: Arc<StatsRecorder>,
+    cassette: Option<Arc<CassetteMode>>,
+    cache: Option<Arc<dyn CacheStore>>,
+    cache_policy: CachePolicy,
+    strict: bool,
+    bus: Arc<EventBus>,
+    parents: Arc<ParentCache>,
+    shutdown: Arc<Shutdown>,
+    #[cfg(feature = "otel")]
+    telemetry: Arc<crate::otel::Telemetry>,
+    #[cfg(feature = "tower")]
+    service: Option<crate::service::HttpService>,
+}
+
+impl fmt::Debug for HnClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HnClient")
+            .field("max_concurrent_requests", &self.limiter.is_some())
+            .field("serve_stale", &self.stale_store.is_some())
+            .field("cache", &self.cache.is_some())
+            .field("batch_size", &self.batch_size)
+            .field("throttle_retries", &self.throttle_retries)
+            .field("circuit_state", &self.circuit_state())
+            .field("strict", &self.strict)
+            .field("debug_http", &self.debug_http)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A builder to configure an [`HnClient`].
+pub struct HnClientBuilder {
+    timeout: Duration,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    default_headers: HeaderMap,
+    debug_http: bool,
+    invalid_header: Option<String>,
+    max_concurrent_requests: Option<usize>,
+    max_stale_age: Option<Duration>,
+    batch_size: usize,
+    throttle_retries: u32,
+    on_backoff: Option<BackoffCallback>,
+    on_retry: Option<BackoffCallback>,
+    on_rate_limited: Option<RateLimitCallback>,
+    max_response_size: u64,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Option<Duration>>,
+    tcp_keepalive: Option<Duration>,
+    http2_adaptive_window: bool,
+    circuit_breaker: Option<(u32, Duration)>,
+    on_circuit_change: Option<CircuitCallback>,
+    cassette: Option<CassetteMode>,
+    cache: Option<Arc<dyn CacheStore>>,
+    cache_policy: CachePolicy,
+    strict: bool,
+    subscription_interval: Duration,
+    #[cfg(feature = "tower")]
+    service: Option<crate::service::HttpService>,
+}
+
+impl Default for HnClientBuilder {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            connect_timeout: None,
+            read_timeout: None,
+            default_headers: HeaderMap::new(),
+            debug_http: false,
+            invalid_header: None,
+            max_concurrent_requests: None,
+            max_stale_age: None,
+            batch_size: 100,
+            throttle_retries: 3,
+            on_backoff: None,
+            on_retry: None,
+            on_rate_limited: None,
+            max_response_size: super::DEFAULT_MAX_RESPONSE_SIZE,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            tcp_keepalive: None,
+            http2_adaptive_window: false,
+            circuit_breaker: None,
+            on_circuit_change: None,
+            cassette: None,
+            cache: None,
+            cache_policy: CachePolicy::default(),
+            strict: false,
+            subscription_interval: Duration::from_secs(30),
+            #[cfg(feature = "tower")]
+            service: None,
+        }
+    }
+}
+
+impl HnClientBuilder {
+    /// Return a builder preconfigured with defaults suited for long-running
+    /// production use, see [`HnClient::recommended`].
+    pub fn recommended() -> Self {
+        #[cfg(feature = "moka")]
+        let cache = crate::cache::MokaStore::new(RECOMMENDED_CACHE_ENTRIES as u64);
+        #[cfg(not(feature = "moka"))]
+        let cache = crate::cache::MemoryStore::with_capacity(RECOMMENDED_CACHE_ENTRIES);
+        Self::default()
+            .max_concurrent_requests(16)
+            .throttle_retries(3)
+            .circuit_breaker(5, Duration::from_secs(30))
+            .cache(cache)
+            .cache_policy(CachePolicy::default())
+            .serve_stale(Duration::from_secs(60 * 60))
+            .tcp_keepalive(Duration::from_secs(60))
+    }
+
+    /// Set the overall timeout for a single request, from connecting to
+    /// reading the last byte of the response. Defaults to 10 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the timeout for establishing a connection, including the DNS
+    /// lookup and TLS handshake. Only limited by the overall timeout by
+    /// default.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the longest wait for the next chunk of a response body. If a
+    /// connect timeout is set too, waiting for the response headers is
+    /// limited to the sum of both. Only limited by the overall timeout by
+    /// default.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Add a header to every request, e.g. `X-App-Name` or a tracing
+    /// propagation header. Replaces a previous value of the same header,
+    /// and can override the `User-Agent`.
+    ///
+    /// An invalid name or value makes [`HnClientBuilder::build`] fail.
+    pub fn default_header(mut self, name: &str, value: &str) -> Self {
+        match (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            (Ok(name), Ok(value)) => {
+                self.default_headers.insert(name, value);
+            }
+            _ => self.invalid_header = Some(name.to_string()),
+        }
+        self
+    }
+
+    /// Add headers to every request, see [`HnClientBuilder::default_header`].
+    pub fn default_headers(mut self, headers: HeaderMap) -> Self {
+        for (name, value) in headers {
+            if let Some(name) = name {
+                self.default_headers.insert(name, value);
+            }
+        }
+        self
+    }
+
+    /// Log every exchange with the server at debug level with the `log`
+    /// crate, target `hn_api::http`: URL, status, latency and the start of
+    /// the body, e.g. to find out why an item is `None`.
+    ///
+    /// Also enabled by setting the `HN_API_DEBUG_HTTP` environment variable
+    /// to anything but `0`. Disabled by default.
+    pub fn debug_http(mut self, enabled: bool) -> Self {
+        self.debug_http = enabled;
+        self
+    }
+
+    /// Cap the number of HTTP requests the client runs at the same time.
+    ///
+    /// The limit is shared by every method of the client, including the
+    /// batch methods like [`HnClient::get_items`]. At least 1, unlimited by
+    /// default.
+    pub fn max_concurrent_requests(mut self, max: usize) -> Self {
+        self.max_concurrent_requests = Some(max.max(1));
+        self
+    }
+
+    /// Set how many requests a batch method like [`HnClient::get_items`]
+    /// keeps outstanding at once. This is also the chunk size of the
+    /// `*_chunked` methods. Defaults to 100.
+    pub fn batch_size(mut self, size: usize) -> Self {
+        self.batch_size = size.max(1);
+        self
+    }
+
+    /// Set how many times a request is retried when the server answers with
+    /// 429 Too Many Requests or 503 Service Unavailable.
+    ///
+    /// The client waits as long as the `Retry-After` header asks, in seconds
+    /// or until an HTTP date, or doubles the wait starting at 2 seconds if
+    /// there is none, never longer than a minute. Defaults to 3, 0 disables
+    /// retrying.
+    pub fn throttle_retries(mut self, retries: u32) -> Self {
+        self.throttle_retries = retries;
+        self
+    }
+
+    /// Call `callback` whenever a throttled request is about to wait before
+    /// being retried, e.g. to show the backoff state to the user.
+    pub fn on_backoff<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Backoff) + Send + Sync + 'static,
+    {
+        self.on_backoff = Some(Arc::new(callback));
+        self
+    }
+
+    /// Call `callback` whenever a throttled request is sent again after
+    /// waiting, e.g. to count retries in the application's own metrics.
+    pub fn on_retry<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Backoff) + Send + Sync + 'static,
+    {
+        self.on_retry = Some(Arc::new(callback));
+        self
+    }
+
+    /// Call `callback` whenever the server answers with 429 Too Many
+    /// Requests or 503 Service Unavailable, including the last response
+    /// when the retries are used up.
+    pub fn on_rate_limited<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&RateLimited) + Send + Sync + 'static,
+    {
+        self.on_rate_limited = Some(Arc::new(callback));
+        self
+    }
+
+    /// Fail with `ResponseTooLarge` instead of reading a response body
+    /// larger than `bytes`. Defaults to 16 MiB.
+    pub fn max_response_size(mut self, bytes: u64) -> Self {
+        self.max_response_size = bytes;
+        self
+    }
+
+    /// Set how many idle connections per host are kept open for reuse.
+    /// Unlimited by default.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Set how long an idle connection is kept open, `None` keeps it
+    /// forever. Defaults to 90 seconds.
+    pub fn pool_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Send TCP keepalive probes on idle connections at the specified
+    /// interval. Disabled by default.
+    pub fn tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Let HTTP/2 connections adapt their flow control window to the
+    /// measured bandwidth. Disabled by default.
+    pub fn http2_adaptive_window(mut self, enabled: bool) -> Self {
+        self.http2_adaptive_window = enabled;
+        self
+    }
+
+    /// Stop sending requests for `cooldown` after `failures` consecutive
+    /// requests failed with a timeout, network error or server error.
+    ///
+    /// While the circuit is open, every method fails immediately with
+    /// `CircuitOpen`. After the cooldown, a single probe request decides
+    /// whether it closes again, see [`circuit`](crate::circuit). Disabled by
+    /// default.
+    pub fn circuit_breaker(mut self, failures: u32, cooldown: Duration) -> Self {
+        self.circuit_breaker = Some((failures, cooldown));
+        self
+    }
+
+    /// Call `callback` whenever the circuit breaker changes its state, e.g.
+    /// to export it as a metric.
+    pub fn on_circuit_change<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(CircuitState) + Send + Sync + 'static,
+    {
+        self.on_circuit_change = Some(Arc::new(callback));
+        self
+    }
+
+    /// Check the invariants of every fetched item, e.g. that stories have a
+    /// title, and fail with `ValidationError` if any is broken.
+    ///
+    /// Lenient by default, where missing fields are filled with defaults,
+    /// see [`validation`](crate::validation).
+    pub fn strict(mut self, enabled: bool) -> Self {
+        self.strict = enabled;
+        self
+    }
+
+    /// Set how often the tasks behind [`HnClient::subscribe`] poll the API.
+    /// Defaults to 30 seconds.
+    pub fn subscription_interval(mut self, interval: Duration) -> Self {
+        self.subscription_interval = interval;
+        self
+    }
+
+    /// Record every response to a cassette file at `path`, see
+    /// [`cassette`](crate::cassette).
+    ///
+    /// The file is written by [`HnClient::save_cassette`], and when the
+    /// client and all of its clones are dropped. Errors writing it on drop
+    /// are logged.
+    pub fn record(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cassette = Some(CassetteMode::record(path.into()));
+        self
+    }
+
+    /// Answer all requests from `cassette` instead of the network, see
+    /// [`cassette`](crate::cassette).
+    ///
+    /// Requests without a recorded response fail with `BackendError`.
+    pub fn replay(mut self, cassette: Cassette) -> Self {
+        self.cassette = Some(CassetteMode::replay(cassette));
+        self
+    }
+
+    /// Send all HTTP requests through `service` instead of the built-in
+    /// reqwest client, see [`service`](crate::service).
+    #[cfg(feature = "tower")]
+    pub fn service<S>(mut self, service: S) -> Self
+    where
+        S: tower_service::Service<reqwest::Request, Response = reqwest::Response>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+        S::Future: Send,
+    {
+        self.service = Some(crate::service::HttpService::new(service));
+        self
+    }
+
+    /// Look items, users and story listings up in `store` before sending a
+    /// request, and put successful responses into it, see
+    /// [`cache`](crate::cache).
+    ///
+    /// Pass an `Arc` to share the store with other clients. Disabled by
+    /// default.
+    pub fn cache(mut self, store: impl CacheStore + 'static) -> Self {
+        self.cache = Some(Arc::new(store));
+        self
+    }
+
+    /// Set how long cached responses are kept. Defaults to
+    /// [`CachePolicy::default`].
+    pub fn cache_policy(mut self, policy: CachePolicy) -> Self {
+        self.cache_policy = policy;
+        self
+    }
+
+    /// Keep the last successful response of every request, and serve it
+    /// from the `*_tracked` methods if a fresh request fails.
+    ///
+    /// Responses older than `max_age` are dropped, and so are the oldest
+    /// ones beyond the latest 10,000. Disabled by default.
+    pub fn serve_stale(mut self, max_age: Duration) -> Self {
+        self.max_stale_age = Some(max_age);
+        self
+    }
+
+    /// Create the configured `HnClient` instance.
+    pub fn build(self) -> Result<HnClient> {
+        self.check_headers()?;
+        let mut client = reqwest::Client::builder()
+            .timeout(self.timeout)
+            .tcp_keepalive(self.tcp_keepalive)
+            .http2_adaptive_window(self.http2_adaptive_window);
+        if let Some(timeout) = self.connect_timeout {
+            client = client.connect_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            client = client.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            client = client.pool_idle_timeout(timeout);
+        }
+        #[cfg(feature = "rustls-tls")]
+        {
+            client = client.use_rustls_tls();
+        }
+        let transport = Transport::new(self.timeout, self.connect_timeout, self.tcp_keepalive)?;
+        let client = self.build_on(client.build()?);
+        Ok(HnClient {
+            transport,
+            ..client
+        })
+    }
+
+    /// Create the configured `HnClient` instance on top of an existing
+    /// reqwest client. The overall timeout, connect timeout and connection
+    /// settings of this builder are ignored, those of `client` apply instead.
+    ///
+    /// Requests are always sent with `client`, even with the
+    /// `runtime-agnostic` feature, so the instance needs a Tokio runtime.
+    /// Fails like [`HnClientBuilder::build`] on invalid default headers.
+    pub fn build_with_client(self, client: Client) -> Result<HnClient> {
+        self.check_headers()?;
+        Ok(self.build_on(client))
+    }
+
+    fn check_headers(&self) -> Result<()> {
+        match &self.invalid_header {
+            Some(name) => Err(BackendError(format!("invalid default header {}", name))),
+            None => Ok(()),
+        }
+    }
+
+    fn build_on(self, client: Client) -> HnClient {
+        let on_circuit_change = self.on_circuit_change;
+        let breaker = self.circuit_breaker.map(|(failures, cooldown)| {
+            Arc::new(CircuitBreaker::new(failures, cooldown, on_circuit_change))
+        });
+        HnClient {
+            client,
+            transport: Transport::default(),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            limiter: self
+                .max_concurrent_requests
+                .map(|max| Arc::new(Semaphore::new(max))),
+            stale_store: self
+                .max_stale_age
+                .map(|max_age| Arc::new(Mutex::new(StaleStore::new(max_age)))),
+            prefetched: Arc::new(Mutex::new(HashMap::new())),
+            batch_size: self.batch_size,
+            throttle_retries: self.throttle_retries,
+            on_backoff: self.on_backoff,
+            on_retry: self.on_retry,
+            on_rate_limited: self.on_rate_limited,
+            max_response_size: self.max_response_size,
+            connect_timeout: self.connect_timeout,
+            read_timeout: self.read_timeout,
+            default_headers: self.default_headers,
+            debug_http: self.debug_http || super::debug_http_from_env(),
+            breaker,
+            stats: Arc::default(),
+            cassette: self.cassette.map(Arc::new),
+            cache: self.cache,
+            cache_policy: self.cache_policy,
+            strict: self.strict,
+            bus: Arc::new(EventBus::new(self.subscription_interval)),
+            parents: Arc::default(),
+            shutdown: Arc::default(),
+            #[cfg(feature = "otel")]
+            telemetry: Arc::new(crate::otel::Telemetry::new()),
+            #[cfg(feature = "tower")]
+            service: self.service,
+        }
+    }
+}
+
+/// Per-call overrides for the time a request may take.
+///
+/// Both limits only shorten the client-wide timeout set with
+/// [`HnClientBuilder::timeout`], they cannot extend it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestOptions {
+    /// The maximum time a single request may take.
+    pub timeout: Option<Duration>,
+    /// The point in time by which the whole call must be finished. For batch
+    /// methods, all requests still outstanding at the deadline are cancelled.
+    pub deadline: Option<Instant>,
+}
+
+impl RequestOptions {
+    /// Return the time left until the first of both limits is reached.
+    fn remaining(&self) -> Option<Duration> {
+        let until_deadline = self
+            .deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        match (self.timeout, until_deadline) {
+            (Some(timeout), Some(until_deadline)) => Some(timeout.min(until_deadline)),
+            (timeout, until_deadline) => timeout.or(until_deadline),
+        }
+    }
+
+    /// Run `future`, failing with `TimeoutError` if it exceeds the limits.
+    async fn apply<T>(&self, future: impl Future<Output = Result<T>>) -> Result<T> {
+        match self.remaining() {
+            Some(limit) => runtime::timeout(limit, future)
+                .await
+                .unwrap_or(Err(TimeoutError)),
+            None => future.await,
+        }
+    }
 }
 
 impl HnClient {
     /// Create a new `HnClient` instance.
     pub fn init() -> Result<Self> {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(10))
-            .build()?;
-        Ok(Self { client })
+        Self::builder().build()
+    }
+
+    /// Create a new `HnClient` instance sharing an existing reqwest client,
+    /// including its connection pool, proxy and TLS settings.
+    pub fn init_with_client(client: Client) -> Self {
+        Self::builder().build_on(client)
+    }
+
+    /// Create a new `HnClient` instance with defaults suited for long-running
+    /// production use.
+    ///
+    /// Unlike [`HnClient::init`], the client
+    ///
+    /// - limits itself to 16 concurrent requests,
+    /// - retries throttled requests up to 3 times,
+    /// - caches up to 10,000 responses for the durations of
+    ///   [`CachePolicy::default`], in a [`MokaStore`](crate::cache::MokaStore)
+    ///   with the `moka` feature and a
+    ///   [`MemoryStore`](crate::cache::MemoryStore) otherwise,
+    /// - stops sending requests for 30 seconds after 5 consecutive failures,
+    ///   see [`HnClientBuilder::circuit_breaker`],
+    /// - serves responses up to one hour old from the `*_tracked` methods
+    ///   while the API is unreachable,
+    /// - and keeps idle connections alive with TCP keepalive probes every
+    ///   minute.
+    ///
+    /// Tracing is not configured by the preset: with the `otel` feature every
+    /// client records spans and metrics anyway, and
+    /// [`HnClientBuilder::debug_http`] stays off since it logs response
+    /// bodies; set `HN_API_DEBUG_HTTP` to enable it without a rebuild. Use
+    /// [`HnClientBuilder::recommended`] to adjust individual settings.
+    pub fn recommended() -> Result<Self> {
+        HnClientBuilder::recommended().build()
+    }
+
+    /// Return a builder to configure a new `HnClient` instance.
+    pub fn builder() -> HnClientBuilder {
+        HnClientBuilder::default()
+    }
+
+    /// Return the state of the circuit breaker, or `None` if it is disabled.
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.breaker.as_ref().map(|breaker| breaker.state())
+    }
+
+    /// Return the counters of all requests made by this client so far.
+    pub fn stats(&self) -> ClientStats {
+        self.stats.snapshot()
+    }
+
+    /// Write the responses recorded so far to the cassette file set with
+    /// [`HnClientBuilder::record`]. Does nothing when not recording.
+    pub fn save_cassette(&self) -> Result<()> {
+        match &self.cassette {
+            Some(cassette) => cassette.save(),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    pub(crate) fn event_bus(&self) -> &EventBus {
+        &self.bus
+    }
+
+    pub(crate) fn parent_cache(&self) -> &ParentCache {
+        &self.parents
+    }
+
+    pub(crate) fn shutdown_state(&self) -> &Arc<Shutdown> {
+        &self.shutdown
+    }
+
+    /// Open the Firebase event stream of an API path, e.g. `topstories`.
+    /// The connection is closed after `max_duration`.
+    pub(crate) async fn open_event_stream(
+        &self,
+        path: &str,
+        max_duration: Duration,
+    ) -> Result<reqwest::Response> {
+        let request = self
+            .client
+            .get(format!("{}/{}.json", API_BASE_URL, path))
+            .headers(self.default_headers.clone())
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .timeout(max_duration);
+        Ok(self.transport.send(request).await?.error_for_status()?)
+    }
+
+    /// Run `fetch` for every input, keeping at most `batch_size` requests
+    /// outstanding. Results are in input order. Fails on the first error,
+    /// cancelling all outstanding requests.
+    async fn batch<I, T, F, Fut>(&self, inputs: I, fetch: F) -> Result<Vec<T>>
+    where
+        I: IntoIterator,
+        F: FnMut(I::Item) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        stream::iter(inputs)
+            .map(fetch)
+            .buffered(self.batch_size)
+            .try_collect()
+            .await
+    }
+
+    /// Fetch `url` and deserialize the JSON response body.
+    async fn fetch_json<T: DeserializeOwned>(&self, url: String) -> Result<T> {
+        Ok(self.fetch_json_with_meta(url).await?.value)
+    }
+
+    /// Fetch `url` and deserialize the JSON response body, keeping the
+    /// metadata of the response.
+    async fn fetch_json_with_meta<T: DeserializeOwned>(&self, url: String) -> Result<WithMeta<T>> {
+        let (body, meta) = self.fetch_shared(url.clone()).await?;
+        Ok(WithMeta {
+            value: super::decode_json(&url, Some(meta.status), &body)?,
+            meta,
+        })
+    }
+
+    /// Return the JSON response of any API endpoint, deserialized into `T`,
+    /// e.g. to call endpoints this crate does not support yet.
+    ///
+    /// The `path` is relative to the v0 API, e.g. `item/8863` or
+    /// `topstories.json?orderBy="$key"&limitToFirst=10`, `.json` is appended
+    /// if it is missing. The request is cached, coalesced and retried like
+    /// any other.
+    pub async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.fetch_json(super::api_url(API_BASE_URL, path)).await
+    }
+
+    /// Like [`HnClient::get_json`], with the metadata of the response.
+    pub async fn get_json_with_meta<T: DeserializeOwned>(&self, path: &str) -> Result<WithMeta<T>> {
+        self.fetch_json_with_meta(super::api_url(API_BASE_URL, path))
+            .await
+    }
+
+    /// Return the raw response body of any API endpoint, see
+    /// [`HnClient::get_json`].
+    pub async fn get_bytes(&self, path: &str) -> Result<Bytes> {
+        self.fetch(super::api_url(API_BASE_URL, path)).await
+    }
+
+    /// Fetch a page of the website, e.g. `favorites?id=pg`.
+    pub(crate) async fn get_page(&self, path: &str) -> Result<String> {
+        let body = self.fetch(format!("{}/{}", SITE_BASE_URL, path)).await?;
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+
+    /// Fetch `url` and deserialize the JSON response body. If the request
+    /// fails, fall back to the last successful response, marked as stale.
+    async fn get_json_tracked<T: DeserializeOwned>(&self, url: String) -> Result<Tracked<T>> {
+        let error = match self.fetch_shared(url.clone()).await {
+            Ok((body, meta)) => {
+                let value = super::decode_json(&url, Some(meta.status), &body)?;
+                return Ok(Tracked::fresh(value));
+            }
+            Err(error) => error,
+        };
+        let stored = self
+            .stale_store
+            .as_ref()
+            .and_then(|store| store.lock().unwrap().get(&url));
+        match stored {
+            Some((received, body)) => {
+                let staleness = Staleness {
+                    age: received.elapsed(),
+                    reason: match error {
+                        CircuitOpen => StaleReason::CircuitOpen,
+                        _ => StaleReason::RequestFailed(format!("{:?}", error)),
+                    },
+                };
+                Ok(Tracked::stale(
+                    super::decode_json(&url, None, &body)?,
+                    staleness,
+                ))
+            }
+            None => Err(error),
+        }
+    }
+
+    /// Fetch the body of `url`, see [`HnClient::fetch_shared`].
+    async fn fetch(&self, url: String) -> Result<Bytes> {
+        Ok(self.fetch_shared(url).await?.0)
+    }
+
+    /// Fetch the body of `url` and the metadata of the response, joining an
+    /// identical request that is already in flight instead of issuing a new
+    /// one.
+    ///
+    /// A prefetched response is served, and discarded, instead.
+    fn fetch_shared(&self, url: String) -> SharedResponse {
+        if let Some((received, body)) = self.prefetched.lock().unwrap().remove(&url) {
+            if received.elapsed() <= PREFETCH_TTL {
+                self.stats.record_hit();
+                let meta = ResponseMeta::served(ResponseSource::Prefetch, body.len());
+                return future::ready(Ok((body, meta))).boxed().shared();
+            }
+        }
+
+        if let Some(cassette) = self.cassette.as_ref().filter(|mode| mode.is_replay()) {
+            let response = cassette.respond(&url).map(|body| {
+                let meta = ResponseMeta::served(ResponseSource::Replay, body.len());
+                (body, meta)
+            });
+            return future::ready(response).boxed().shared();
+        }
+
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(response) = in_flight.get(&url).and_then(WeakShared::upgrade) {
+            self.stats.record_hit();
+            return response;
+        }
+
+        let client = self.client.clone();
+        let default_headers = self.default_headers.clone();
+        let debug_http = self.debug_http;
+        let transport = self.transport.clone();
+        let registry = Arc::clone(&self.in_flight);
+        let limiter = self.limiter.clone();
+        let stale_store = self.stale_store.clone();
+        let throttle_retries = self.throttle_retries;
+        let on_backoff = self.on_backoff.clone();
+        let on_retry = self.on_retry.clone();
+        let on_rate_limited = self.on_rate_limited.clone();
+        let max_response_size = self.max_response_size;
+        let read_timeout = self.read_timeout;
+        let headers_timeout = self.connect_timeout.zip(self.read_timeout);
+        let headers_timeout = headers_timeout.map(|(connect, read)| connect + read);
+        let breaker = self.breaker.clone();
+        let stats = Arc::clone(&self.stats);
+        let cassette = self.cassette.clone();
+        let cache = self.cache.clone().zip(self.cache_policy.entry(&url));
+        #[cfg(feature = "otel")]
+        let telemetry = Arc::clone(&self.telemetry);
+        #[cfg(feature = "tower")]
+        let service = self.service.clone();
+        let key = url.clone();
+        let response = async move {
+            #[cfg(feature = "otel")]
+            let mut span = telemetry.start(&key);
+            // Only taken for requests that reach the network, and released
+            // when the request is cancelled, see `CircuitPermit`.
+            let mut permit = None;
+            let result = async {
+                let started = Instant::now();
+                if let Some((store, (cache_key, _))) = &cache {
+                    if let Some(body) = store.get(cache_key).await {
+                        stats.record_hit();
+                        let meta = ResponseMeta::served(ResponseSource::Cache, body.len());
+                        return Ok((body, meta));
+                    }
+                }
+                if let Some(breaker) = &breaker {
+                    permit = Some(breaker.acquire().ok_or(CircuitOpen)?);
+                }
+                stats.record_miss();
+                let _permit = match &limiter {
+                    Some(limiter) => Some(limiter.acquire().await.expect("semaphore closed")),
+                    None => None,
+                };
+                let request = || client.get(&key).headers(default_headers.clone());
+                let log_failure = |sent: Instant, err: &super::HnClientError| {
+                    if debug_http {
+                        super::log_failure(&key, sent.elapsed(), err);
+                    }
+                };
+                let mut attempt = 0;
+                loop {
+                    stats.record_request(&key, attempt > 0);
+                    let sent = Instant::now();
+                    #[cfg(feature = "tower")]
+                    let response = limit_time(headers_timeout, async {
+                        match &service {
+                            Some(service) => service.call(request().build()?).await,
+                            None => transport.send(request()).await,
+                        }
+                    })
+                    .await;
+                    #[cfg(not(feature = "tower"))]
+                    let response = limit_time(headers_timeout, transport.send(request())).await;
+                    let response = response.inspect_err(|err| log_failure(sent, err))?;
+                    let status = response.status();
+                    let throttled = status == StatusCode::TOO_MANY_REQUESTS
+                        || status == StatusCode::SERVICE_UNAVAILABLE;
+                    if let (true, Some(on_rate_limited)) = (throttled, &on_rate_limited) {
+                        on_rate_limited(&RateLimited {
+                            url: key.clone(),
+                            status: status.as_u16(),
+                            retry_after: retry_after(response.headers()),
+                            retrying: attempt < throttle_retries,
+                        });
+                    }
+                    if !throttled || attempt == throttle_retries {
+                        let headers = response.headers();
+                        let content_type = headers.get(CONTENT_TYPE).cloned();
+                        let header = |name| {
+                            let value = headers.get(name)?.to_str().ok()?;
+                            Some(value.to_string())
+                        };
+                        let mut meta = ResponseMeta {
+                            source: ResponseSource::Network,
+                            status: status.as_u16(),
+                            etag: header(ETAG),
+                            date: header(DATE),
+                            content_type: header(CONTENT_TYPE),
+                            elapsed: Duration::ZERO,
+                            bytes: 0,
+                            retries: attempt,
+                        };
+                        let body = read_body(response, max_response_size, read_timeout)
+                            .await
+                            .inspect_err(|err| log_failure(sent, err))?;
+                        if debug_http {
+                            super::log_response(&key, meta.status, sent.elapsed(), Some(&body));
+                        }
+                        meta.elapsed = started.elapsed();
+                        meta.bytes = body.len();
+                        stats.record_bytes(body.len());
+                        if let Some(cassette) = &cassette {
+                            cassette.store(&key, status.as_u16(), &body);
+                        }
+                        if status.is_success() {
+                            if key.starts_with(API_BASE_URL) {
+                                let content_type = content_type.as_ref();
+                                super::check_json(content_type.and_then(|v| v.to_str().ok()))?;
+                            }
+                            if let Some((store, (cache_key, ttl))) = &cache {
+                                store.put(cache_key, body.clone(), *ttl).await;
+                            }
+                            return Ok((body, meta));
+                        }
+                        let error = RequestError::status(&key, status.as_u16(), &body);
+                        return Err(HttpError(Box::new(error)));
+                    }
+                    if debug_http {
+                        super::log_response(&key, status.as_u16(), sent.elapsed(), None);
+                    }
+                    attempt += 1;
+                    #[cfg(feature = "otel")]
+                    span.retry(attempt);
+                    let backoff = Backoff {
+                        url: key.clone(),
+                        status: status.as_u16(),
+                        delay: backoff_delay(response.headers(), attempt),
+                        attempt,
+                    };
+                    if let Some(on_backoff) = &on_backoff {
+                        on_backoff(&backoff);
+                    }
+                    runtime::sleep(backoff.delay).await;
+                    if let Some(on_retry) = &on_retry {
+                        on_retry(&backoff);
+                    }
+                }
+            }
+            .await;
+            if let Err(err) = &result {
+                stats.record_error(err);
+            }
+            #[cfg(feature = "otel")]
+            span.finish(&result);
+            if let Some(permit) = permit {
+                permit.record(!matches!(&result, Err(err) if err.is_retryable()));
+            }
+            if let (Ok((body, _)), Some(store)) = (&result, &stale_store) {
+                store.lock().unwrap().insert(key.clone(), body.clone());
+            }
+            registry.lock().unwrap().remove(&key);
+            result
+        }
+        .boxed()
+        .shared();
+
+        in_flight.retain(|_, response| response.upgrade().is_some());
+        if let Some(weak) = response.downgrade() {
+            in_flight.insert(url, weak);
+        }
+        response
+    }
+
+    /// Validate an item in strict mode.
+    fn check(&self, item: &Option<types::Item>) -> Result<()> {
+        match item {
+            Some(item) if self.strict => {
+                validation::validate(item).map_err(|err| ValidationError(Box::new(err)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fetch the items with the specified ids in the background, so that the
+    /// next request for each of them is answered without waiting.
+    ///
+    /// The requests respect the concurrency limit and batch size of the
+    /// client. Prefetched items are served once, for up to 5 minutes.
+    /// Failed requests are ignored. Must be called within a Tokio runtime,
+    /// unless the `runtime-agnostic` feature is enabled.
+    pub fn prefetch(&self, ids: &[impl Into<types::ItemId> + Copy]) {
+        let responses: Vec<_> = ids
+            .iter()
+            .map(|id| {
+                let id: types::ItemId = (*id).into();
+                let url = format!("{}/item/{}.json", API_BASE_URL, id);
+                (url.clone(), self.fetch_shared(url))
+            })
+            .collect();
+        let prefetched = Arc::clone(&self.prefetched);
+        let batch_size = self.batch_size;
+        self.spawn(async move {
+            stream::iter(responses)
+                .for_each_concurrent(batch_size, |(url, response)| {
+                    let prefetched = Arc::clone(&prefetched);
+                    async move {
+                        if let Ok((body, _)) = response.await {
+                            let mut prefetched = prefetched.lock().unwrap();
+                            prefetched
+                                .retain(|_, (received, _)| received.elapsed() <= PREFETCH_TTL);
+                            prefetched.insert(url, (Instant::now(), body));
+                        }
+                    }
+                })
+                .await;
+        });
     }
 
     /// Return the item with the specified id.
     ///
     /// May return error if item id is invalid or not exist.
-    pub async fn get_item(&self, id: u32) -> Result<types::Item> {
+    pub async fn get_item(&self, id: impl Into<types::ItemId>) -> Result<types::Item> {
+        let id = id.into();
         self.try_get_item(id).await?.ok_or(ItemNotFoundError(id))
     }
 
     /// Return the item with the specified id.
     ///
     /// May return `None` if item id is invalid.
-    pub async fn try_get_item(&self, id: u32) -> Result<Option<types::Item>> {
-        self.client
-            .get(&format!("{}/item/{}.json", API_BASE_URL, id))
-            .send()
-            .await?
-            .json()
+    pub async fn try_get_item(&self, id: impl Into<types::ItemId>) -> Result<Option<types::Item>> {
+        let id = id.into();
+        let item = self
+            .fetch_json(format!("{}/item/{}.json", API_BASE_URL, id))
+            .await?;
+        self.check(&item)?;
+        Ok(item)
+    }
+
+    /// Return the item with the specified id, marked as stale if it had to
+    /// be served from a previous response.
+    ///
+    /// See [`HnClientBuilder::serve_stale`].
+    pub async fn try_get_item_tracked(
+        &self,
+        id: impl Into<types::ItemId>,
+    ) -> Result<Tracked<Option<types::Item>>> {
+        let id = id.into();
+        let item = self
+            .get_json_tracked(format!("{}/item/{}.json", API_BASE_URL, id))
+            .await?;
+        self.check(&item.value)?;
+        Ok(item)
+    }
+
+    /// Return the item with the specified id, with the metadata of the
+    /// response.
+    ///
+    /// May return error if item id is invalid or not exist.
+    pub async fn get_item_with_meta(
+        &self,
+        id: impl Into<types::ItemId>,
+    ) -> Result<WithMeta<types::Item>> {
+        let id = id.into();
+        let item = self.try_get_item_with_meta(id).await?;
+        match item.value {
+            Some(value) => Ok(WithMeta {
+                value,
+                meta: item.meta,
+            }),
+            None => Err(ItemNotFoundError(id)),
+        }
+    }
+
+    /// Return the item with the specified id, with the metadata of the
+    /// response.
+    ///
+    /// May return `None` if item id is invalid.
+    pub async fn try_get_item_with_meta(
+        &self,
+        id: impl Into<types::ItemId>,
+    ) -> Result<WithMeta<Option<types::Item>>> {
+        let id = id.into();
+        let item = self
+            .fetch_json_with_meta(format!("{}/item/{}.json", API_BASE_URL, id))
+            .await?;
+        self.check(&item.value)?;
+        Ok(item)
+    }
+
+    /// Return the item with the specified id as untyped JSON, including
+    /// fields this crate does not know about.
+    ///
+    /// Returns `Value::Null` if item id is invalid.
+    pub async fn get_item_raw(&self, id: impl Into<types::ItemId>) -> Result<serde_json::Value> {
+        let id = id.into();
+        self.fetch_json(format!("{}/item/{}.json", API_BASE_URL, id))
             .await
-            .map_err(HnClientError::from)
+    }
+
+    /// Return the item with the specified id, limiting the time the request
+    /// may take.
+    ///
+    /// May return error if item id is invalid or not exist.
+    pub async fn get_item_with(
+        &self,
+        id: impl Into<types::ItemId>,
+        options: RequestOptions,
+    ) -> Result<types::Item> {
+        options.apply(self.get_item(id)).await
+    }
+
+    /// Return the item with the specified id, limiting the time the request
+    /// may take.
+    ///
+    /// May return `None` if item id is invalid.
+    pub async fn try_get_item_with(
+        &self,
+        id: impl Into<types::ItemId>,
+        options: RequestOptions,
+    ) -> Result<Option<types::Item>> {
+        options.apply(self.try_get_item(id)).await
     }
 
     /// Return the items with the specified ids.
     ///
     /// May return error if item id is invalid or not exist.
     /// Fails if any of the request failed.
-    pub async fn get_items(&self, items: &[u32]) -> Result<Vec<types::Item>> {
-        join_all(items.iter().map(|id| self.get_item(*id)))
+    pub async fn get_items(
+        &self,
+        items: &[impl Into<types::ItemId> + Copy],
+    ) -> Result<Vec<types::Item>> {
+        self.batch(items.iter().copied(), |id| self.get_item(id))
             .await
-            .into_iter()
-            .collect()
     }
 
     /// Return the items with the specified ids.
     ///
     /// May return `None` if item id is invalid.
     /// Fails if any of the request failed.
-    pub async fn try_get_items(&self, items: &[u32]) -> Result<Vec<Option<types::Item>>> {
-        join_all(items.iter().map(|id| self.try_get_item(*id)))
+    pub async fn try_get_items(
+        &self,
+        items: &[impl Into<types::ItemId> + Copy],
+    ) -> Result<Vec<Option<types::Item>>> {
+        self.batch(items.iter().copied(), |id| self.try_get_item(id))
             .await
+    }
+
+    /// Return the items with the specified ids that match `filter`.
+    ///
+    /// Missing items are skipped.
+    /// Fails if any of the request failed.
+    pub async fn get_items_filtered(
+        &self,
+        items: &[impl Into<types::ItemId> + Copy],
+        filter: types::ItemFilter,
+    ) -> Result<Vec<types::Item>> {
+        Ok(self
+            .try_get_items(items)
+            .await?
             .into_iter()
-            .collect()
+            .flatten()
+            .filter(|item| filter.matches(item))
+            .collect())
+    }
+
+    /// Return the items with the specified ids as a stream of chunks.
+    ///
+    /// The ids are split into chunks of the configured batch size, see
+    /// [`HnClientBuilder::batch_size`]. Each chunk is yielded, in order, as
+    /// soon as all of its items are fetched.
+    ///
+    /// May return error if item id is invalid or not exist.
+    pub fn get_items_chunked<'a>(
+        &'a self,
+        items: &'a [impl Into<types::ItemId> + Copy + Sync],
+    ) -> impl Stream<Item = Result<Vec<types::Item>>> + 'a {
+        stream::iter(items.chunks(self.batch_size)).then(move |chunk| self.get_items(chunk))
+    }
+
+    /// Return the items with the specified ids as a stream of chunks.
+    ///
+    /// The ids are split into chunks of the configured batch size, see
+    /// [`HnClientBuilder::batch_size`]. Each chunk is yielded, in order, as
+    /// soon as all of its items are fetched.
+    ///
+    /// May return `None` if item id is invalid.
+    pub fn try_get_items_chunked<'a>(
+        &'a self,
+        items: &'a [impl Into<types::ItemId> + Copy + Sync],
+    ) -> impl Stream<Item = Result<Vec<Option<types::Item>>>> + 'a {
+        stream::iter(items.chunks(self.batch_size)).then(move |chunk| self.try_get_items(chunk))
+    }
+
+    /// Return the items with the specified ids, limiting the time the
+    /// requests may take.
+    ///
+    /// May return error if item id is invalid or not exist.
+    /// Fails if any of the request failed or the deadline passed.
+    pub async fn get_items_with(
+        &self,
+        items: &[impl Into<types::ItemId> + Copy],
+        options: RequestOptions,
+    ) -> Result<Vec<types::Item>> {
+        self.batch(items.iter().copied(), |id| self.get_item_with(id, options))
+            .await
+    }
+
+    /// Return the items with the specified ids, limiting the time the
+    /// requests may take.
+    ///
+    /// May return `None` if item id is invalid.
+    /// Fails if any of the request failed or the deadline passed.
+    pub async fn try_get_items_with(
+        &self,
+        items: &[impl Into<types::ItemId> + Copy],
+        options: RequestOptions,
+    ) -> Result<Vec<Option<types::Item>>> {
+        self.batch(items.iter().copied(), |id| {
+            self.try_get_item_with(id, options)
+        })
+        .await
+    }
+
+    /// Return the items with the specified ids, calling `progress` with the
+    /// number of fetched items and the total after every completed request.
+    ///
+    /// Requests run concurrently up to the configured batch size, the items
+    /// are returned in input order.
+    /// May return error if item id is invalid or not exist.
+    /// Fails if any of the request failed or the deadline passed.
+    pub async fn get_items_with_progress<F>(
+        &self,
+        items: &[impl Into<types::ItemId> + Copy],
+        options: RequestOptions,
+        mut progress: F,
+    ) -> Result<Vec<types::Item>>
+    where
+        F: FnMut(usize, usize),
+    {
+        let total = items.len();
+        let mut fetched = vec![None; total];
+        let mut done = 0;
+        let mut responses = stream::iter(items.iter().enumerate())
+            .map(|(index, id)| async move { (index, self.get_item_with(*id, options).await) })
+            .buffer_unordered(self.batch_size);
+        while let Some((index, item)) = responses.next().await {
+            fetched[index] = Some(item?);
+            done += 1;
+            progress(done, total);
+        }
+        Ok(fetched.into_iter().flatten().collect())
+    }
+
+    /// Return the item with the specified id together with all of its
+    /// comments, recursively.
+    ///
+    /// Missing comments are skipped.
+    /// Fails if any of the request failed.
+    pub async fn get_comment_tree(&self, id: impl Into<types::ItemId>) -> Result<CommentTree> {
+        self.get_comment_tree_filtered(id, types::ItemFilter::ALL)
+            .await
+    }
+
+    /// Return the item with the specified id together with all of its
+    /// comments that match `filter`, recursively.
+    ///
+    /// The filter only applies to comments, not to the root item. Replies to
+    /// a skipped comment are skipped as well.
+    /// Fails if any of the request failed.
+    pub async fn get_comment_tree_filtered(
+        &self,
+        id: impl Into<types::ItemId>,
+        filter: types::ItemFilter,
+    ) -> Result<CommentTree> {
+        let item = self.get_item(id).await?;
+        let visited = VisitedIds::default();
+        visited.claim(&[item.id()]);
+        self.fetch_replies(item, filter, &visited).await
+    }
+
+    /// Return the items with the specified ids together with all of their
+    /// comments, recursively, fetching the trees concurrently.
+    ///
+    /// Every item is fetched once across all trees: an id listed more than
+    /// once, as a root or as a reply, only appears in the first tree that
+    /// reaches it, which also stops cycles in malformed data. Missing items
+    /// are skipped.
+    /// Fails if any of the request failed.
+    pub async fn get_comment_trees(
+        &self,
+        ids: &[impl Into<types::ItemId> + Copy],
+    ) -> Result<Vec<CommentTree>> {
+        let visited = VisitedIds::default();
+        let ids: Vec<_> = ids.iter().map(|id| (*id).into()).collect();
+        let roots = visited.claim(&ids);
+        let trees = self
+            .batch(roots, |id| {
+                let visited = &visited;
+                async move {
+                    match self.try_get_item(id).await? {
+                        Some(item) => self
+                            .fetch_replies(item, types::ItemFilter::ALL, visited)
+                            .await
+                            .map(Some),
+                        None => Ok(None),
+                    }
+                }
+            })
+            .await?;
+        Ok(trees.into_iter().flatten().collect())
+    }
+
+    /// Return the item with the specified id together with its comments,
+    /// recursively, until one of the `limits` is reached.
+    ///
+    /// Replies left out are returned as continuations, which can be fetched
+    /// later with [`HnClient::resume_comment_tree`]. Missing comments are
+    /// skipped.
+    /// Fails if any of the request failed.
+    pub async fn get_comment_tree_limited(
+        &self,
+        id: impl Into<types::ItemId>,
+        limits: TreeLimits,
+    ) -> Result<PartialTree> {
+        let item = self.get_item(id).await?;
+        let kids = item.kids().to_vec();
+        self.fetch_replies_limited(item, kids, limits).await
+    }
+
+    /// Fetch the replies left out of a partial tree, until one of the
+    /// `limits` is reached, see [`HnClient::get_comment_tree_limited`].
+    ///
+    /// The root of the returned tree is the parent of the continuation,
+    /// with only the replies of the continuation. Merge it into the partial
+    /// tree with [`PartialTree::merge`].
+    pub async fn resume_comment_tree(
+        &self,
+        continuation: &Continuation,
+        limits: TreeLimits,
+    ) -> Result<PartialTree> {
+        let item = self.get_item(continuation.parent).await?;
+        self.fetch_replies_limited(item, continuation.ids.clone(), limits)
+            .await
+    }
+
+    /// Fetch the replies `kids` of `root` level by level, in display order,
+    /// until one of the `limits` is reached.
+    async fn fetch_replies_limited(
+        &self,
+        root: types::Item,
+        kids: Vec<types::ItemId>,
+        limits: TreeLimits,
+    ) -> Result<PartialTree> {
+        let options = RequestOptions {
+            timeout: None,
+            deadline: limits
+                .max_duration
+                .map(|duration| Instant::now() + duration),
+        };
+        let mut budget = limits.max_comments.unwrap_or(usize::MAX);
+        let mut fetched = HashMap::new();
+        // Ids listed more than once, or by one of their own replies, are
+        // only fetched the first time.
+        let mut requested = HashSet::from([root.id()]);
+        let mut level = kids.clone();
+        let mut depth = 1;
+        while !level.is_empty() && budget > 0 && limits.max_depth.is_none_or(|max| depth <= max) {
+            let mut seen = HashSet::new();
+            level.retain(|id| !requested.contains(id) && seen.insert(*id));
+            level.truncate(budget);
+            let items = match options.apply(self.try_get_items(&level)).await {
+                Ok(items) => items,
+                Err(TimeoutError) => break,
+                Err(err) => return Err(err),
+            };
+            budget -= level.len();
+            requested.extend(level.drain(..));
+            for item in items.into_iter().flatten() {
+                level.extend_from_slice(item.kids());
+                fetched.insert(item.id(), item);
+            }
+            depth += 1;
+        }
+
+        fn build(
+            item: types::Item,
+            kids: &[types::ItemId],
+            fetched: &mut HashMap<types::ItemId, types::Item>,
+            requested: &HashSet<types::ItemId>,
+            continuations: &mut Vec<Continuation>,
+        ) -> CommentTree {
+            let mut replies = Vec::new();
+            let mut missing = Vec::new();
+            for kid in kids {
+                match fetched.remove(kid) {
+                    Some(reply) => {
+                        let kids = reply.kids().to_vec();
+                        replies.push((reply, kids));
+                    }
+                    None if !requested.contains(kid) => missing.push(*kid),
+                    None => {}
+                }
+            }
+            if !missing.is_empty() {
+                continuations.push(Continuation {
+                    parent: item.id(),
+                    ids: missing,
+                });
+            }
+            let replies = replies
+                .into_iter()
+                .map(|(reply, kids)| build(reply, &kids, fetched, requested, continuations))
+                .collect();
+            CommentTree { item, replies }
+        }
+        let mut continuations = Vec::new();
+        let tree = build(root, &kids, &mut fetched, &requested, &mut continuations);
+        Ok(PartialTree {
+            tree,
+            continuations,
+        })
+    }
+
+    /// Return statistics about the comments of the item with the specified
+    /// id, computed by fetching its whole comment tree.
+    ///
+    /// Fails if any of the request failed.
+    pub async fn get_item_stats(&self, id: impl Into<types::ItemId>) -> Result<TreeStats> {
+        Ok(self.get_comment_tree(id).await?.stats())
+    }
+
+    /// Fetch the replies to `item` recursively, level by level, skipping
+    /// the ones already in `visited`.
+    fn fetch_replies<'a>(
+        &'a self,
+        item: types::Item,
+        filter: types::ItemFilter,
+        visited: &'a VisitedIds,
+    ) -> BoxFuture<'a, Result<CommentTree>> {
+        async move {
+            let replies = self
+                .batch(visited.claim(item.kids()), |id| async move {
+                    match self.try_get_item(id).await? {
+                        Some(reply) if filter.matches(&reply) => {
+                            self.fetch_replies(reply, filter, visited).await.map(Some)
+                        }
+                        _ => Ok(None),
+                    }
+                })
+                .await?;
+            Ok(CommentTree {
+                item,
+                replies: replies.into_iter().flatten().collect(),
+            })
+        }
+        .boxed()
+    }
+
+    /// Return the poll with the specified id, together with all of its
+    /// options and their vote shares.
+    ///
+    /// The options are fetched concurrently. Fails with `ItemTypeError` if
+    /// the item is not a poll or one of its parts is not a poll option.
+    pub async fn get_poll(&self, id: impl Into<types::ItemId>) -> Result<PollWithOptions> {
+        let id = id.into();
+        let poll = match self.get_item(id).await? {
+            types::Item::Poll(poll) => poll,
+            _ => return Err(ItemTypeError(id)),
+        };
+        let parts = poll.parts.clone().unwrap_or_default();
+        let options = self
+            .get_items(&parts)
+            .await?
+            .into_iter()
+            .map(|item| match item {
+                types::Item::Pollopt(option) => Ok(option),
+                other => Err(ItemTypeError(other.id())),
+            })
+            .collect::<Result<_>>()?;
+        Ok(PollWithOptions::new(poll, options))
+    }
+
+    /// Return the results of the poll with the specified id, with its
+    /// options ranked by their votes.
+    ///
+    /// See [`HnClient::get_poll`].
+    pub async fn get_poll_results(&self, id: impl Into<types::ItemId>) -> Result<PollResults> {
+        Ok(PollResults::ranked(self.get_poll(id).await?))
+    }
+
+    /// Return the items selected by a Firebase query, keyed by id.
+    ///
+    /// All items are fetched with a single request.
+    pub async fn query_items(
+        &self,
+        query: &ItemQuery,
+    ) -> Result<BTreeMap<types::ItemId, types::Item>> {
+        let url = Url::parse_with_params(
+            &format!("{}/item.json", API_BASE_URL),
+            query.to_query_pairs(),
+        )
+        .expect("valid url");
+        query::parse_items(self.fetch_json(url.into()).await?)
+    }
+
+    /// Return whether an item with the specified id exists, without
+    /// downloading its contents.
+    pub async fn item_exists(&self, id: impl Into<types::ItemId>) -> Result<bool> {
+        let id = id.into();
+        let fields: Option<HashMap<String, bool>> = self
+            .fetch_json(format!("{}/item/{}.json?shallow=true", API_BASE_URL, id))
+            .await?;
+        Ok(fields.is_some())
     }
 
     /// Return the user with the specified username.
@@ -119,20 +1663,105 @@ impl HnClient {
     where
         T: AsRef<str> + Display,
     {
-        self.client
-            .get(&format!("{}/user/{}.json", API_BASE_URL, username))
-            .send()
-            .await?
-            .json()
+        self.fetch_json(format!("{}/user/{}.json", API_BASE_URL, username)).await
+    }
+
+    /// Return the user with the specified username, marked as stale if it had
+    /// to be served from a previous response.
+    ///
+    /// See [`HnClientBuilder::serve_stale`].
+    pub async fn try_get_user_tracked<T>(&self, username: T) -> Result<Tracked<Option<types::User>>>
+    where
+        T: AsRef<str> + Display,
+    {
+        self.get_json_tracked(format!("{}/user/{}.json", API_BASE_URL, username))
             .await
-            .map_err(HnClientError::from)
+    }
+
+    /// Return the user with the specified username, with the metadata of the
+    /// response.
+    ///
+    /// May return `None` if username is invalid.
+    pub async fn try_get_user_with_meta<T>(
+        &self,
+        username: T,
+    ) -> Result<WithMeta<Option<types::User>>>
+    where
+        T: AsRef<str> + Display,
+    {
+        self.fetch_json_with_meta(format!("{}/user/{}.json", API_BASE_URL, username))
+            .await
+    }
+
+    /// Return the users with the specified usernames, keyed by username.
+    ///
+    /// Every distinct username is fetched once.
+    /// May return error if a username is invalid.
+    /// Fails if any of the request failed.
+    pub async fn get_users<T: AsRef<str>>(
+        &self,
+        usernames: &[T],
+    ) -> Result<HashMap<types::Username, types::User>> {
+        let mut distinct: Vec<&str> = usernames.iter().map(AsRef::as_ref).collect();
+        distinct.sort_unstable();
+        distinct.dedup();
+
+        let users = self.batch(distinct, |u| self.get_user(u)).await?;
+        Ok(users
+            .into_iter()
+            .map(|user| (user.id.clone(), user))
+            .collect())
+    }
+
+    /// Return the ids of the stories the user marked as favorite, on the
+    /// specified page of the user's favorites, starting at 1.
+    ///
+    /// Favorites are not part of the API, so they are read from the website.
+    /// Returns an empty list past the last page.
+    pub async fn get_user_favorites(
+        &self,
+        username: &str,
+        page: u32,
+    ) -> Result<Vec<types::ItemId>> {
+        let page = self
+            .get_page(&format!("favorites?id={}&p={}", username, page.max(1)))
+            .await?;
+        Ok(html::parse_listing_ids(&page))
+    }
+
+    /// Return the stories the user marked as favorite, on the specified page
+    /// of the user's favorites, starting at 1.
+    ///
+    /// Missing stories are skipped.
+    /// Fails if any of the request failed.
+    pub async fn get_user_favorite_items(
+        &self,
+        username: &str,
+        page: u32,
+    ) -> Result<Vec<types::Item>> {
+        let ids = self.get_user_favorites(username, page).await?;
+        self.get_items_filtered(&ids, types::ItemFilter::ALL).await
     }
 
     /// Return all the authors of the specified items.
     ///
+    /// Every distinct author is fetched once.
     /// May fail if item is None or returned user is None.
     /// Fails if any of the request failed.
     pub async fn get_authors(&self, items: &[types::Item]) -> Result<Vec<types::User>> {
+        Ok(self.get_authors_with_map(items).await?.0)
+    }
+
+    /// Return all the authors of the specified items, in item order, together
+    /// with the distinct authors keyed by username.
+    ///
+    /// Every distinct author is fetched once.
+    /// May fail if item is None or returned user is None.
+    /// Fails if any of the request failed.
+    pub async fn get_authors_with_map(
+        &self,
+        items: &[types::Item],
+    ) -> Result<(Vec<types::User>, HashMap<types::Username, types::User>)> {
         let usernames: Vec<_> = items
             .iter()
             .map(|item| {
@@ -141,10 +1770,17 @@ impl HnClient {
             })
             .collect::<Result<_>>()?;
 
-        join_all(usernames.into_iter().map(|u| self.get_user(u)))
-            .await
-            .into_iter()
-            .collect()
+        let users = self.get_users(&usernames).await?;
+        let authors = usernames
+            .iter()
+            .map(|username| {
+                users
+                    .get(*username)
+                    .cloned()
+                    .ok_or_else(|| UserNotFoundError(username.to_string()))
+            })
+            .collect::<Result<_>>()?;
+        Ok((authors, users))
     }
 
     /// Return all the authors of the specified items.
@@ -155,109 +1791,579 @@ impl HnClient {
         &self,
         items: &[Option<types::Item>],
     ) -> Result<Vec<Option<types::User>>> {
-        let authors: Result<_> = join_all(items.iter().map(|item| {
-            let a: OptionFuture<_> = item
-                .as_ref()
-                .and_then(|a| a.author().map(|a| a.to_string()))
-                .map(|a| async move { self.try_get_user(&a).await })
-                .into();
-            a
-        }))
+        self.batch(items, |item| async move {
+            match item.as_ref().and_then(|item| item.author()) {
+                Some(author) => self.try_get_user(author).await,
+                None => Ok(None),
+            }
+        })
         .await
-        .into_iter()
-        .map(|a| a.transpose().map(|a| a.flatten()))
-        .collect();
-
-        Ok(authors?)
     }
 
     /// Return the id of the newest item.
     ///
-    /// To get the 10 latest items, you can decrement the id 10 times.
-    pub async fn get_max_item_id(&self) -> Result<u32> {
-        self.client
-            .get(&format!("{}/maxitem.json", API_BASE_URL))
-            .send()
-            .await?
-            .json()
+    /// To get the 10 latest items, you can decrement the id 10 times, or use
+    /// [`HnClient::recent_items_stream`].
+    pub async fn get_max_item_id(&self) -> Result<types::ItemId> {
+        self.fetch_json(format!("{}/maxitem.json", API_BASE_URL)).await
+    }
+
+    /// Return the id of the first item created at or after `time`, or the
+    /// id after the newest item if there is none yet.
+    ///
+    /// Ids are assigned in creation order, so the id is found by a binary
+    /// search over the ids, fetching about 30 items. Where a probed item is
+    /// missing, the following ids are tried instead. The result is
+    /// approximate since the times of neighbouring items are not strictly
+    /// ordered.
+    /// Fails if any of the request failed.
+    pub async fn find_item_id_at(&self, time: types::Timestamp) -> Result<types::ItemId> {
+        let max_id = self.get_max_item_id().await?;
+        self.first_id_at(time, max_id.0).await.map(types::ItemId)
+    }
+
+    /// Return the smallest id up to `max_id` whose item was created at or
+    /// after `time`, or `max_id + 1` if there is none.
+    pub(crate) async fn first_id_at(&self, time: types::Timestamp, max_id: u32) -> Result<u32> {
+        let (mut low, mut high) = (1, max_id + 1);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let mut probe_time = None;
+            for id in mid..high.min(mid + MAX_PROBE_GAP) {
+                if let Some(item) = self.try_get_item(id).await? {
+                    probe_time = Some(item.time());
+                    break;
+                }
+            }
+            match probe_time {
+                Some(probe_time) if probe_time < time => low = mid + 1,
+                _ => high = mid,
+            }
+        }
+        Ok(low)
+    }
+
+    /// Return a stream of items, starting at the newest item and walking
+    /// backwards through the ids.
+    ///
+    /// Items are fetched lazily, one at a time. Missing and deleted items are
+    /// skipped. A failed request yields an error without ending the stream.
+    /// Use [`StreamExt::take`] to limit the number of items.
+    pub fn recent_items_stream(&self) -> impl Stream<Item = Result<types::Item>> + '_ {
+        stream::once(self.get_max_item_id())
+            .map_ok(|max_id| stream::iter((1..=max_id.0).rev()).map(Ok))
+            .try_flatten()
+            .and_then(move |id| self.try_get_item(id))
+            .try_filter_map(|item| future::ok(item.filter(|item| !item.is_deleted())))
+    }
+
+    /// Return the item ids of the specified story listing, in ranked order.
+    pub async fn get_stories(&self, list: types::StoryList) -> Result<Vec<types::ItemId>> {
+        self.fetch_json(format!("{}/{}.json", API_BASE_URL, list.api_name()))
             .await
-            .map_err(HnClientError::from)
     }
 
-    /// Return a list of top story item ids.
-    pub async fn get_top_stories(&self) -> Result<Vec<u32>> {
-        self.client
-            .get(&format!("{}/topstories.json", API_BASE_URL))
-            .send()
-            .await?
-            .json()
+    /// Return the item ids of a story list, with the metadata of the
+    /// response.
+    pub async fn get_stories_with_meta(
+        &self,
+        list: types::StoryList,
+    ) -> Result<WithMeta<Vec<types::ItemId>>> {
+        self.fetch_json_with_meta(format!("{}/{}.json", API_BASE_URL, list.api_name()))
             .await
-            .map_err(HnClientError::from)
+    }
+
+    /// Return a list of top story item ids.
+    pub async fn get_top_stories(&self) -> Result<Vec<types::ItemId>> {
+        self.get_stories(types::StoryList::Top).await
+    }
+
+    /// Return the first `count` top stories.
+    ///
+    /// The items are fetched concurrently. Missing items are skipped.
+    /// Fails if any of the request failed.
+    pub async fn get_top_stories_with_items(&self, count: usize) -> Result<Vec<types::Item>> {
+        let mut ids = self.get_top_stories().await?;
+        ids.truncate(count);
+        self.get_items_filtered(&ids, types::ItemFilter::ALL).await
     }
 
     /// Return a list of new story item ids.
-    pub async fn get_new_stories(&self) -> Result<Vec<u32>> {
-        self.client
-            .get(&format!("{}/newstories.json", API_BASE_URL))
-            .send()
-            .await?
-            .json()
-            .await
-            .map_err(HnClientError::from)
+    pub async fn get_new_stories(&self) -> Result<Vec<types::ItemId>> {
+        self.get_stories(types::StoryList::New).await
     }
 
     /// Return a list of best story item ids.
-    pub async fn get_best_stories(&self) -> Result<Vec<u32>> {
-        self.client
-            .get(&format!("{}/beststories.json", API_BASE_URL))
-            .send()
-            .await?
-            .json()
-            .await
-            .map_err(HnClientError::from)
+    pub async fn get_best_stories(&self) -> Result<Vec<types::ItemId>> {
+        self.get_stories(types::StoryList::Best).await
     }
 
     /// Return up to 200 latest Ask HN story item ids.
-    pub async fn get_ask_stories(&self) -> Result<Vec<u32>> {
-        self.client
-            .get(&format!("{}/askstories.json", API_BASE_URL))
-            .send()
-            .await?
-            .json()
-            .await
-            .map_err(HnClientError::from)
+    pub async fn get_ask_stories(&self) -> Result<Vec<types::ItemId>> {
+        self.get_stories(types::StoryList::Ask).await
     }
 
     /// Return up to 200 latest Show HN story item ids.
-    pub async fn get_show_stories(&self) -> Result<Vec<u32>> {
-        self.client
-            .get(&format!("{}/showstories.json", API_BASE_URL))
-            .send()
-            .await?
-            .json()
-            .await
-            .map_err(HnClientError::from)
+    pub async fn get_show_stories(&self) -> Result<Vec<types::ItemId>> {
+        self.get_stories(types::StoryList::Show).await
     }
 
     /// Return up to 200 latest Job story item ids.
-    pub async fn get_job_stories(&self) -> Result<Vec<u32>> {
-        self.client
-            .get(&format!("{}/jobstories.json", API_BASE_URL))
-            .send()
-            .await?
-            .json()
-            .await
-            .map_err(HnClientError::from)
+    pub async fn get_job_stories(&self) -> Result<Vec<types::ItemId>> {
+        self.get_stories(types::StoryList::Job).await
     }
 
     /// Return a list of items and users that have been updated recently.
     pub async fn get_updates(&self) -> Result<types::Updates> {
-        self.client
-            .get(&format!("{}/updates.json", API_BASE_URL))
-            .send()
-            .await?
-            .json()
+        self.fetch_json(format!("{}/updates.json", API_BASE_URL)).await
+    }
+
+    /// Return a stream of changed items and users, polling the updates
+    /// endpoint every `interval`.
+    ///
+    /// Changes that were already part of the previous poll are not repeated.
+    /// A failed poll yields an error without ending the stream.
+    pub fn updates_stream(
+        &self,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<types::UpdateEvent>> + '_ {
+        let stream = stream::unfold(
+            (None, false),
+            move |(previous, started): (Option<types::Updates>, bool)| async move {
+                if started {
+                    runtime::sleep(interval).await;
+                }
+                let (events, previous) = match self.get_updates().await {
+                    Ok(current) => {
+                        let events = current.events_since(previous.as_ref());
+                        (events.into_iter().map(Ok).collect(), Some(current))
+                    }
+                    Err(err) => (vec![Err(err)], previous),
+                };
+                Some((stream::iter(events), (previous, true)))
+            },
+        )
+        .flatten();
+        self.until_shutdown(stream)
+    }
+}
+
+/// Fail with `TimeoutError` if `future` takes longer than `limit`.
+async fn limit_time<T>(
+    limit: Option<Duration>,
+    future: impl std::future::Future<Output = Result<T>>,
+) -> Result<T> {
+    match limit {
+        Some(limit) => runtime::timeout(limit, future)
+            .await
+            .unwrap_or(Err(TimeoutError)),
+        None => future.await,
+    }
+}
+
+/// Read a response body, failing with `ResponseTooLarge` as soon as it
+/// exceeds `limit` bytes, and with `TimeoutError` if a chunk takes longer
+/// than `read_timeout`.
+async fn read_body(
+    mut response: Response,
+    limit: u64,
+    read_timeout: Option<Duration>,
+) -> Result<Bytes> {
+    if response
+        .content_length()
+        .is_some_and(|length| length > limit)
+    {
+        return Err(ResponseTooLarge(limit));
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = limit_time(read_timeout, async { Ok(response.chunk().await?) }).await? {
+        if (body.len() + chunk.len()) as u64 > limit {
+            return Err(ResponseTooLarge(limit));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body.into())
+}
+
+/// Return how long to wait before retry number `attempt` of a throttled
+/// request, honoring a `Retry-After` header.
+fn backoff_delay(headers: &HeaderMap, attempt: u32) -> Duration {
+    retry_after(headers)
+        .unwrap_or_else(|| Duration::from_secs(1 << attempt.min(6)))
+        .min(MAX_BACKOFF)
+}
+
+/// Return the delay of a `Retry-After` header, in seconds or until an HTTP
+/// date. Dates in the past mean no delay.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<HnClient>();
+        #[cfg(feature = "blocking")]
+        assert_send_sync::<crate::blocking::HnClient>();
+        assert_send_sync::<crate::archive::ArchiveClient>();
+        #[cfg(feature = "store-sqlite")]
+        assert_send_sync::<crate::sqlite::StoredHnClient>();
+        assert_send_sync::<crate::listing::Listing<'_>>();
+        assert_send_sync::<crate::reader::ReaderSession<'_>>();
+    }
+
+    #[test]
+    fn test_recommended() {
+        let builder = HnClientBuilder::recommended();
+        assert_eq!(builder.max_concurrent_requests, Some(16));
+        assert_eq!(builder.throttle_retries, 3);
+        assert_eq!(builder.circuit_breaker, Some((5, Duration::from_secs(30))));
+        assert!(builder.cache.is_some());
+        assert_eq!(builder.cache_policy, CachePolicy::default());
+        assert_eq!(builder.max_stale_age, Some(Duration::from_secs(60 * 60)));
+        assert_eq!(builder.tcp_keepalive, Some(Duration::from_secs(60)));
+
+        let client = builder.build().unwrap();
+        assert_eq!(client.circuit_state(), Some(CircuitState::Closed));
+        assert!(client.stale_store.is_some());
+    }
+
+    #[test]
+    fn test_stale_store() {
+        let mut store = StaleStore::new(Duration::from_secs(60));
+        for id in 0..=MAX_STALE_ENTRIES {
+            store.insert(id.to_string(), Bytes::new());
+        }
+        // Storing a URL again doesn't drop its newer response with the old one.
+        store.insert("1".to_string(), Bytes::from_static(b"1"));
+        assert_eq!(store.entries.len(), MAX_STALE_ENTRIES);
+        assert!(store.get("0").is_none());
+        assert_eq!(store.get("1").unwrap().1, Bytes::from_static(b"1"));
+
+        let mut store = StaleStore::new(Duration::from_millis(10));
+        store.insert("old".to_string(), Bytes::new());
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(store.get("old").is_none());
+        store.insert("new".to_string(), Bytes::new());
+        assert_eq!(store.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(backoff_delay(&headers, 1), Duration::from_secs(2));
+        assert_eq!(backoff_delay(&headers, 10), MAX_BACKOFF);
+        headers.insert(RETRY_AFTER, "5".parse().unwrap());
+        assert_eq!(backoff_delay(&headers, 3), Duration::from_secs(5));
+        headers.insert(
+            RETRY_AFTER,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(backoff_delay(&headers, 2), Duration::ZERO);
+        let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(30));
+        headers.insert(RETRY_AFTER, date.parse().unwrap());
+        let delay = backoff_delay(&headers, 2);
+        assert!(delay > Duration::from_secs(20) && delay <= Duration::from_secs(30));
+        headers.insert(RETRY_AFTER, "soon".parse().unwrap());
+        assert_eq!(backoff_delay(&headers, 2), Duration::from_secs(4));
+    }
+
+    #[tokio::test]
+    async fn test_max_concurrent_requests_zero() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/item/1.json", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let _ = socket.read(&mut [0; 4096]).await;
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nnull")
+                .await
+                .unwrap();
+        });
+        let client = HnClient::builder()
+            .max_concurrent_requests(0)
+            .build()
+            .unwrap();
+        let body = tokio::time::timeout(Duration::from_secs(5), client.fetch(url))
             .await
-            .map_err(HnClientError::from)
+            .expect("request blocked by the concurrency limit");
+        assert_eq!(body.unwrap(), "null");
+    }
+
+    #[tokio::test]
+    async fn test_read_timeout() {
+        use tokio::io::AsyncWriteExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/item/1.json", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let head = "HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\n{";
+            socket.write_all(head.as_bytes()).await.unwrap();
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+        let client = HnClient::builder()
+            .connect_timeout(Duration::from_secs(1))
+            .read_timeout(Duration::from_millis(50))
+            .build()
+            .unwrap();
+        let result = client.fetch(url).await;
+        assert!(matches!(result, Err(TimeoutError)));
+    }
+
+    #[tokio::test]
+    async fn test_default_headers() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/item/1.json", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 4096];
+            let len = socket.read(&mut request).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nnull")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&request[..len]).to_lowercase()
+        });
+        let client = HnClient::builder()
+            .default_header("X-App-Name", "demo")
+            .build()
+            .unwrap();
+        client.fetch(url).await.unwrap();
+        assert!(server.await.unwrap().contains("x-app-name: demo"));
+
+        let invalid = || HnClient::builder().default_header("X App", "demo");
+        assert!(invalid().build().is_err());
+        assert!(invalid().build_with_client(Client::new()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retry_observers() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/item/1.json", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let responses: [&[u8]; 2] = [
+                b"HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\n\
+                  Content-Length: 0\r\nConnection: close\r\n\r\n",
+                b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\nConnection: close\r\n\r\nnull",
+            ];
+            for response in responses.iter() {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = vec![0; 4096];
+                let _ = socket.read(&mut request).await.unwrap();
+                socket.write_all(response).await.unwrap();
+            }
+        });
+        let retries = Arc::new(AtomicU32::new(0));
+        let limited = Arc::new(Mutex::new(Vec::new()));
+        let client = {
+            let retries = Arc::clone(&retries);
+            let limited = Arc::clone(&limited);
+            HnClient::builder()
+                .on_retry(move |backoff| {
+                    assert_eq!(backoff.attempt, 1);
+                    retries.fetch_add(1, Ordering::SeqCst);
+                })
+                .on_rate_limited(move |event| limited.lock().unwrap().push(event.clone()))
+                .build()
+                .unwrap()
+        };
+        let (_, meta) = client.fetch_shared(url.clone()).await.unwrap();
+        assert_eq!(meta.retries, 1);
+        assert_eq!(retries.load(Ordering::SeqCst), 1);
+        let limited = limited.lock().unwrap();
+        assert_eq!(
+            *limited,
+            [RateLimited {
+                url,
+                status: 429,
+                retry_after: Some(Duration::ZERO),
+                retrying: true,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_circuit_probe_cancelled() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let responses: [&[u8]; 3] = [
+                b"HTTP/1.1 500 Internal Server Error\r\n\
+                  Content-Length: 0\r\nConnection: close\r\n\r\n",
+                // The probe never gets a response.
+                b"",
+                b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\nConnection: close\r\n\r\nnull",
+            ];
+            let mut sockets = Vec::new();
+            for response in responses.iter() {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = vec![0; 4096];
+                let _ = socket.read(&mut request).await.unwrap();
+                socket.write_all(response).await.unwrap();
+                sockets.push(socket);
+            }
+        });
+        let client = HnClient::builder()
+            .circuit_breaker(1, Duration::ZERO)
+            .build()
+            .unwrap();
+        let url = |id| format!("{}/item/{}.json", base, id);
+        assert!(client.fetch(url(1)).await.is_err());
+        assert_eq!(client.circuit_state(), Some(CircuitState::Open));
+
+        let probe = client.fetch(url(2));
+        assert!(tokio::time::timeout(Duration::from_millis(50), probe)
+            .await
+            .is_err());
+        assert_eq!(client.circuit_state(), Some(CircuitState::HalfOpen));
+        assert_eq!(&client.fetch(url(3)).await.unwrap()[..], b"null");
+        assert_eq!(client.circuit_state(), Some(CircuitState::Closed));
+    }
+
+    #[tokio::test]
+    async fn test_cache_before_circuit() {
+        use crate::cache::{CacheStore, MemoryStore};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/item/1.json", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = vec![0; 4096];
+                let _ = socket.read(&mut request).await.unwrap();
+                let response = b"HTTP/1.1 500 Internal Server Error\r\n\
+                    Content-Length: 0\r\nConnection: close\r\n\r\n";
+                socket.write_all(response).await.unwrap();
+            }
+        });
+        let store = Arc::new(MemoryStore::new());
+        let body = br#"{"id": 1, "score": 7, "time": 0, "type": "story"}"#;
+        store
+            .put("item/1", Bytes::from_static(body), Duration::from_secs(60))
+            .await;
+        for cooldown in [Duration::from_secs(60), Duration::ZERO] {
+            let client = HnClient::builder()
+                .cache(store.clone())
+                .circuit_breaker(1, cooldown)
+                .build()
+                .unwrap();
+            assert!(client.fetch(url.clone()).await.is_err());
+            assert_eq!(client.circuit_state(), Some(CircuitState::Open));
+            // Cache hits neither wait for nor probe the circuit.
+            assert_eq!(client.get_item(1).await.unwrap().score(), Some(7));
+            assert_eq!(client.circuit_state(), Some(CircuitState::Open));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_debug_http() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        struct Capture(Mutex<Vec<String>>);
+        impl log::Log for Capture {
+            fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+                metadata.target() == "hn_api::http"
+            }
+            fn log(&self, record: &log::Record<'_>) {
+                if self.enabled(record.metadata()) {
+                    self.0.lock().unwrap().push(record.args().to_string());
+                }
+            }
+            fn flush(&self) {}
+        }
+        static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+        log::set_logger(&CAPTURE).unwrap();
+        log::set_max_level(log::LevelFilter::Debug);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/item/1.json", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 4096];
+            let _ = socket.read(&mut request).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nnull")
+                .await
+                .unwrap();
+        });
+        let client = HnClient::builder().debug_http(true).build().unwrap();
+        client.fetch(url.clone()).await.unwrap();
+        let logged = CAPTURE.0.lock().unwrap();
+        assert_eq!(logged.len(), 1);
+        assert!(logged[0].starts_with(&format!("GET {} -> 200 in ", url)));
+        assert!(logged[0].ends_with("4 bytes: null"));
+    }
+
+    #[tokio::test]
+    async fn test_response_meta() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/item/1.json", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 4096];
+            let _ = socket.read(&mut request).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: 4\r\n\r\nnull")
+                .await
+                .unwrap();
+        });
+        let client = HnClient::init().unwrap();
+        let (body, meta) = client.fetch_shared(url).await.unwrap();
+        assert_eq!(&body[..], b"null");
+        assert_eq!(meta.source, ResponseSource::Network);
+        assert_eq!(meta.status, 200);
+        assert_eq!(meta.etag.as_deref(), Some("\"v1\""));
+        assert_eq!((meta.bytes, meta.retries), (4, 0));
+
+        let mut cassette = crate::cassette::Cassette::new();
+        cassette.push(format!("{}/item/1.json", API_BASE_URL), "null");
+        let client = HnClient::builder().replay(cassette).build().unwrap();
+        let item = client.try_get_item_with_meta(1).await.unwrap();
+        assert_eq!(item.value, None);
+        assert_eq!(item.meta.source, ResponseSource::Replay);
+        assert!(client.get_item_with_meta(1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_find_item_id_at() {
+        let mut cassette = crate::cassette::Cassette::new();
+        cassette.push(format!("{}/maxitem.json", API_BASE_URL), "4");
+        for id in 1..=4 {
+            let item = format!(
+                r#"{{"id": {}, "score": 1, "time": {}, "type": "story"}}"#,
+                id,
+                id * 10
+            );
+            cassette.push(format!("{}/item/{}.json", API_BASE_URL, id), item);
+        }
+        let client = HnClient::builder().replay(cassette).build().unwrap();
+        let find = |time| client.find_item_id_at(types::Timestamp(time));
+        assert_eq!(find(25).await.unwrap(), types::ItemId(3));
+        assert_eq!(find(0).await.unwrap(), types::ItemId(1));
+        assert_eq!(find(50).await.unwrap(), types::ItemId(5));
     }
 }
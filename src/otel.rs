@@ -0,0 +1,107 @@
+//! OpenTelemetry spans and metrics for the async client.
+//!
+//! Every HTTP request is recorded with the globally installed tracer and
+//! meter providers, under the instrumentation scope `hn_api`:
+//!
+//! * a span `GET <endpoint>` per request, including its retries,
+//! * the histogram `hn_api.request.duration` in seconds,
+//! * the counters `hn_api.request.retries` and `hn_api.request.errors`.
+//!
+//! All of them carry the attribute `hn.endpoint`, see [`Endpoint::name`].
+
+use std::time::Instant;
+
+use opentelemetry::{
+    global::{self, BoxedSpan},
+    metrics::{Counter, Histogram},
+    trace::{Span, Status, Tracer},
+    KeyValue,
+};
+
+use super::{stats::error_category, Endpoint, HnClientError, Result};
+
+/// The instruments shared by all requests of a client.
+pub(crate) struct Telemetry {
+    duration: Histogram<f64>,
+    retries: Counter<u64>,
+    errors: Counter<u64>,
+}
+
+impl Telemetry {
+    pub(crate) fn new() -> Self {
+        let meter = global::meter("hn_api");
+        Self {
+            duration: meter
+                .f64_histogram("hn_api.request.duration")
+                .with_unit("s")
+                .with_description("Duration of HTTP requests, including retries.")
+                .build(),
+            retries: meter
+                .u64_counter("hn_api.request.retries")
+                .with_description("Throttled requests that were retried.")
+                .build(),
+            errors: meter
+                .u64_counter("hn_api.request.errors")
+                .with_description("Failed requests.")
+                .build(),
+        }
+    }
+
+    /// Start recording a request to `url`.
+    pub(crate) fn start(&self, url: &str) -> RequestSpan<'_> {
+        let endpoint = Endpoint::from_url(url).name();
+        let mut span = global::tracer("hn_api").start(format!("GET {}", endpoint));
+        span.set_attribute(KeyValue::new("http.request.method", "GET"));
+        span.set_attribute(KeyValue::new("url.full", url.to_string()));
+        span.set_attribute(KeyValue::new("hn.endpoint", endpoint));
+        RequestSpan {
+            telemetry: self,
+            span,
+            endpoint,
+            started: Instant::now(),
+        }
+    }
+}
+
+/// A request being recorded.
+pub(crate) struct RequestSpan<'a> {
+    telemetry: &'a Telemetry,
+    span: BoxedSpan,
+    endpoint: &'static str,
+    started: Instant,
+}
+
+impl RequestSpan<'_> {
+    /// Record that the request is retried after being throttled.
+    pub(crate) fn retry(&mut self, attempt: u32) {
+        let attributes = [KeyValue::new("hn.endpoint", self.endpoint)];
+        self.telemetry.retries.add(1, &attributes);
+        self.span.add_event(
+            "retry",
+            vec![KeyValue::new("hn.attempt", i64::from(attempt))],
+        );
+    }
+
+    /// Record the outcome of the request and end the span.
+    pub(crate) fn finish<T>(mut self, result: &Result<T>) {
+        let endpoint = KeyValue::new("hn.endpoint", self.endpoint);
+        self.telemetry.duration.record(
+            self.started.elapsed().as_secs_f64(),
+            std::slice::from_ref(&endpoint),
+        );
+        if let Err(err) = result {
+            let category = KeyValue::new("error.type", error_category(err));
+            self.telemetry.errors.add(1, &[endpoint, category]);
+            if let HnClientError::HttpError(request) = err {
+                if let Some(status) = request.status {
+                    self.span.set_attribute(KeyValue::new(
+                        "http.response.status_code",
+                        i64::from(status),
+                    ));
+                }
+            }
+            self.span.set_status(Status::error(err.to_string()));
+        }
+        self.span.end();
+    }
+}
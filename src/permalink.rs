@@ -0,0 +1,215 @@
+//! Conversion between Hacker News website URLs and typed references.
+//!
+//! ```rust
+//! use hn_api::{permalink::parse_hn_url, HnRef, ItemId};
+//!
+//! let reference = parse_hn_url("https://news.ycombinator.com/item?id=8863");
+//! assert_eq!(reference, Some(HnRef::Item(ItemId(8863))));
+//! assert_eq!(
+//!     HnRef::Item(ItemId(8863)).to_url(),
+//!     "https://news.ycombinator.com/item?id=8863"
+//! );
+//! ```
+
+use std::fmt;
+
+use url::Url;
+
+use super::types::{ItemId, StoryList, Username};
+
+pub(crate) static SITE_BASE_URL: &str = "https://news.ycombinator.com";
+
+/// Something a Hacker News website URL points to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HnRef {
+    /// An item page, e.g. `/item?id=8863`.
+    Item(ItemId),
+    /// A user profile page, e.g. `/user?id=pg`.
+    User(Username),
+    /// A story listing, e.g. `/newest` or `/ask`.
+    List(StoryList),
+    /// The stories submitted from a site, e.g. `/from?site=github.com`.
+    Site(String),
+    /// The front page of a past day, e.g. `/front?day=2023-05-01`.
+    Front {
+        /// The day, formatted as `YYYY-MM-DD`.
+        day: String,
+    },
+}
+
+impl HnRef {
+    /// Return the website URL of this reference.
+    pub fn to_url(&self) -> String {
+        let (path, query) = match self {
+            HnRef::Item(id) => ("item", Some(("id", id.to_string()))),
+            HnRef::User(username) => ("user", Some(("id", username.to_string()))),
+            HnRef::List(list) => (list_path(*list), None),
+            HnRef::Site(site) => ("from", Some(("site", site.clone()))),
+            HnRef::Front { day } => ("front", Some(("day", day.clone()))),
+        };
+        let mut url = Url::parse(SITE_BASE_URL).expect("valid base url");
+        url.set_path(path);
+        if let Some((key, value)) = query {
+            url.query_pairs_mut().append_pair(key, &value);
+        }
+        url.into()
+    }
+
+    /// Return the item id, if this reference points to an item.
+    pub fn item_id(&self) -> Option<ItemId> {
+        match self {
+            HnRef::Item(id) => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for HnRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_url())
+    }
+}
+
+/// Return the website URL of the item with the specified id.
+pub fn item_url(id: impl Into<ItemId>) -> String {
+    HnRef::Item(id.into()).to_url()
+}
+
+/// Return the website URL of the user with the specified username.
+pub fn user_url(username: &str) -> String {
+    HnRef::User(username.into()).to_url()
+}
+
+/// Return the website URL of a story listing.
+pub fn list_url(list: StoryList) -> String {
+    HnRef::List(list).to_url()
+}
+
+/// Parse a Hacker News website URL.
+///
+/// The scheme may be omitted. Returns `None` if the URL does not point to
+/// news.ycombinator.com or to a page that has a typed representation.
+pub fn parse_hn_url(url: &str) -> Option<HnRef> {
+    let url = Url::parse(url)
+        .or_else(|_| Url::parse(&format!("https://{}", url)))
+        .ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    if !matches!(
+        url.host_str()?,
+        "news.ycombinator.com" | "www.news.ycombinator.com"
+    ) {
+        return None;
+    }
+
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+            .filter(|value| !value.is_empty())
+    };
+    match url.path().trim_matches('/') {
+        "item" => param("id")?.parse().ok().map(HnRef::Item),
+        "user" => param("id").map(|username| HnRef::User(username.into())),
+        "from" => param("site").map(HnRef::Site),
+        "front" => param("day").map(|day| HnRef::Front { day }),
+        "" | "news" => Some(HnRef::List(StoryList::Top)),
+        "newest" => Some(HnRef::List(StoryList::New)),
+        "best" => Some(HnRef::List(StoryList::Best)),
+        "ask" => Some(HnRef::List(StoryList::Ask)),
+        "show" => Some(HnRef::List(StoryList::Show)),
+        "jobs" => Some(HnRef::List(StoryList::Job)),
+        _ => None,
+    }
+}
+
+/// Parse several website URLs, returning the ids of those that point to
+/// items, in order.
+pub fn item_ids_from_urls<'a>(urls: impl IntoIterator<Item = &'a str>) -> Vec<ItemId> {
+    urls.into_iter()
+        .filter_map(|url| parse_hn_url(url)?.item_id())
+        .collect()
+}
+
+pub(crate) fn list_path(list: StoryList) -> &'static str {
+    match list {
+        StoryList::Top => "news",
+        StoryList::New => "newest",
+        StoryList::Best => "best",
+        StoryList::Ask => "ask",
+        StoryList::Show => "show",
+        StoryList::Job => "jobs",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let cases = [
+            (
+                "https://news.ycombinator.com/item?id=8863",
+                HnRef::Item(ItemId(8863)),
+            ),
+            (
+                "http://news.ycombinator.com/user?id=pg",
+                HnRef::User("pg".into()),
+            ),
+            ("news.ycombinator.com/newest", HnRef::List(StoryList::New)),
+            ("https://news.ycombinator.com/", HnRef::List(StoryList::Top)),
+            (
+                "https://news.ycombinator.com/from?site=github.com",
+                HnRef::Site("github.com".into()),
+            ),
+            (
+                "https://news.ycombinator.com/front?day=2023-05-01",
+                HnRef::Front {
+                    day: "2023-05-01".into(),
+                },
+            ),
+        ];
+        for (url, expected) in cases.iter() {
+            assert_eq!(parse_hn_url(url).as_ref(), Some(expected), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert_eq!(parse_hn_url("https://example.com/item?id=1"), None);
+        assert_eq!(parse_hn_url("https://news.ycombinator.com/item"), None);
+        assert_eq!(parse_hn_url("https://news.ycombinator.com/item?id=x"), None);
+        assert_eq!(parse_hn_url("ftp://news.ycombinator.com/news"), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let refs = [
+            HnRef::Item(ItemId(1)),
+            HnRef::User("dang".into()),
+            HnRef::Site("blog.rust-lang.org".into()),
+            HnRef::Front {
+                day: "2020-01-01".into(),
+            },
+        ];
+        for reference in refs
+            .iter()
+            .cloned()
+            .chain(StoryList::ALL.iter().map(|l| HnRef::List(*l)))
+        {
+            assert_eq!(parse_hn_url(&reference.to_url()), Some(reference));
+        }
+    }
+
+    #[test]
+    fn test_item_ids_from_urls() {
+        let urls = [
+            "https://news.ycombinator.com/item?id=1",
+            "https://news.ycombinator.com/user?id=pg",
+            "https://news.ycombinator.com/item?id=2",
+        ];
+        assert_eq!(item_ids_from_urls(urls.iter().copied()), vec![1, 2]);
+    }
+}
@@ -0,0 +1,135 @@
+//! Polls together with their options.
+
+use super::types::{Poll, Pollopt};
+
+/// A poll option with its share of all votes.
+#[derive(Debug)]
+pub struct PollOptionShare {
+    /// The poll option.
+    pub option: Pollopt,
+    /// The option's share of all votes of the poll, between 0 and 100.
+    pub percentage: f64,
+}
+
+/// A poll with all of its options.
+#[derive(Debug)]
+pub struct PollWithOptions {
+    /// The poll.
+    pub poll: Poll,
+    /// The poll's options, in display order.
+    pub options: Vec<PollOptionShare>,
+    /// The sum of the votes of all options.
+    pub total_votes: u32,
+}
+
+impl PollWithOptions {
+    /// Combine a poll with its options, computing the vote shares.
+    pub fn new(poll: Poll, options: Vec<Pollopt>) -> Self {
+        let total_votes = options.iter().map(|option| option.score).sum();
+        let options = options
+            .into_iter()
+            .map(|option| PollOptionShare {
+                percentage: if total_votes == 0 {
+                    0.0
+                } else {
+                    f64::from(option.score) * 100.0 / f64::from(total_votes)
+                },
+                option,
+            })
+            .collect();
+        Self {
+            poll,
+            options,
+            total_votes,
+        }
+    }
+}
+
+/// The outcome of a poll, with the options ranked by their votes.
+#[derive(Debug)]
+pub struct PollResults {
+    /// The poll.
+    pub poll: Poll,
+    /// The poll's options, most votes first. Options with the same number
+    /// of votes keep their display order.
+    pub options: Vec<PollOptionShare>,
+    /// The sum of the votes of all options.
+    pub total_votes: u32,
+}
+
+impl PollResults {
+    /// Combine a poll with its options, computing and ranking the vote
+    /// shares.
+    pub fn from(poll: Poll, options: Vec<Pollopt>) -> Self {
+        Self::ranked(PollWithOptions::new(poll, options))
+    }
+
+    pub(crate) fn ranked(poll: PollWithOptions) -> Self {
+        let mut options = poll.options;
+        options.sort_by_key(|share| std::cmp::Reverse(share.option.score));
+        Self {
+            poll: poll.poll,
+            options,
+            total_votes: poll.total_votes,
+        }
+    }
+
+    /// Return the option with the most votes.
+    ///
+    /// Returns `None` if nobody voted or several options share the most
+    /// votes.
+    pub fn winner(&self) -> Option<&PollOptionShare> {
+        let first = self
+            .options
+            .first()
+            .filter(|first| first.option.score > 0)?;
+        match self.options.get(1) {
+            Some(second) if second.option.score == first.option.score => None,
+            _ => Some(first),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poll_with_scores(scores: &[(u32, u32)]) -> (Poll, Vec<Pollopt>) {
+        let poll: Poll = serde_json::from_str(
+            r#"{"by": "pg", "id": 1, "parts": [2, 3], "score": 5, "time": 0, "title": "Poll", "type": "poll"}"#,
+        )
+        .unwrap();
+        let options = scores
+            .iter()
+            .map(|(id, score)| {
+                serde_json::from_str(&format!(
+                    r#"{{"by": "pg", "id": {}, "poll": 1, "score": {}, "time": 0}}"#,
+                    id, score
+                ))
+                .unwrap()
+            })
+            .collect();
+        (poll, options)
+    }
+
+    #[test]
+    fn test_vote_shares() {
+        let (poll, options) = poll_with_scores(&[(2, 30), (3, 10)]);
+        let poll = PollWithOptions::new(poll, options);
+        assert_eq!(poll.total_votes, 40);
+        let percentages: Vec<_> = poll.options.iter().map(|o| o.percentage).collect();
+        assert_eq!(percentages, vec![75.0, 25.0]);
+    }
+
+    #[test]
+    fn test_poll_results() {
+        let (poll, options) = poll_with_scores(&[(2, 10), (3, 30), (4, 10)]);
+        let results = PollResults::from(poll, options);
+        let ranked: Vec<_> = results.options.iter().map(|o| o.option.id.0).collect();
+        assert_eq!(ranked, vec![3, 2, 4]);
+        assert_eq!(results.winner().unwrap().percentage, 60.0);
+
+        let (poll, options) = poll_with_scores(&[(2, 10), (3, 10)]);
+        assert!(PollResults::from(poll, options).winner().is_none());
+    }
+}
@@ -0,0 +1,36 @@
+//! The types most programs need, for a single glob import.
+//!
+//! ```rust,no_run
+//! use hn_api::prelude::*;
+//!
+//! # #[cfg(feature = "nonblocking")]
+//! # async fn run() -> Result<()> {
+//! let client = AsyncHnClient::init()?;
+//! let ids = client.get_stories(StoryList::Top).await?;
+//! let item: Item = client.get_item(ids[0]).await?;
+//! println!("{}", item.title().unwrap_or_default());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Since both clients are called `HnClient`, they are exported as
+//! [`AsyncHnClient`] and [`BlockingHnClient`].
+
+pub use crate::{
+    types::{
+        Comment, Item, ItemFilter, ItemId, Job, ModerationStatus, Poll, Pollopt, Story,
+        StoryCategory, StoryList, Timestamp, UpdateEvent, Updates, User, Username,
+    },
+    CommentTree, HnClientError, Result, Tracked,
+};
+
+#[cfg(any(feature = "blocking", feature = "blocking-lite"))]
+pub use crate::blocking::HnClient as BlockingHnClient;
+
+#[cfg(feature = "nonblocking")]
+pub use crate::{
+    api::HnApi,
+    listing::{Listing, ListingDiff},
+    live::ListingUpdate,
+    nonblocking::{HnClient as AsyncHnClient, HnClientBuilder, RequestOptions},
+};
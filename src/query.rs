@@ -0,0 +1,164 @@
+//! Firebase REST query parameters for the item endpoint.
+//!
+//! The API is served by Firebase, which can filter and order the children of
+//! a location on the server. [`ItemQuery`] exposes these parameters for bulk
+//! retrieval of items in a single request.
+//!
+//! See <https://firebase.google.com/docs/database/rest/retrieve-data>.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use super::{
+    types::{Item, ItemId},
+    HnClientError, Result,
+};
+
+/// A query selecting a range of items.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ItemQuery {
+    order_by: Option<String>,
+    start_at: Option<Value>,
+    end_at: Option<Value>,
+    limit_to_first: Option<u32>,
+    limit_to_last: Option<u32>,
+}
+
+impl ItemQuery {
+    /// Create an empty query. Firebase requires an ordering to be set before
+    /// any filter is applied.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Select the items with ids in `start..=end`, ordered by id.
+    pub fn id_range(start: impl Into<ItemId>, end: impl Into<ItemId>) -> Self {
+        Self::new()
+            .order_by_key()
+            .start_at(start.into().to_string())
+            .end_at(end.into().to_string())
+    }
+
+    /// Order the items by id. Filter values must be ids formatted as strings.
+    pub fn order_by_key(self) -> Self {
+        self.order_by("$key")
+    }
+
+    /// Order the items by the specified child field, e.g. `"time"`.
+    pub fn order_by(mut self, child: &str) -> Self {
+        self.order_by = Some(child.to_string());
+        self
+    }
+
+    /// Only return items whose ordering value is at least `value`.
+    pub fn start_at(mut self, value: impl Into<Value>) -> Self {
+        self.start_at = Some(value.into());
+        self
+    }
+
+    /// Only return items whose ordering value is at most `value`.
+    pub fn end_at(mut self, value: impl Into<Value>) -> Self {
+        self.end_at = Some(value.into());
+        self
+    }
+
+    /// Only return the first `limit` items in the ordering.
+    pub fn limit_to_first(mut self, limit: u32) -> Self {
+        self.limit_to_first = Some(limit);
+        self
+    }
+
+    /// Only return the last `limit` items in the ordering.
+    pub fn limit_to_last(mut self, limit: u32) -> Self {
+        self.limit_to_last = Some(limit);
+        self
+    }
+
+    /// Return the query string parameters. Firebase expects all values to be
+    /// JSON encoded.
+    pub(crate) fn to_query_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = Vec::new();
+        if let Some(order_by) = &self.order_by {
+            pairs.push(("orderBy", Value::from(order_by.as_str()).to_string()));
+        }
+        if let Some(start_at) = &self.start_at {
+            pairs.push(("startAt", start_at.to_string()));
+        }
+        if let Some(end_at) = &self.end_at {
+            pairs.push(("endAt", end_at.to_string()));
+        }
+        if let Some(limit) = self.limit_to_first {
+            pairs.push(("limitToFirst", limit.to_string()));
+        }
+        if let Some(limit) = self.limit_to_last {
+            pairs.push(("limitToLast", limit.to_string()));
+        }
+        pairs
+    }
+}
+
+/// Parse the result of an item query into items keyed by id.
+///
+/// Firebase returns an object keyed by id, or an array indexed by id if the
+/// ids are dense enough. Missing items are skipped.
+pub(crate) fn parse_items(value: Value) -> Result<BTreeMap<ItemId, Item>> {
+    let entries: Vec<(ItemId, Value)> = match value {
+        Value::Null => Vec::new(),
+        Value::Object(map) => map
+            .into_iter()
+            .map(|(key, value)| {
+                key.parse()
+                    .map(|id| (id, value))
+                    .map_err(|_| HnClientError::BackendError(format!("invalid item id {}", key)))
+            })
+            .collect::<Result<_>>()?,
+        Value::Array(values) => (0..)
+            .map(ItemId)
+            .zip(values)
+            .filter(|(_, value)| !value.is_null())
+            .collect(),
+        other => {
+            return Err(HnClientError::BackendError(format!(
+                "unexpected query result {}",
+                other
+            )))
+        }
+    };
+    entries
+        .into_iter()
+        .map(|(id, value)| Ok((id, serde_json::from_value(value)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_pairs() {
+        let query = ItemQuery::id_range(8863, 8870).limit_to_first(5);
+        assert_eq!(
+            query.to_query_pairs(),
+            vec![
+                ("orderBy", r#""$key""#.to_string()),
+                ("startAt", r#""8863""#.to_string()),
+                ("endAt", r#""8870""#.to_string()),
+                ("limitToFirst", "5".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_items() {
+        let comment =
+            r#"{"by": "a", "id": 2, "parent": 1, "text": "", "time": 0, "type": "comment"}"#;
+        let object: Value = serde_json::from_str(&format!(r#"{{"2": {}}}"#, comment)).unwrap();
+        let array: Value = serde_json::from_str(&format!("[null, null, {}]", comment)).unwrap();
+        for value in [object, array] {
+            let items = parse_items(value).unwrap();
+            assert_eq!(items.keys().collect::<Vec<_>>(), vec![&2]);
+        }
+        assert!(parse_items(Value::Null).unwrap().is_empty());
+    }
+}
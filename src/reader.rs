@@ -0,0 +1,152 @@
+//! A model layer for interactive Hacker News readers.
+//!
+//! ```rust,no_run
+//! use hn_api::{nonblocking::HnClient, reader::ReaderSession, StoryList};
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let mut session = ReaderSession::new(&client, StoryList::Top);
+//! for story in session.next_page().await? {
+//!     println!("{}", story.title().unwrap_or("?"));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashSet;
+
+use super::{
+    nonblocking::HnClient,
+    tree::CommentTree,
+    types::{Item, ItemFilter, ItemId, StoryCategory, StoryList},
+    Result,
+};
+
+/// Pages through a story listing and keeps track of opened stories.
+pub struct ReaderSession<'a> {
+    client: &'a HnClient,
+    list: StoryList,
+    filter: ItemFilter,
+    categories: Option<Vec<StoryCategory>>,
+    page_size: usize,
+    prefetch: bool,
+    ids: Option<Vec<ItemId>>,
+    next_page: usize,
+    visited: HashSet<ItemId>,
+}
+
+impl<'a> ReaderSession<'a> {
+    /// Create a session reading the specified listing.
+    ///
+    /// Dead and deleted items are skipped and pages hold 30 stories, like on
+    /// the website.
+    pub fn new(client: &'a HnClient, list: StoryList) -> Self {
+        Self {
+            client,
+            list,
+            filter: ItemFilter::ALIVE,
+            categories: None,
+            page_size: 30,
+            prefetch: false,
+            ids: None,
+            next_page: 0,
+            visited: HashSet::new(),
+        }
+    }
+
+    /// Set the number of stories per page.
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Set whether the stories of the following page are prefetched in the
+    /// background whenever a page is returned, see [`HnClient::prefetch`].
+    pub fn prefetch_next_page(mut self, enabled: bool) -> Self {
+        self.prefetch = enabled;
+        self
+    }
+
+    /// Set which stories and comments to skip.
+    pub fn filter(mut self, filter: ItemFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Only return stories of the specified categories, e.g. to hide job
+    /// postings. Pages then hold fewer stories. All categories are returned
+    /// by default.
+    pub fn categories(mut self, categories: &[StoryCategory]) -> Self {
+        self.categories = Some(categories.to_vec());
+        self
+    }
+
+    /// Return the listing this session reads.
+    pub fn list(&self) -> StoryList {
+        self.list
+    }
+
+    /// Reload the listing and start again from the first page.
+    pub async fn refresh(&mut self) -> Result<()> {
+        self.ids = Some(self.client.get_stories(self.list).await?);
+        self.next_page = 0;
+        Ok(())
+    }
+
+    /// Return the stories of the next page.
+    ///
+    /// The listing is loaded on the first call. Returns an empty list once
+    /// all pages have been read.
+    pub async fn next_page(&mut self) -> Result<Vec<Item>> {
+        if self.ids.is_none() {
+            self.refresh().await?;
+        }
+        let ids = self.ids.as_deref().unwrap_or_default();
+        let start = (self.next_page * self.page_size).min(ids.len());
+        let end = (start + self.page_size).min(ids.len());
+        let mut items = self
+            .client
+            .get_items_filtered(&ids[start..end], self.filter)
+            .await?;
+        if let Some(categories) = &self.categories {
+            items.retain(|item| {
+                item.story_category()
+                    .is_some_and(|category| categories.contains(&category))
+            });
+        }
+        if self.prefetch {
+            let next_end = (end + self.page_size).min(ids.len());
+            self.client.prefetch(&ids[end..next_end]);
+        }
+        self.next_page += 1;
+        Ok(items)
+    }
+
+    /// Return the zero-based index of the page the next call to
+    /// [`ReaderSession::next_page`] returns.
+    pub fn current_page(&self) -> usize {
+        self.next_page
+    }
+
+    /// Fetch a story with its comments and mark it as visited.
+    pub async fn open_story(&mut self, id: impl Into<ItemId>) -> Result<CommentTree> {
+        let id = id.into();
+        let tree = self
+            .client
+            .get_comment_tree_filtered(id, self.filter)
+            .await?;
+        self.visited.insert(id);
+        Ok(tree)
+    }
+
+    /// Return whether the story with the specified id was opened in this
+    /// session.
+    pub fn is_visited(&self, id: impl Into<ItemId>) -> bool {
+        self.visited.contains(&id.into())
+    }
+
+    /// Return the ids of all stories opened in this session.
+    pub fn visited(&self) -> &HashSet<ItemId> {
+        &self.visited
+    }
+}
@@ -0,0 +1,191 @@
+//! Keep story listings up to date in the background.
+//!
+//! A [`ListingRefresher`] refetches a listing on a fixed interval, so that a
+//! web frontend can always serve the latest copy without waiting for the API.
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use hn_api::{nonblocking::HnClient, StoryList};
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let refresher =
+//!     client.spawn_listing_refresher_with_items(StoryList::Top, Duration::from_secs(60), 30);
+//! // Later, e.g. in a request handler:
+//! if let Some(listing) = refresher.latest() {
+//!     println!("{} stories, {} loaded", listing.ids.len(), listing.items.len());
+//! }
+//! refresher.shutdown().await;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures::{
+    channel::oneshot,
+    future::{self, Either},
+};
+
+use super::{
+    nonblocking::HnClient,
+    runtime,
+    types::{Item, ItemFilter, ItemId, StoryList},
+    HnClientError, Result,
+};
+
+/// A copy of a listing fetched by a [`ListingRefresher`].
+#[derive(Debug, Clone)]
+pub struct RefreshedListing {
+    /// The listing.
+    pub list: StoryList,
+    /// The ids of the listing, in ranked order.
+    pub ids: Vec<ItemId>,
+    /// The first stories of the listing, if the refresher loads them. Missing
+    /// items are skipped.
+    pub items: Vec<Item>,
+    /// When the listing was fetched.
+    pub fetched_at: Instant,
+}
+
+/// The state shared between a refresher and its task.
+#[derive(Debug, Default)]
+struct Shared {
+    latest: Option<Arc<RefreshedListing>>,
+    last_error: Option<HnClientError>,
+}
+
+/// A handle to a listing refreshed in the background, see
+/// [`HnClient::spawn_listing_refresher`].
+///
+/// Dropping the handle, or shutting down the client, stops the refresher
+/// after its current refresh.
+#[derive(Debug)]
+pub struct ListingRefresher {
+    shared: Arc<Mutex<Shared>>,
+    stop: oneshot::Sender<()>,
+    stopped: oneshot::Receiver<()>,
+}
+
+impl ListingRefresher {
+    /// Return the most recently fetched copy of the listing, `None` until the
+    /// first refresh succeeded.
+    pub fn latest(&self) -> Option<Arc<RefreshedListing>> {
+        self.shared.lock().unwrap().latest.clone()
+    }
+
+    /// Return the error of the last refresh, `None` if it succeeded. The
+    /// previous copy of the listing is kept when a refresh fails.
+    pub fn last_error(&self) -> Option<HnClientError> {
+        self.shared.lock().unwrap().last_error.clone()
+    }
+
+    /// Stop refreshing, and wait until a refresh in progress is finished.
+    pub async fn shutdown(self) {
+        let _ = self.stop.send(());
+        let _ = self.stopped.await;
+    }
+}
+
+impl HnClient {
+    /// Refetch the ids of a story listing every `interval` in the background,
+    /// starting immediately.
+    ///
+    /// Must be called within a Tokio runtime, unless the `runtime-agnostic`
+    /// feature is enabled.
+    pub fn spawn_listing_refresher(&self, list: StoryList, interval: Duration) -> ListingRefresher {
+        self.spawn_listing_refresher_with_items(list, interval, 0)
+    }
+
+    /// Refetch the ids of a story listing and its first `count` stories
+    /// every `interval` in the background, starting immediately, see
+    /// [`HnClient::spawn_listing_refresher`].
+    pub fn spawn_listing_refresher_with_items(
+        &self,
+        list: StoryList,
+        interval: Duration,
+        count: usize,
+    ) -> ListingRefresher {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let (stop, mut stop_requested) = oneshot::channel();
+        let (stopped, stopped_receiver) = oneshot::channel();
+        let client = self.clone();
+        let state = Arc::clone(&shared);
+        let token = self.shutdown_token();
+        self.spawn(async move {
+            loop {
+                let result = refresh(&client, list, count).await;
+                {
+                    let mut state = state.lock().unwrap();
+                    match result {
+                        Ok(listing) => {
+                            state.latest = Some(Arc::new(listing));
+                            state.last_error = None;
+                        }
+                        Err(err) => state.last_error = Some(err),
+                    }
+                }
+                let sleep = Box::pin(runtime::sleep(interval));
+                let stop = future::select(&mut stop_requested, Box::pin(token.wait()));
+                if let Either::Right(_) = future::select(sleep, stop).await {
+                    break;
+                }
+            }
+            let _ = stopped.send(());
+        });
+        ListingRefresher {
+            shared,
+            stop,
+            stopped: stopped_receiver,
+        }
+    }
+}
+
+/// Fetch the listing and its first `count` stories.
+async fn refresh(client: &HnClient, list: StoryList, count: usize) -> Result<RefreshedListing> {
+    let ids = client.get_stories(list).await?;
+    let items = client
+        .get_items_filtered(&ids[..count.min(ids.len())], ItemFilter::ALL)
+        .await?;
+    Ok(RefreshedListing {
+        list,
+        ids,
+        items,
+        fetched_at: Instant::now(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cassette::Cassette;
+
+    #[tokio::test]
+    async fn test_refresher() {
+        let api = "https://hacker-news.firebaseio.com/v0";
+        let mut cassette = Cassette::new();
+        cassette
+            .push(format!("{}/topstories.json", api), "[1, 2]")
+            .push(
+                format!("{}/item/1.json", api),
+                r#"{"id": 1, "score": 1, "time": 0, "type": "story"}"#,
+            );
+        let client = HnClient::builder().replay(cassette).build().unwrap();
+        let refresher =
+            client.spawn_listing_refresher_with_items(StoryList::Top, Duration::from_secs(60), 1);
+        let listing = loop {
+            match refresher.latest() {
+                Some(listing) => break listing,
+                None => tokio::time::sleep(Duration::from_millis(1)).await,
+            }
+        };
+        assert_eq!(listing.ids, vec![ItemId(1), ItemId(2)]);
+        assert_eq!(listing.items.len(), 1);
+        assert!(refresher.last_error().is_none());
+        refresher.shutdown().await;
+    }
+}
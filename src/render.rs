@@ -0,0 +1,233 @@
+//! Standalone HTML pages and Markdown documents of discussion threads.
+//!
+//! Requires the `render` feature.
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "nonblocking")]
+//! use hn_api::{nonblocking::HnClient, render};
+//!
+//! # #[cfg(feature = "nonblocking")]
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let thread = client.get_comment_tree(8863).await?;
+//! std::fs::write("8863.html", render::html(&thread)).expect("could not write page");
+//! std::fs::write("8863.md", render::markdown(&thread)).expect("could not write document");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::Write;
+
+use super::{
+    html::{decode_entities, escape, DateTime},
+    permalink,
+    tree::CommentTree,
+    types::{Item, Timestamp},
+};
+
+/// Return a standalone HTML page of the thread, with replies nested below
+/// their parents.
+///
+/// Item texts are included as returned by the API, which only uses a small
+/// set of formatting tags.
+pub fn html(tree: &CommentTree) -> String {
+    let title = escape(&title(&tree.item));
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(out, "<title>{}</title>", title);
+    out.push_str(
+        "<style>\
+         body{font-family:sans-serif;max-width:50em;margin:auto}\
+         .comment{margin-left:1.5em;border-left:1px solid #ccc;padding-left:.5em}\
+         .meta{color:#666;font-size:.85em}\
+         </style>\n</head>\n<body>\n",
+    );
+    let link = tree.item.url().unwrap_or_default();
+    if link.is_empty() {
+        let _ = writeln!(out, "<h1>{}</h1>", title);
+    } else {
+        let _ = writeln!(out, "<h1><a href=\"{}\">{}</a></h1>", escape(link), title);
+    }
+    let _ = writeln!(out, "<p class=\"meta\">{}</p>", meta(&tree.item, true));
+    if let Some(text) = tree.item.text() {
+        let _ = writeln!(out, "<div>{}</div>", text);
+    }
+    for reply in &tree.replies {
+        html_comment(&mut out, reply);
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn html_comment(out: &mut String, tree: &CommentTree) {
+    out.push_str("<div class=\"comment\">\n");
+    let _ = writeln!(out, "<p class=\"meta\">{}</p>", meta(&tree.item, true));
+    let _ = writeln!(out, "<div>{}</div>", tree.item.text().unwrap_or_default());
+    for reply in &tree.replies {
+        html_comment(out, reply);
+    }
+    out.push_str("</div>\n");
+}
+
+/// Return a Markdown document of the thread, with replies nested as block
+/// quotes below their parents.
+///
+/// Item texts are converted from HTML to Markdown.
+pub fn markdown(tree: &CommentTree) -> String {
+    let mut out = String::new();
+    let title = title(&tree.item);
+    match tree.item.url() {
+        Some(url) => {
+            let _ = writeln!(out, "# [{}]({})\n", title, url);
+        }
+        None => {
+            let _ = writeln!(out, "# {}\n", title);
+        }
+    }
+    let _ = writeln!(out, "{}\n", meta(&tree.item, false));
+    if let Some(text) = tree.item.text() {
+        let _ = writeln!(out, "{}\n", to_markdown(text));
+    }
+    for reply in &tree.replies {
+        markdown_comment(&mut out, reply, 1);
+    }
+    out
+}
+
+fn markdown_comment(out: &mut String, tree: &CommentTree, depth: usize) {
+    let prefix = "> ".repeat(depth);
+    let text = to_markdown(tree.item.text().unwrap_or_default());
+    let header = meta(&tree.item, false);
+    for line in header.lines().chain([""]).chain(text.lines()) {
+        let _ = writeln!(out, "{}", format!("{}{}", prefix, line).trim_end());
+    }
+    out.push('\n');
+    for reply in &tree.replies {
+        markdown_comment(out, reply, depth + 1);
+    }
+}
+
+fn title(item: &Item) -> String {
+    match item.title() {
+        Some(title) if !title.is_empty() => title.to_string(),
+        _ => format!("Item {}", item.id()),
+    }
+}
+
+/// Return the author, time and permalink of an item, as HTML or Markdown.
+fn meta(item: &Item, html: bool) -> String {
+    let author = item.author().unwrap_or(if item.is_deleted() {
+        "[deleted]"
+    } else {
+        "[unknown]"
+    });
+    let mut meta = if html {
+        format!(
+            "<b>{}</b> · <a href=\"{}\">{}</a>",
+            escape(author),
+            permalink::item_url(item.id()),
+            date(item.time())
+        )
+    } else {
+        format!(
+            "**{}** · [{}]({})",
+            author,
+            date(item.time()),
+            permalink::item_url(item.id())
+        )
+    };
+    if item.is_dead() {
+        meta.push_str(" · [dead]");
+    }
+    meta
+}
+
+fn date(timestamp: Timestamp) -> String {
+    let dt = DateTime::from(timestamp);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        dt.year, dt.month, dt.day, dt.hour, dt.minute
+    )
+}
+
+/// Convert the HTML of an item text to Markdown.
+fn to_markdown(html: &str) -> String {
+    let mut out = String::new();
+    let mut rest = html;
+    let mut link: Option<(usize, String)> = None;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&decode_entities(&rest[..start]));
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let tag = &rest[start + 1..end];
+        let name = tag.split_whitespace().next().unwrap_or_default();
+        match name.to_ascii_lowercase().as_str() {
+            "p" => out.push_str("\n\n"),
+            "i" | "/i" | "em" | "/em" => out.push('*'),
+            "b" | "/b" | "strong" | "/strong" => out.push_str("**"),
+            "pre" => out.push_str("\n\n```\n"),
+            "/pre" => out.push_str("\n```\n\n"),
+            "a" => {
+                let href = attribute(tag, "href").unwrap_or_default();
+                link = Some((out.len(), decode_entities(href)));
+            }
+            "/a" => {
+                if let Some((text_start, href)) = link.take() {
+                    let text = out.split_off(text_start);
+                    let _ = write!(out, "[{}]({})", text, href);
+                }
+            }
+            _ => {}
+        }
+        rest = &rest[end + 1..];
+    }
+    out.push_str(&decode_entities(rest));
+    out.trim().to_string()
+}
+
+/// Return the value of a quoted attribute of an HTML tag.
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let value = &tag[tag.find(&format!("{}=", name))? + name.len() + 1..];
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = &value[1..];
+    Some(&value[..value.find(quote)?])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> CommentTree {
+        let item = |json: &str| -> Item { serde_json::from_str(json).unwrap() };
+        CommentTree {
+            item: item(
+                r#"{"by": "pg", "id": 1, "time": 1175714200, "title": "Tom & Jerry", "url": "http://example.com", "type": "story"}"#,
+            ),
+            replies: vec![CommentTree {
+                item: item(
+                    r#"{"by": "a", "id": 2, "parent": 1, "time": 1175714260, "text": "It&#x27;s <i>great</i><p>See <a href=\"http:&#x2F;&#x2F;x.org\">x.org</a>", "type": "comment"}"#,
+                ),
+                replies: Vec::new(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_markdown() {
+        let markdown = markdown(&tree());
+        assert!(markdown.starts_with("# [Tom & Jerry](http://example.com)\n"));
+        assert!(markdown.contains(
+            "> **a** · [2007-04-04 19:17 UTC](https://news.ycombinator.com/item?id=2)\n"
+        ));
+        assert!(markdown.contains("> It's *great*\n>\n> See [x.org](http://x.org)\n"));
+    }
+
+    #[test]
+    fn test_html() {
+        let html = html(&tree());
+        assert!(html.contains("<title>Tom &amp; Jerry</title>"));
+        assert!(html.contains("<div class=\"comment\">\n<p class=\"meta\"><b>a</b>"));
+    }
+}
@@ -0,0 +1,190 @@
+//! The async runtime the async client runs on.
+//!
+//! By default, requests are sent with reqwest and timers use Tokio, so the
+//! client must run within a Tokio runtime. With the `runtime-agnostic`
+//! feature, requests are sent with isahc, which drives them on its own
+//! background thread, and timers use `futures-timer`, so the client runs on
+//! any executor, e.g. async-std or smol.
+
+use std::{future::Future, time::Duration};
+
+#[cfg(feature = "runtime-agnostic")]
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[cfg(feature = "runtime-agnostic")]
+use bytes::Bytes;
+#[cfg(feature = "runtime-agnostic")]
+use futures::{
+    future::{self, Either},
+    io::AsyncRead,
+    Stream,
+};
+use reqwest::{RequestBuilder, Response};
+
+#[cfg(feature = "runtime-agnostic")]
+use super::HnClientError;
+use super::Result;
+
+/// Wait for `duration`.
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(feature = "runtime-agnostic")]
+    futures_timer::Delay::new(duration).await;
+    #[cfg(not(feature = "runtime-agnostic"))]
+    tokio::time::sleep(duration).await;
+}
+
+/// Run `future`, returning `None` if it takes longer than `duration`.
+pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    #[cfg(feature = "runtime-agnostic")]
+    {
+        futures::pin_mut!(future);
+        match future::select(future, futures_timer::Delay::new(duration)).await {
+            Either::Left((output, _)) => Some(output),
+            Either::Right(_) => None,
+        }
+    }
+    #[cfg(not(feature = "runtime-agnostic"))]
+    tokio::time::timeout(duration, future).await.ok()
+}
+
+/// Run `future` in the background.
+///
+/// Without the `runtime-agnostic` feature, this must be called within a
+/// Tokio runtime. With it, the future runs on a thread of its own.
+pub(crate) fn spawn(future: impl Future<Output = ()> + Send + 'static) {
+    #[cfg(feature = "runtime-agnostic")]
+    std::thread::spawn(move || futures::executor::block_on(future));
+    #[cfg(not(feature = "runtime-agnostic"))]
+    tokio::spawn(future);
+}
+
+/// Sends the requests built with the reqwest client of an async client.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Transport {
+    #[cfg(feature = "runtime-agnostic")]
+    isahc: Option<isahc::HttpClient>,
+}
+
+impl Transport {
+    /// Create the transport of a client whose requests fail after `timeout`,
+    /// or if connecting takes longer than `connect_timeout`.
+    ///
+    /// With the `runtime-agnostic` feature, requests are sent with isahc.
+    /// Otherwise, and for the default transport, they are sent with the
+    /// reqwest client they were built with.
+    pub(crate) fn new(
+        timeout: Duration,
+        connect_timeout: Option<Duration>,
+        tcp_keepalive: Option<Duration>,
+    ) -> Result<Self> {
+        #[cfg(feature = "runtime-agnostic")]
+        {
+            use isahc::config::Configurable;
+
+            let mut client = isahc::HttpClient::builder().timeout(timeout);
+            if let Some(timeout) = connect_timeout {
+                client = client.connect_timeout(timeout);
+            }
+            if let Some(interval) = tcp_keepalive {
+                client = client.tcp_keepalive(interval);
+            }
+            let client = client.build().map_err(|err| {
+                HnClientError::BackendError(format!("cannot create HTTP client: {}", err))
+            })?;
+            Ok(Self {
+                isahc: Some(client),
+            })
+        }
+        #[cfg(not(feature = "runtime-agnostic"))]
+        {
+            let _ = (timeout, connect_timeout, tcp_keepalive);
+            Ok(Self::default())
+        }
+    }
+
+    /// Send `request`.
+    pub(crate) async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        #[cfg(feature = "runtime-agnostic")]
+        if let Some(client) = &self.isahc {
+            return send_isahc(client, request.build()?).await;
+        }
+        Ok(request.send().await?)
+    }
+}
+
+/// Send `request` with isahc, converting the response back into a reqwest
+/// response with a streamed body.
+#[cfg(feature = "runtime-agnostic")]
+async fn send_isahc(client: &isahc::HttpClient, request: reqwest::Request) -> Result<Response> {
+    use isahc::config::Configurable;
+
+    let url = request.url().to_string();
+    let mut builder = isahc::Request::builder()
+        .method(request.method().clone())
+        .uri(url.as_str());
+    for (name, value) in request.headers() {
+        builder = builder.header(name, value);
+    }
+    if let Some(timeout) = request.timeout() {
+        builder = builder.timeout(*timeout);
+    }
+    let request = builder
+        .body(())
+        .map_err(|err| HnClientError::BackendError(err.to_string()))?;
+    let response = client.send_async(request).await.map_err(|err| {
+        if err.is_timeout() {
+            HnClientError::TimeoutError
+        } else {
+            HnClientError::BackendError(format!("request to {} failed: {}", url, err))
+        }
+    })?;
+    let (parts, body) = response.into_parts();
+    let body = reqwest::Body::wrap_stream(BodyStream(body));
+    Ok(Response::from(isahc::http::Response::from_parts(
+        parts, body,
+    )))
+}
+
+/// The body of an isahc response as a stream of chunks.
+#[cfg(feature = "runtime-agnostic")]
+struct BodyStream(isahc::AsyncBody);
+
+#[cfg(feature = "runtime-agnostic")]
+impl Stream for BodyStream {
+    type Item = std::io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut buffer = vec![0; 16 * 1024];
+        match Pin::new(&mut self.0).poll_read(cx, &mut buffer) {
+            Poll::Ready(Ok(0)) => Poll::Ready(None),
+            Poll::Ready(Ok(read)) => {
+                buffer.truncate(read);
+                Poll::Ready(Some(Ok(Bytes::from(buffer))))
+            }
+            Poll::Ready(Err(err)) => Poll::Ready(Some(Err(err))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "runtime-agnostic"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeout_without_tokio() {
+        let elapsed = futures::executor::block_on(timeout(
+            Duration::from_millis(10),
+            future::pending::<()>(),
+        ));
+        assert_eq!(elapsed, None);
+        let ready = futures::executor::block_on(timeout(Duration::from_secs(1), async {
+            sleep(Duration::from_millis(1)).await;
+            1
+        }));
+        assert_eq!(ready, Some(1));
+    }
+}
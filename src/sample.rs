@@ -0,0 +1,193 @@
+//! Reproducible random samples of items.
+//!
+//! Samples are drawn with a small built-in pseudo random number generator
+//! (SplitMix64), so the same seed selects the same ids across crate and
+//! dependency versions. Ids that turn out to be missing or deleted are
+//! replaced by further draws, so the result only depends on the seed and on
+//! the data served by the API.
+//!
+//! The strategies are:
+//!
+//! - [`SamplingStrategy::IdRange`]: ids drawn uniformly without replacement
+//!   from a range of ids.
+//! - [`SamplingStrategy::ByTime`]: the time window is split into `n` equally
+//!   long strata, and one id is drawn uniformly from the ids created within
+//!   each stratum. Stratum boundaries are located by binary search over ids.
+//! - [`SamplingStrategy::List`]: ids drawn uniformly without replacement from
+//!   a list, e.g. a story listing.
+
+use std::{collections::HashSet, ops::RangeInclusive};
+
+use super::{
+    nonblocking::HnClient,
+    types::{Item, ItemId, Timestamp},
+    Result,
+};
+
+/// How many draws per requested item are made before giving up.
+const MAX_DRAWS_PER_ITEM: usize = 10;
+
+/// Where the ids of a sample are drawn from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SamplingStrategy {
+    /// Draw ids uniformly from an id range.
+    IdRange(RangeInclusive<u32>),
+    /// Draw one id from each of `n` equally long time strata.
+    ByTime {
+        /// Start of the time window, inclusive.
+        from: Timestamp,
+        /// End of the time window, exclusive.
+        to: Timestamp,
+    },
+    /// Draw ids uniformly from a list.
+    List(Vec<ItemId>),
+}
+
+/// The SplitMix64 pseudo random number generator.
+#[derive(Debug, Clone)]
+pub struct SampleRng {
+    state: u64,
+}
+
+impl SampleRng {
+    /// Create a generator from a seed.
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Return the next pseudo random number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Return a number in `range`.
+    pub fn in_range(&mut self, range: RangeInclusive<u32>) -> u32 {
+        let span = u64::from(range.end() - range.start()) + 1;
+        range.start() + (self.next_u64() % span) as u32
+    }
+
+    /// Shuffle `values` in place (Fisher-Yates).
+    pub fn shuffle<T>(&mut self, values: &mut [T]) {
+        for i in (1..values.len()).rev() {
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            values.swap(i, j);
+        }
+    }
+}
+
+impl HnClient {
+    /// Return a reproducible sample of up to `n` items.
+    ///
+    /// Missing and deleted items are skipped. Fewer than `n` items are
+    /// returned if the strategy does not provide enough ids.
+    /// Fails if any of the request failed.
+    pub async fn sample_items(
+        &self,
+        seed: u64,
+        strategy: &SamplingStrategy,
+        n: usize,
+    ) -> Result<Vec<Item>> {
+        let mut rng = SampleRng::new(seed);
+        match strategy {
+            SamplingStrategy::IdRange(range) => {
+                if range.is_empty() {
+                    return Ok(Vec::new());
+                }
+                let size = (range.end() - range.start()) as usize + 1;
+                let mut drawn = HashSet::new();
+                let mut items = Vec::new();
+                let mut draws = 0;
+                while items.len() < n && drawn.len() < size && draws < n * MAX_DRAWS_PER_ITEM {
+                    let mut ids = Vec::new();
+                    while ids.len() < n - items.len() && drawn.len() < size {
+                        let id = rng.in_range(range.clone());
+                        if drawn.insert(id) {
+                            ids.push(id);
+                        }
+                    }
+                    draws += ids.len();
+                    items.extend(self.alive_items(&ids).await?);
+                }
+                Ok(items)
+            }
+            SamplingStrategy::List(ids) => {
+                let mut ids = ids.clone();
+                ids.sort_unstable();
+                ids.dedup();
+                rng.shuffle(&mut ids);
+                let mut items = Vec::new();
+                let mut remaining = &ids[..];
+                while items.len() < n && !remaining.is_empty() {
+                    let (chunk, rest) = remaining.split_at((n - items.len()).min(remaining.len()));
+                    items.extend(self.alive_items(chunk).await?);
+                    remaining = rest;
+                }
+                Ok(items)
+            }
+            SamplingStrategy::ByTime { from, to } => {
+                if n == 0 || from >= to {
+                    return Ok(Vec::new());
+                }
+                let max_id = self.get_max_item_id().await?;
+                let span = to.as_secs() - from.as_secs();
+                let mut bounds = Vec::with_capacity(n + 1);
+                for i in 0..=n as u64 {
+                    let time = Timestamp(from.as_secs() + span * i / n as u64);
+                    bounds.push(self.first_id_at(time, max_id.0).await?);
+                }
+                let mut items = Vec::new();
+                for stratum in bounds.windows(2) {
+                    let (start, end) = (stratum[0], stratum[1]);
+                    for _ in 0..MAX_DRAWS_PER_ITEM.min((end - start) as usize) {
+                        let id = rng.in_range(start..=end - 1);
+                        if let Some(item) = self.alive_items(&[id]).await?.pop() {
+                            items.push(item);
+                            break;
+                        }
+                    }
+                }
+                Ok(items)
+            }
+        }
+    }
+
+    /// Return the items that exist and are not deleted.
+    async fn alive_items(&self, ids: &[impl Into<ItemId> + Copy]) -> Result<Vec<Item>> {
+        Ok(self
+            .try_get_items(ids)
+            .await?
+            .into_iter()
+            .flatten()
+            .filter(|item| !item.is_deleted())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rng_is_deterministic() {
+        let draws = |seed| {
+            let mut rng = SampleRng::new(seed);
+            (0..5).map(|_| rng.in_range(1..=100)).collect::<Vec<_>>()
+        };
+        assert_eq!(draws(42), draws(42));
+        assert_ne!(draws(42), draws(43));
+        assert!(draws(7).iter().all(|id| (1..=100).contains(id)));
+    }
+
+    #[test]
+    fn test_shuffle_keeps_values() {
+        let mut values: Vec<u32> = (0..20).collect();
+        SampleRng::new(1).shuffle(&mut values);
+        assert_ne!(values, (0..20).collect::<Vec<_>>());
+        values.sort_unstable();
+        assert_eq!(values, (0..20).collect::<Vec<_>>());
+    }
+}
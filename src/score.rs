@@ -0,0 +1,164 @@
+//! Score history of individual stories, e.g. to chart how a submission
+//! performed.
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use futures::StreamExt;
+//! use hn_api::{nonblocking::HnClient, score};
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = HnClient::init()?;
+//! let samples: Vec<_> = client
+//!     .sample_story_score(8863, Duration::from_secs(300), Duration::from_secs(6 * 3600))
+//!     .filter_map(|sample| async { sample.ok() })
+//!     .collect()
+//!     .await;
+//! score::write_samples_jsonl(std::io::stdout(), &samples)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use futures::stream::{self, Stream};
+use serde::Serialize;
+
+use super::{
+    export,
+    nonblocking::HnClient,
+    runtime,
+    types::{ItemId, StoryList, Timestamp},
+    watch::FRONT_PAGE_SIZE,
+    Result,
+};
+
+/// The state of a story at one point in time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScoreSample {
+    /// When the story was fetched.
+    pub time: Timestamp,
+    /// The score of the story.
+    pub score: u32,
+    /// The number of comments of the story.
+    pub descendants: u32,
+    /// The rank of the story on the front page, starting at 1. `None` if it
+    /// is not among the first 30 top stories.
+    pub rank: Option<usize>,
+}
+
+/// Write score samples as JSON Lines.
+pub fn write_samples_jsonl<W: Write>(writer: W, samples: &[ScoreSample]) -> io::Result<()> {
+    export::write_jsonl(writer, samples)
+}
+
+/// Write score samples as CSV with the columns `time`, `score`,
+/// `descendants` and `rank`. Missing ranks are empty.
+#[cfg(feature = "csv")]
+pub fn write_samples_csv<W: Write>(writer: W, samples: &[ScoreSample]) -> io::Result<()> {
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record(["time", "score", "descendants", "rank"])?;
+    for sample in samples {
+        csv.write_record([
+            sample.time.as_secs().to_string(),
+            sample.score.to_string(),
+            sample.descendants.to_string(),
+            sample.rank.map(|rank| rank.to_string()).unwrap_or_default(),
+        ])?;
+    }
+    csv.flush()
+}
+
+impl HnClient {
+    /// Return a stream of samples of the score, comment count and front page
+    /// rank of the story with the specified id, taken every `interval`,
+    /// starting immediately, for `duration`.
+    ///
+    /// Each sample fetches the story and the top stories. A failed sample
+    /// yields an error without ending the stream.
+    pub fn sample_story_score(
+        &self,
+        id: impl Into<ItemId>,
+        interval: Duration,
+        duration: Duration,
+    ) -> impl Stream<Item = Result<ScoreSample>> + '_ {
+        let id = id.into();
+        let stream = stream::unfold(None, move |end: Option<Instant>| async move {
+            let end = match end {
+                Some(end) if Instant::now() + interval > end => return None,
+                Some(end) => {
+                    runtime::sleep(interval).await;
+                    end
+                }
+                None => Instant::now() + duration,
+            };
+            Some((self.sample_score(id).await, Some(end)))
+        });
+        self.until_shutdown(stream)
+    }
+
+    async fn sample_score(&self, id: ItemId) -> Result<ScoreSample> {
+        let (item, top) = futures::try_join!(self.get_item(id), self.get_stories(StoryList::Top))?;
+        let rank = top
+            .iter()
+            .take(FRONT_PAGE_SIZE)
+            .position(|top| *top == id)
+            .map(|index| index + 1);
+        Ok(ScoreSample {
+            time: Timestamp::now(),
+            score: item.score().unwrap_or_default(),
+            descendants: item.descendants().unwrap_or_default(),
+            rank,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::cassette::Cassette;
+
+    #[tokio::test]
+    async fn test_sample_story_score() {
+        let api = "https://hacker-news.firebaseio.com/v0";
+        let mut cassette = Cassette::new();
+        cassette
+            .push(format!("{}/topstories.json", api), "[1, 2]")
+            .push(
+                format!("{}/item/2.json", api),
+                r#"{"id": 2, "score": 7, "descendants": 3, "time": 0, "type": "story"}"#,
+            );
+        let client = HnClient::builder().replay(cassette).build().unwrap();
+        let samples: Vec<_> = client
+            .sample_story_score(2, Duration::from_millis(1), Duration::ZERO)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(samples.len(), 1);
+        let sample = &samples[0];
+        assert_eq!(
+            (sample.score, sample.descendants, sample.rank),
+            (7, 3, Some(2))
+        );
+
+        let mut out = Vec::new();
+        write_samples_jsonl(&mut out, &samples).unwrap();
+        let json = String::from_utf8(out).unwrap();
+        assert!(json.ends_with("\"score\":7,\"descendants\":3,\"rank\":2}\n"));
+        #[cfg(feature = "csv")]
+        {
+            let mut out = Vec::new();
+            write_samples_csv(&mut out, &samples).unwrap();
+            let csv = String::from_utf8(out).unwrap();
+            assert!(csv.starts_with("time,score,descendants,rank\n"));
+            assert!(csv.ends_with(",7,3,2\n"));
+        }
+    }
+}
@@ -0,0 +1,583 @@
+//! Read the pages of the Hacker News website, as a fallback when the API lags
+//! behind, and to enrich API items with what only the site shows, like the
+//! exact ranks of a listing.
+//!
+//! ```rust,no_run
+//! use hn_api::{nonblocking::HnClient, types::StoryList};
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! for story in client.scrape_stories(StoryList::Top, 1).await? {
+//!     println!("{:>2}. {}", story.rank.unwrap_or_default(), story.title);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The markup of the site is no stable interface. Values that can't be found
+//! on a page are left empty instead of failing.
+
+use std::collections::HashMap;
+
+use super::{
+    html,
+    nonblocking::HnClient,
+    permalink,
+    types::{Comment, Item, ItemId, Job, ModerationStatus, Story, StoryList, Timestamp, Username},
+    Result,
+};
+
+/// A story, or a job, as shown on a listing or item page of the website.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScrapedStory {
+    /// The item's unique id.
+    pub id: ItemId,
+    /// The rank of the story on the listing, starting at 1. `None` on item
+    /// pages.
+    pub rank: Option<usize>,
+    /// The title of the story.
+    pub title: String,
+    /// The URL of the story. `None` for text posts, which link to their own
+    /// page.
+    pub url: Option<String>,
+    /// The site of the URL as shown next to the title, e.g.
+    /// `github.com/rust-lang`.
+    pub site: Option<String>,
+    /// The story's score. `None` for jobs.
+    pub score: Option<u32>,
+    /// The username of the story's author. `None` for jobs.
+    pub by: Option<Username>,
+    /// Creation date of the story.
+    pub time: Option<Timestamp>,
+    /// The total comment count. `None` if comments are closed, e.g. for jobs.
+    pub descendants: Option<u32>,
+    /// The story text. HTML. Only on item pages.
+    pub text: Option<String>,
+    /// Whether the story is marked `[dead]`.
+    pub dead: bool,
+    /// Whether the story is marked `[flagged]`.
+    pub flagged: bool,
+}
+
+impl ScrapedStory {
+    /// Return the moderation status of the story from its markers.
+    pub fn moderation_status(&self) -> ModerationStatus {
+        marked_status(self.dead, self.flagged)
+    }
+
+    /// Convert the story into an API item, a job if it has neither score nor
+    /// author. Missing values are left at their defaults, and there are no
+    /// `kids`.
+    pub fn into_item(self) -> Item {
+        self.into_item_with_kids(None)
+    }
+
+    fn into_item_with_kids(self, kids: Option<Vec<ItemId>>) -> Item {
+        let time = self.time.unwrap_or_default();
+        match (self.score, self.by) {
+            (None, None) => Item::Job(Job {
+                id: self.id,
+                deleted: false,
+                dead: self.dead,
+                score: 0,
+                text: self.text,
+                time,
+                title: self.title,
+                url: self.url,
+                #[cfg(feature = "extra-fields")]
+                extra: Default::default(),
+            }),
+            (score, by) => Item::Story(Story {
+                id: self.id,
+                deleted: false,
+                dead: self.dead,
+                descendants: self.descendants.unwrap_or_default(),
+                by: by.unwrap_or_default(),
+                kids,
+                score: score.unwrap_or_default(),
+                title: self.title,
+                url: self.url,
+                text: self.text,
+                time,
+                #[cfg(feature = "extra-fields")]
+                extra: Default::default(),
+            }),
+        }
+    }
+}
+
+/// A comment as shown on an item page of the website.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScrapedComment {
+    /// The item's unique id.
+    pub id: ItemId,
+    /// The comment's parent: either another comment or the item of the page.
+    pub parent: ItemId,
+    /// The depth of the comment below the item of the page, starting at 0.
+    pub depth: usize,
+    /// The username of the comment's author. `None` for deleted comments.
+    pub by: Option<Username>,
+    /// Creation date of the comment.
+    pub time: Option<Timestamp>,
+    /// The comment text. HTML.
+    pub text: String,
+    /// Whether the comment is marked `[dead]`.
+    pub dead: bool,
+    /// Whether the comment is marked `[flagged]`.
+    pub flagged: bool,
+}
+
+impl ScrapedComment {
+    /// Return the moderation status of the comment from its markers.
+    /// Comments without author are deleted.
+    pub fn moderation_status(&self) -> ModerationStatus {
+        match self.by {
+            Some(_) => marked_status(self.dead, self.flagged),
+            None => ModerationStatus::Deleted,
+        }
+    }
+
+    /// Convert the comment into an API item. Missing values are left at their
+    /// defaults, and there are no `kids`.
+    pub fn into_item(self) -> Item {
+        self.into_item_with_kids(None)
+    }
+
+    fn into_item_with_kids(self, kids: Option<Vec<ItemId>>) -> Item {
+        Item::Comment(Comment {
+            id: self.id,
+            deleted: self.by.is_none(),
+            dead: self.dead,
+            by: self.by.unwrap_or_default(),
+            kids,
+            parent: self.parent,
+            text: self.text,
+            time: self.time.unwrap_or_default(),
+            #[cfg(feature = "extra-fields")]
+            extra: Default::default(),
+        })
+    }
+}
+
+/// The item page of the website, see [`HnClient::scrape_item`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScrapedItemPage {
+    /// The id of the item of the page.
+    pub id: ItemId,
+    /// The story of the page. `None` if the item is no story, e.g. a comment.
+    pub story: Option<ScrapedStory>,
+    /// The moderation status of the item of the page, from its markers.
+    pub status: ModerationStatus,
+    /// The comments below the item, in display order.
+    pub comments: Vec<ScrapedComment>,
+}
+
+impl ScrapedItemPage {
+    /// Convert the story and the comments into API items, in display order,
+    /// with the `kids` of each item as shown on the page.
+    pub fn into_items(self) -> Vec<Item> {
+        let mut kids: HashMap<ItemId, Vec<ItemId>> = HashMap::new();
+        for comment in &self.comments {
+            kids.entry(comment.parent).or_default().push(comment.id);
+        }
+        let story_kids = kids.remove(&self.id).unwrap_or_default();
+        let story = self
+            .story
+            .map(|story| story.into_item_with_kids(Some(story_kids)));
+        story
+            .into_iter()
+            .chain(self.comments.into_iter().map(|comment| {
+                let comment_kids = kids.remove(&comment.id);
+                comment.into_item_with_kids(comment_kids)
+            }))
+            .collect()
+    }
+}
+
+/// Parse the stories of a listing page, e.g. the front page, in page order.
+pub fn parse_listing_page(html: &str) -> Vec<ScrapedStory> {
+    item_rows(html)
+        .into_iter()
+        .filter(|(_, class, _)| !class.contains("comtr"))
+        .filter_map(|(id, _, row)| parse_story(id, row))
+        .collect()
+}
+
+/// Parse an item page. Returns `None` if the page shows no item, e.g. for
+/// unknown ids.
+pub fn parse_item_page(html: &str) -> Option<ScrapedItemPage> {
+    let rows = item_rows(html);
+    let (id, _, row) = *rows.first()?;
+    let mut path: Vec<ItemId> = Vec::new();
+    let comments = rows[1..]
+        .iter()
+        .filter(|(_, class, _)| class.contains("comtr"))
+        .map(|&(comment, _, row)| {
+            let depth = find_tag(row, "class=\"ind\"")
+                .and_then(|(tag, _)| attribute(tag, "indent")?.parse().ok())
+                .unwrap_or_default();
+            path.truncate(depth);
+            let parent = path.last().copied().unwrap_or(id);
+            path.push(comment);
+            parse_comment(comment, parent, depth, row)
+        })
+        .collect();
+    let story = parse_story(id, row).map(|story| ScrapedStory {
+        rank: None,
+        ..story
+    });
+    let status = match &story {
+        Some(story) => story.moderation_status(),
+        None => parse_comment(id, id, 0, row).moderation_status(),
+    };
+    Some(ScrapedItemPage {
+        id,
+        story,
+        status,
+        comments,
+    })
+}
+
+/// Parse the row of a comment.
+fn parse_comment(id: ItemId, parent: ItemId, depth: usize, row: &str) -> ScrapedComment {
+    let text = find_tag(row, "class=\"commtext");
+    let header = text.map_or(row, |(_, text)| &row[..row.len() - text.len()]);
+    ScrapedComment {
+        id,
+        parent,
+        depth,
+        by: user(row),
+        time: age(row),
+        text: text
+            .and_then(|(_, text)| Some(text[..text.find("</div>")?].trim().to_string()))
+            .unwrap_or_default(),
+        dead: header.contains("[dead]"),
+        flagged: header.contains("[flagged]"),
+    }
+}
+
+/// Parse the title and subtext rows of a story.
+fn parse_story(id: ItemId, row: &str) -> Option<ScrapedStory> {
+    let (_, titleline) = find_tag(row, "class=\"titleline\"")?;
+    let (anchor, title) = find_tag(titleline, "<a ")?;
+    let markers = &title[title.find("</a>")?..];
+    let title = &title[..title.find("</a>")?];
+    let text = find_tag(row, "class=\"toptext\"");
+    let markers = match text {
+        Some((_, text)) => &markers[..markers.len() - text.len()],
+        None => markers,
+    };
+    let url = attribute(anchor, "href")
+        .map(html::decode_entities)
+        .filter(|url| !url.starts_with("item?id="));
+    let subtext = find_tag(row, "class=\"subtext\"").map_or("", |(_, subtext)| subtext);
+    Some(ScrapedStory {
+        id,
+        rank: content(row, "class=\"rank\"", "</span>")
+            .and_then(|rank| rank.trim_end_matches('.').parse().ok()),
+        title: html::plain_text(title).trim().to_string(),
+        url,
+        site: content(row, "class=\"sitestr\"", "</span>").filter(|site| !site.is_empty()),
+        score: content(subtext, "class=\"score\"", "</span>")
+            .and_then(|score| leading_number(&score)),
+        by: user(subtext),
+        time: age(subtext),
+        descendants: comment_count(subtext),
+        text: text
+            .and_then(|(_, text)| Some(text[..text.find("</div>")?].trim().to_string()))
+            .filter(|text| !text.is_empty()),
+        dead: markers.contains("[dead]"),
+        flagged: markers.contains("[flagged]"),
+    })
+}
+
+/// Split a page into its item rows (`<tr class="athing" id="...">`), each
+/// with its id, its classes and the markup up to the next item row.
+fn item_rows(html: &str) -> Vec<(ItemId, &str, &str)> {
+    let starts: Vec<_> = html
+        .match_indices("<tr")
+        .filter_map(|(start, _)| {
+            let tag = &html[start..start + html[start..].find('>')?];
+            let class = attribute(tag, "class").filter(|class| class.contains("athing"))?;
+            let id = ItemId(attribute(tag, "id")?.parse().ok()?);
+            Some((start, id, class))
+        })
+        .collect();
+    starts
+        .iter()
+        .enumerate()
+        .map(|(index, &(start, id, class))| {
+            let end = starts.get(index + 1).map_or(html.len(), |next| next.0);
+            (id, class, &html[start..end])
+        })
+        .collect()
+}
+
+/// Return the opening tag containing `marker`, and the markup after it.
+fn find_tag<'a>(html: &'a str, marker: &str) -> Option<(&'a str, &'a str)> {
+    let index = html.find(marker)?;
+    let start = html[..=index].rfind('<')?;
+    let end = index + html[index..].find('>')?;
+    Some((&html[start..end], &html[end + 1..]))
+}
+
+/// Return the plain text of the element with the opening tag containing
+/// `marker`, up to `close`.
+fn content(html: &str, marker: &str, close: &str) -> Option<String> {
+    let (_, content) = find_tag(html, marker)?;
+    Some(
+        html::plain_text(&content[..content.find(close)?])
+            .trim()
+            .to_string(),
+    )
+}
+
+/// Return the value of the attribute `name` of an opening tag.
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut offset = 0;
+    while let Some(index) = tag[offset..].find(name) {
+        let start = offset + index;
+        offset = start + name.len();
+        let separated = tag[..start].ends_with(char::is_whitespace) || start == 0;
+        let value = match tag[offset..].strip_prefix('=') {
+            Some(value) if separated => value,
+            _ => continue,
+        };
+        return match value.chars().next()? {
+            quote @ ('"' | '\'') => {
+                let value = &value[1..];
+                Some(&value[..value.find(quote)?])
+            }
+            _ => {
+                let end = value.find(char::is_whitespace).unwrap_or(value.len());
+                Some(&value[..end])
+            }
+        };
+    }
+    None
+}
+
+/// The author of the first user link (`class="hnuser"`).
+fn user(html: &str) -> Option<Username> {
+    content(html, "class=\"hnuser\"", "</a>")
+        .filter(|user| !user.is_empty())
+        .map(Username)
+}
+
+/// The creation date of the first age element, whose title ends with the
+/// Unix time, e.g. `2007-04-04T19:16:40 1175714200`.
+fn age(html: &str) -> Option<Timestamp> {
+    let (tag, _) = find_tag(html, "class=\"age\"")?;
+    let secs = attribute(tag, "title")?.split_whitespace().last()?;
+    secs.parse().ok().map(Timestamp)
+}
+
+/// The comment count of the item link in a subtext, e.g. `71 comments`, or
+/// 0 for `discuss`.
+fn comment_count(subtext: &str) -> Option<u32> {
+    subtext.split("<a ").skip(1).find_map(|anchor| {
+        let (tag, text) = anchor.split_once('>')?;
+        if !attribute(tag, "href")?.starts_with("item?id=") {
+            return None;
+        }
+        let text = html::plain_text(&text[..text.find("</a>")?]);
+        match text.trim() {
+            "discuss" => Some(0),
+            text if text.ends_with("comment") || text.ends_with("comments") => leading_number(text),
+            _ => None,
+        }
+    })
+}
+
+fn marked_status(dead: bool, flagged: bool) -> ModerationStatus {
+    if dead {
+        ModerationStatus::Dead
+    } else if flagged {
+        ModerationStatus::Flagged
+    } else {
+        ModerationStatus::Visible
+    }
+}
+
+fn leading_number(text: &str) -> Option<u32> {
+    text.split_whitespace().next()?.parse().ok()
+}
+
+impl HnClient {
+    /// Scrape the specified page of a listing from the website, starting at
+    /// 1, e.g. to get the exact ranks of the front page.
+    pub async fn scrape_stories(&self, list: StoryList, page: u32) -> Result<Vec<ScrapedStory>> {
+        let path = format!("{}?p={}", permalink::list_path(list), page.max(1));
+        Ok(parse_listing_page(&self.get_page(&path).await?))
+    }
+
+    /// Scrape the page of the item with the specified id from the website,
+    /// with its comments. Returns `None` if the site doesn't show the item.
+    pub async fn scrape_item(&self, id: impl Into<ItemId>) -> Result<Option<ScrapedItemPage>> {
+        let page = self.get_page(&format!("item?id={}", id.into())).await?;
+        Ok(parse_item_page(&page))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cassette::Cassette;
+
+    static LISTING: &str = r#"<table>
+<tr class="athing submission" id="8863">
+  <td align="right" valign="top" class="title"><span class="rank">1.</span></td>
+  <td class="title"><span class="titleline"><a href="http://www.getdropbox.com/u/2/screencast.html">My YC app: Dropbox - Throw away your USB drive</a><span class="sitebit comhead"> (<a href="from?site=getdropbox.com"><span class="sitestr">getdropbox.com</span></a>)</span></span></td>
+</tr>
+<tr><td colspan="2"></td><td class="subtext"><span class="subline">
+  <span class="score" id="score_8863">104 points</span> by <a href="user?id=dhouston" class="hnuser">dhouston</a>
+  <span class="age" title="2007-04-04T19:16:40 1175714200"><a href="item?id=8863">on April 4, 2007</a></span> |
+  <a href="item?id=8863">71&nbsp;comments</a>
+</span></td></tr>
+<tr class="athing submission" id="121003">
+  <td align="right" valign="top" class="title"><span class="rank">2.</span></td>
+  <td class="title"><span class="titleline"><a href="item?id=121003">Ask HN: The Arc Effect [dead]</a> [flagged]</span></td>
+</tr>
+<tr><td colspan="2"></td><td class="subtext"><span class="subline">
+  <span class="score" id="score_121003">25 points</span> by <a href="user?id=tel" class="hnuser">tel</a>
+  <span class="age" title="2008-02-21T18:30:05 1203618605"><a href="item?id=121003">on Feb 21, 2008</a></span> |
+  <a href="item?id=121003">discuss</a>
+</span></td></tr>
+<tr class="athing submission" id="192327">
+  <td align="right" valign="top" class="title"><span class="rank">3.</span></td>
+  <td class="title"><span class="titleline"><a href="item?id=192327">Justin.tv is looking for a Lead Flash Engineer!</a></span></td>
+</tr>
+<tr><td colspan="2"></td><td class="subtext">
+  <span class="age" title="2008-05-16T17:47:35 1210981655"><a href="item?id=192327">on May 16, 2008</a></span>
+</td></tr>
+</table>"#;
+
+    static ITEM: &str = r#"<table>
+<tr class="athing submission" id="2921983">
+  <td class="title"><span class="titleline"><a href="item?id=2921983">Ask HN: Norvig's comment</a></span></td>
+</tr>
+<tr><td colspan="2"></td><td class="subtext"><span class="subline">
+  <span class="score" id="score_2921983">13 points</span> by <a href="user?id=x" class="hnuser">x</a>
+  <span class="age" title="2011-08-24T18:38:47 1314211127"><a href="item?id=2921983">on Aug 24, 2011</a></span> |
+  <a href="item?id=2921983">3&nbsp;comments</a>
+</span></td></tr>
+<tr><td colspan="2"></td><td><div class="toptext">What does <i>he</i> mean? [dead]</div></td></tr>
+<tr class="athing comtr" id="2922097"><td><table><tr>
+  <td class="ind" indent="0"><img src="s.gif" height="1" width="0"></td>
+  <td class="default"><span class="comhead"><a href="user?id=norvig" class="hnuser">norvig</a>
+  <span class="age" title="2011-08-24T18:59:26 1314212366"><a href="item?id=2922097">on Aug 24, 2011</a></span></span>
+  <div class="comment"><div class="commtext c00">Yes, I was <a href="https://norvig.com">there</a>.<p>[dead] is not a marker here.</div></div></td>
+</tr></table></td></tr>
+<tr class="athing comtr" id="2922141"><td><table><tr>
+  <td class="ind" indent="1"><img src="s.gif" height="1" width="40"></td>
+  <td class="default"><span class="comhead"><a href="user?id=y" class="hnuser">y</a>
+  <span class="age" title="2011-08-24T19:05:00 1314212700"><a href="item?id=2922141">on Aug 24, 2011</a></span> [flagged] [dead]</span>
+  <div class="comment"><div class="commtext cdd">Spam</div></div></td>
+</tr></table></td></tr>
+<tr class="athing comtr" id="2922150"><td><table><tr>
+  <td class="ind" indent="0"><img src="s.gif" height="1" width="0"></td>
+  <td class="default"><span class="comhead">
+  <span class="age" title="2011-08-24T19:10:00 1314213000"><a href="item?id=2922150">on Aug 24, 2011</a></span></span>
+  <div class="comment"><div class="commtext c00">[deleted]</div></div></td>
+</tr></table></td></tr>
+</table>"#;
+
+    #[test]
+    fn test_parse_listing_page() {
+        let stories = parse_listing_page(LISTING);
+        assert_eq!(stories.len(), 3);
+        assert_eq!(
+            stories[0],
+            ScrapedStory {
+                id: ItemId(8863),
+                rank: Some(1),
+                title: "My YC app: Dropbox - Throw away your USB drive".to_string(),
+                url: Some("http://www.getdropbox.com/u/2/screencast.html".to_string()),
+                site: Some("getdropbox.com".to_string()),
+                score: Some(104),
+                by: Some(Username::from("dhouston")),
+                time: Some(Timestamp(1175714200)),
+                descendants: Some(71),
+                text: None,
+                dead: false,
+                flagged: false,
+            }
+        );
+        // Markers within the title don't count.
+        let ask = &stories[1];
+        assert_eq!((ask.url.as_deref(), ask.descendants), (None, Some(0)));
+        assert_eq!((ask.dead, ask.flagged), (false, true));
+        assert!(
+            matches!(stories[2].clone().into_item(), Item::Job(job) if job.id == ItemId(192327))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scrape_item() {
+        let mut cassette = Cassette::new();
+        cassette
+            .push("https://news.ycombinator.com/item?id=2921983", ITEM)
+            .push(
+                "https://news.ycombinator.com/item?id=1",
+                "<html><body>No such item.</body></html>",
+            )
+            .push("https://news.ycombinator.com/news?p=1", LISTING);
+        let client = HnClient::builder().replay(cassette).build().unwrap();
+
+        let page = client.scrape_item(2921983).await.unwrap().unwrap();
+        let story = page.story.as_ref().unwrap();
+        assert_eq!(
+            story.text.as_deref(),
+            Some("What does <i>he</i> mean? [dead]")
+        );
+        assert_eq!(
+            (story.rank, story.dead, story.descendants),
+            (None, false, Some(3))
+        );
+        let comments: Vec<_> = page
+            .comments
+            .iter()
+            .map(|comment| {
+                (
+                    comment.id.0,
+                    comment.parent.0,
+                    comment.depth,
+                    comment.dead,
+                    comment.flagged,
+                )
+            })
+            .collect();
+        assert_eq!(
+            comments,
+            [
+                (2922097, 2921983, 0, false, false),
+                (2922141, 2922097, 1, true, true),
+                (2922150, 2921983, 0, false, false),
+            ]
+        );
+        assert!(page.comments[0].text.starts_with("Yes, I was <a href"));
+        assert_eq!(page.status, ModerationStatus::Visible);
+        let statuses: Vec<_> = page
+            .comments
+            .iter()
+            .map(ScrapedComment::moderation_status)
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                ModerationStatus::Visible,
+                ModerationStatus::Dead,
+                ModerationStatus::Deleted
+            ]
+        );
+
+        let items = page.into_items();
+        assert_eq!(items[0].kids(), [ItemId(2922097), ItemId(2922150)]);
+        assert_eq!(items[1].kids(), [ItemId(2922141)]);
+        assert!(matches!(&items[3], Item::Comment(comment) if comment.deleted));
+
+        assert_eq!(client.scrape_item(1).await.unwrap(), None);
+        let stories = client.scrape_stories(StoryList::Top, 0).await.unwrap();
+        assert_eq!(stories.len(), 3);
+    }
+}
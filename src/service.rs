@@ -0,0 +1,129 @@
+//! Sending the requests of the async client through a `tower::Service`.
+//!
+//! With the `tower` feature, [`HnClientBuilder::service`] replaces the HTTP
+//! call at the bottom of the request pipeline with any service taking a
+//! `reqwest::Request`. Since `reqwest::Client` is such a service, the usual
+//! tower layers can be stacked on top of it:
+//!
+//! ```rust,ignore
+//! use std::time::Duration;
+//! use tower::ServiceBuilder;
+//!
+//! let service = ServiceBuilder::new()
+//!     .load_shed()
+//!     .concurrency_limit(8)
+//!     .timeout(Duration::from_secs(5))
+//!     .service(reqwest::Client::new());
+//! let api = HnClient::builder().service(service).build()?;
+//! ```
+//!
+//! Caching, coalescing, the circuit breaker and the retries on throttling
+//! still run in front of the service. Errors of the layers, e.g. an elapsed
+//! timeout, are returned as `BackendError`.
+//!
+//! [`HnClientBuilder::service`]: crate::nonblocking::HnClientBuilder::service
+
+use std::{error::Error, sync::Arc};
+
+use futures::future::{self, BoxFuture, FutureExt};
+use reqwest::{Request, Response};
+use tower_service::Service;
+
+use super::{HnClientError, Result};
+
+/// The error of a type-erased service.
+type BoxError = Box<dyn Error + Send + Sync>;
+
+/// A type-erased service sending the requests of a client.
+#[derive(Clone)]
+pub(crate) struct HttpService {
+    call: Arc<dyn Fn(Request) -> BoxFuture<'static, Result<Response>> + Send + Sync>,
+}
+
+impl HttpService {
+    /// Wrap `service`, which is cloned for every request.
+    pub(crate) fn new<S>(service: S) -> Self
+    where
+        S: Service<Request, Response = Response> + Clone + Send + Sync + 'static,
+        S::Error: Into<BoxError>,
+        S::Future: Send,
+    {
+        let call = move |request| {
+            let mut service = service.clone();
+            async move {
+                future::poll_fn(|cx| service.poll_ready(cx))
+                    .await
+                    .map_err(into_error)?;
+                service.call(request).await.map_err(into_error)
+            }
+            .boxed()
+        };
+        Self {
+            call: Arc::new(call),
+        }
+    }
+
+    /// Send `request` once the service is ready.
+    pub(crate) fn call(&self, request: Request) -> BoxFuture<'static, Result<Response>> {
+        (self.call)(request)
+    }
+}
+
+/// Convert the error of a service, keeping the context of reqwest errors.
+fn into_error(err: impl Into<BoxError>) -> HnClientError {
+    let err = err.into();
+    match err.downcast::<reqwest::Error>() {
+        Ok(err) => HnClientError::from(*err),
+        Err(err) => match err.downcast::<HnClientError>() {
+            Ok(err) => *err,
+            Err(err) => HnClientError::BackendError(err.to_string()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        task::{Context, Poll},
+    };
+
+    use super::*;
+    use crate::nonblocking::HnClient;
+
+    /// Answer every request with the same item, counting the calls.
+    #[derive(Clone, Default)]
+    struct Canned {
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl Service<Request> for Canned {
+        type Response = Response;
+        type Error = BoxError;
+        type Future = BoxFuture<'static, std::result::Result<Response, BoxError>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<std::result::Result<(), BoxError>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: Request) -> Self::Future {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let body = match request.url().path() {
+                "/v0/item/1.json" => r#"{"id": 1, "score": 1, "time": 0, "type": "story"}"#,
+                _ => return future::ready(Err("unknown url".into())).boxed(),
+            };
+            let response = http::Response::new(body);
+            future::ready(Ok(Response::from(response))).boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_service() {
+        let service = Canned::default();
+        let api = HnClient::builder().service(service.clone()).build().unwrap();
+        assert_eq!(api.get_item(1).await.unwrap().score(), Some(1));
+        assert_eq!(service.calls.load(Ordering::SeqCst), 1);
+        let err = api.try_get_item(2).await.unwrap_err();
+        assert_eq!(err.to_string(), "backend error: unknown url");
+    }
+}
@@ -0,0 +1,167 @@
+//! Stop the background work of a client.
+//!
+//! [`HnClient::shutdown`] stops every background task started by a client
+//! or any of its clones, e.g. [subscriptions](crate::bus), listing
+//! [refreshers](crate::refresh) and prefetches, and waits until they finished
+//! their current work. Polling streams like [`HnClient::updates_stream`] end,
+//! and [`SyncEngine::run`](crate::sync::SyncEngine::run) returns. Requests
+//! can still be made afterwards, but no background task starts anymore.
+//!
+//! A [`ShutdownToken`] ties application tasks to the same signal.
+//!
+//! ```rust,no_run
+//! use hn_api::{bus::SubscriptionKind, nonblocking::HnClient};
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let mut stories = client.subscribe(SubscriptionKind::NewStories);
+//! let token = client.shutdown_token();
+//! tokio::spawn(async move {
+//!     while let Ok(event) = stories.recv().await {
+//!         println!("{:?}", event);
+//!     }
+//! });
+//! tokio::spawn(async move {
+//!     token.wait().await;
+//!     println!("shutting down");
+//! });
+//! // Later, e.g. on Ctrl-C:
+//! client.shutdown().await;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{future::Future, sync::Arc};
+
+use futures::{Stream, StreamExt};
+use tokio::sync::watch;
+
+use super::{nonblocking::HnClient, runtime};
+
+/// Waits for the shutdown of a client, see [`HnClient::shutdown_token`].
+#[derive(Debug, Clone)]
+pub struct ShutdownToken {
+    signal: watch::Receiver<bool>,
+}
+
+impl ShutdownToken {
+    /// Return whether the client was shut down.
+    pub fn is_shutdown(&self) -> bool {
+        *self.signal.borrow()
+    }
+
+    /// Wait until the client is shut down.
+    pub async fn wait(&self) {
+        let mut signal = self.signal.clone();
+        // The sender is only dropped with the last clone of the client, which
+        // ends all of its work just as well.
+        let _ = signal.wait_for(|shutdown| *shutdown).await;
+    }
+}
+
+/// The shutdown signal of a client and its running background tasks.
+#[derive(Debug)]
+pub(crate) struct Shutdown {
+    signal: watch::Sender<bool>,
+    tasks: watch::Sender<usize>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self {
+            signal: watch::channel(false).0,
+            tasks: watch::channel(0).0,
+        }
+    }
+}
+
+/// Counts a background task as running until it is dropped.
+struct TaskGuard(Arc<Shutdown>);
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        self.0.tasks.send_modify(|tasks| *tasks -= 1);
+    }
+}
+
+impl HnClient {
+    /// Stop the background tasks of this client and all of its clones, and
+    /// wait until they finished their current work.
+    ///
+    /// Subscriptions close, refreshers stop after their current refresh,
+    /// prefetches complete, and polling streams end. Calling it again only
+    /// waits for tasks that are still running.
+    pub async fn shutdown(&self) {
+        let shutdown = self.shutdown_state();
+        shutdown.signal.send_replace(true);
+        let mut tasks = shutdown.tasks.subscribe();
+        let _ = tasks.wait_for(|tasks| *tasks == 0).await;
+    }
+
+    /// Return a token to wait for the shutdown of this client, e.g. to stop
+    /// application tasks along with it.
+    pub fn shutdown_token(&self) -> ShutdownToken {
+        ShutdownToken {
+            signal: self.shutdown_state().signal.subscribe(),
+        }
+    }
+
+    /// Run `future` in the background until it completes, counting it as
+    /// work to wait for on shutdown. Nothing is started if the client is
+    /// already shut down.
+    pub(crate) fn spawn(&self, future: impl Future<Output = ()> + Send + 'static) {
+        let shutdown = Arc::clone(self.shutdown_state());
+        if *shutdown.signal.borrow() {
+            return;
+        }
+        shutdown.tasks.send_modify(|tasks| *tasks += 1);
+        let guard = TaskGuard(shutdown);
+        runtime::spawn(async move {
+            let _guard = guard;
+            future.await;
+        });
+    }
+
+    /// End `stream` when the client is shut down.
+    pub(crate) fn until_shutdown<'a, S>(&self, stream: S) -> impl Stream<Item = S::Item> + 'a
+    where
+        S: Stream + 'a,
+    {
+        let token = self.shutdown_token();
+        stream.take_until(async move { token.wait().await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::stream;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shutdown() {
+        let client = HnClient::builder().build().unwrap();
+        let token = client.shutdown_token();
+        let (sender, mut receiver) = futures::channel::oneshot::channel();
+        let worker = client.clone();
+        client.spawn(async move {
+            worker.shutdown_token().wait().await;
+            runtime::sleep(Duration::from_millis(10)).await;
+            let _ = sender.send(());
+        });
+        let pending = client.until_shutdown(stream::pending::<()>());
+
+        assert!(!token.is_shutdown());
+        client.clone().shutdown().await;
+        assert!(token.is_shutdown());
+        // The task finished its work before shutdown returned.
+        assert_eq!(receiver.try_recv(), Ok(Some(())));
+        assert_eq!(pending.collect::<Vec<_>>().await, []);
+
+        client.spawn(async {});
+        assert_eq!(*client.shutdown_state().tasks.borrow(), 0);
+        client.shutdown().await;
+    }
+}
@@ -0,0 +1,530 @@
+//! A local mirror of Hacker News data in SQLite.
+//!
+//! [`SqliteStore`] is a [`SyncStore`], so a [`SyncEngine`] can keep it up to
+//! date, and offers queries over the mirrored data. Items and users are
+//! stored as JSON next to the columns that are queried, and the checkpoint of
+//! the engine is persisted too. Only with the `store-sqlite` feature.
+//!
+//! Every stored version of an item or user is kept in history tables, so
+//! [`StoredHnClient::as_of`] can serve them as they were stored at an earlier
+//! time, e.g. to repeat an analysis.
+//!
+//! ```rust,no_run
+//! use hn_api::{nonblocking::HnClient, sqlite::SqliteStore, sync::SyncEngine};
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let mut engine = SyncEngine::new(&client, SqliteStore::open("hn.sqlite")?);
+//! engine.bootstrap().await?;
+//! for story in engine.store().stories_by_domain("github.com")? {
+//!     println!("{}", story.title().unwrap_or("?"));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`SyncEngine`]: crate::sync::SyncEngine
+
+use std::{
+    convert::TryFrom,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::{
+    domain,
+    sync::{Checkpoint, SyncStore},
+    types::{Item, ItemId, Timestamp, User},
+    HnClientError, Result,
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS items (
+    id INTEGER PRIMARY KEY,
+    type TEXT NOT NULL,
+    by TEXT,
+    time INTEGER NOT NULL,
+    score INTEGER,
+    parent INTEGER,
+    host TEXT,
+    json TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS items_by ON items (by, time);
+CREATE INDEX IF NOT EXISTS items_time ON items (type, time);
+CREATE INDEX IF NOT EXISTS items_host ON items (host);
+CREATE TABLE IF NOT EXISTS users (
+    id TEXT PRIMARY KEY,
+    karma INTEGER NOT NULL,
+    json TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS item_history (
+    id INTEGER NOT NULL,
+    stored_at INTEGER NOT NULL,
+    json TEXT NOT NULL,
+    PRIMARY KEY (id, stored_at)
+);
+CREATE TABLE IF NOT EXISTS user_history (
+    id TEXT NOT NULL,
+    stored_at INTEGER NOT NULL,
+    json TEXT NOT NULL,
+    PRIMARY KEY (id, stored_at)
+);
+CREATE TABLE IF NOT EXISTS checkpoint (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    json TEXT NOT NULL
+);
+";
+
+/// Items and users stored in an SQLite database.
+#[derive(Debug)]
+pub struct SqliteStore {
+    connection: Connection,
+}
+
+impl SqliteStore {
+    /// Open or create the database file at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_connection(Connection::open(path).map_err(sqlite_error)?)
+    }
+
+    /// Create a database in memory.
+    pub fn open_in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory().map_err(sqlite_error)?)
+    }
+
+    /// Use an existing connection, creating the tables if necessary.
+    pub fn from_connection(connection: Connection) -> Result<Self> {
+        connection.execute_batch(SCHEMA).map_err(sqlite_error)?;
+        Ok(Self { connection })
+    }
+
+    /// Return the underlying connection, e.g. for custom queries.
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// Return the stored item with the specified id.
+    pub fn item(&self, id: impl Into<ItemId>) -> Result<Option<Item>> {
+        self.query_one("SELECT json FROM items WHERE id = ?1", params![id.into().0])
+    }
+
+    /// Return the stored user with the specified username.
+    pub fn user(&self, username: &str) -> Result<Option<User>> {
+        self.query_one("SELECT json FROM users WHERE id = ?1", params![username])
+    }
+
+    /// Return the stored stories and jobs linking to `domain` or one of its
+    /// subdomains, newest first.
+    pub fn stories_by_domain(&self, domain: &str) -> Result<Vec<Item>> {
+        let host = domain.trim_start_matches("www.").to_ascii_lowercase();
+        let items = self.query_items(
+            "SELECT json FROM items WHERE host = ?1 OR host LIKE '%.' || ?1 \
+             ORDER BY time DESC, id DESC",
+            params![host],
+        )?;
+        Ok(items
+            .into_iter()
+            .filter(|item| domain::is_from_domain(item, domain))
+            .collect())
+    }
+
+    /// Return the stored comments written by the user, newest first.
+    pub fn comments_by_user(&self, username: &str) -> Result<Vec<Item>> {
+        self.query_items(
+            "SELECT json FROM items WHERE type = 'comment' AND by = ?1 \
+             ORDER BY time DESC, id DESC",
+            params![username],
+        )
+    }
+
+    /// Return the `limit` highest scored stored stories created at or after
+    /// `from` and before `to`, highest score first.
+    pub fn top_scores_between(
+        &self,
+        from: Timestamp,
+        to: Timestamp,
+        limit: usize,
+    ) -> Result<Vec<Item>> {
+        self.query_items(
+            "SELECT json FROM items WHERE type = 'story' AND time >= ?1 AND time < ?2 \
+             ORDER BY score DESC, id ASC LIMIT ?3",
+            params![as_sql(from.as_secs()), as_sql(to.as_secs()), limit as i64],
+        )
+    }
+
+    /// Store an item, recording a new version in its history at `now` if it
+    /// changed.
+    fn put_item_at(&mut self, item: Item, now: Timestamp) -> Result<()> {
+        let host = item
+            .url()
+            .and_then(|url| url::Url::parse(url).ok())
+            .and_then(|url| url.host_str().map(str::to_ascii_lowercase))
+            .map(|host| host.trim_start_matches("www.").to_string());
+        let json = encode(&item)?;
+        let transaction = self.connection.transaction().map_err(sqlite_error)?;
+        transaction
+            .execute(
+                "INSERT OR REPLACE INTO items (id, type, by, time, score, parent, host, json) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    item.id().0,
+                    item.kind(),
+                    item.author(),
+                    as_sql(item.time().as_secs()),
+                    item.score(),
+                    item.parent().map(|parent| parent.0),
+                    host,
+                    json,
+                ],
+            )
+            .map_err(sqlite_error)?;
+        transaction
+            .execute(
+                &history_insert("item_history"),
+                params![item.id().0, as_sql(now.as_secs()), json],
+            )
+            .map_err(sqlite_error)?;
+        transaction.commit().map_err(sqlite_error)
+    }
+
+    /// Store a user, recording a new version in its history at `now` if it
+    /// changed.
+    fn put_user_at(&mut self, user: User, now: Timestamp) -> Result<()> {
+        let json = encode(&user)?;
+        let transaction = self.connection.transaction().map_err(sqlite_error)?;
+        transaction
+            .execute(
+                "INSERT OR REPLACE INTO users (id, karma, json) VALUES (?1, ?2, ?3)",
+                params![user.id.as_str(), user.karma, json],
+            )
+            .map_err(sqlite_error)?;
+        transaction
+            .execute(
+                &history_insert("user_history"),
+                params![user.id.as_str(), as_sql(now.as_secs()), json],
+            )
+            .map_err(sqlite_error)?;
+        transaction.commit().map_err(sqlite_error)
+    }
+
+    fn query_one<T: serde::de::DeserializeOwned>(
+        &self,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Option<T>> {
+        self.connection
+            .query_row(sql, params, |row| row.get::<_, String>(0))
+            .optional()
+            .map_err(sqlite_error)?
+            .map(|json| decode(&json))
+            .transpose()
+    }
+
+    fn query_items(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<Item>> {
+        let mut statement = self.connection.prepare_cached(sql).map_err(sqlite_error)?;
+        let rows = statement
+            .query_map(params, |row| row.get::<_, String>(0))
+            .map_err(sqlite_error)?;
+        rows.map(|json| decode(&json.map_err(sqlite_error)?))
+            .collect()
+    }
+}
+
+/// A client serving reads from a [`SqliteStore`] instead of the API, either
+/// from the latest stored versions or, with [`StoredHnClient::as_of`], from
+/// the versions stored at an earlier time.
+///
+/// The methods mirror those of [`HnClient`]. Listings and items or users
+/// that are not stored are reported as missing. The client is an
+/// [`HnApi`](crate::api::HnApi), so analyses written against the API can run
+/// on a reproducible snapshot.
+///
+/// ```rust,no_run
+/// use hn_api::{sqlite::StoredHnClient, Timestamp};
+///
+/// # async fn run() -> hn_api::Result<()> {
+/// let client = StoredHnClient::open("hn.sqlite")?;
+/// let then = client.as_of(Timestamp(1_700_000_000));
+/// println!("{:?}", then.get_item(8863).await?.score());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`HnClient`]: crate::nonblocking::HnClient
+#[derive(Debug, Clone)]
+pub struct StoredHnClient {
+    store: Arc<Mutex<SqliteStore>>,
+    time: Option<Timestamp>,
+}
+
+impl From<SqliteStore> for StoredHnClient {
+    fn from(store: SqliteStore) -> Self {
+        Self {
+            store: Arc::new(Mutex::new(store)),
+            time: None,
+        }
+    }
+}
+
+impl StoredHnClient {
+    /// Open or create the database file at `path`, see
+    /// [`SqliteStore::open`].
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        SqliteStore::open(path).map(Self::from)
+    }
+
+    /// Return a client sharing the same store that reads the items and
+    /// users as they were stored at `time`, i.e. the latest version stored
+    /// at or before it.
+    pub fn as_of(&self, time: Timestamp) -> Self {
+        Self {
+            store: Arc::clone(&self.store),
+            time: Some(time),
+        }
+    }
+
+    /// Return the point in time reads are served from, `None` for the
+    /// latest versions.
+    pub fn time(&self) -> Option<Timestamp> {
+        self.time
+    }
+
+    /// Return the item with the specified id.
+    ///
+    /// May return error if item id is not stored.
+    pub async fn get_item(&self, id: impl Into<ItemId>) -> Result<Item> {
+        let id = id.into();
+        self.try_get_item(id)
+            .await?
+            .ok_or(HnClientError::ItemNotFoundError(id))
+    }
+
+    /// Return the item with the specified id.
+    ///
+    /// May return `None` if item id is not stored.
+    pub async fn try_get_item(&self, id: impl Into<ItemId>) -> Result<Option<Item>> {
+        let id = id.into();
+        let store = self.store.lock().unwrap();
+        match self.time {
+            Some(time) => store.query_one(
+                "SELECT json FROM item_history WHERE id = ?1 AND stored_at <= ?2 \
+                 ORDER BY stored_at DESC LIMIT 1",
+                params![id.0, as_sql(time.as_secs())],
+            ),
+            None => store.item(id),
+        }
+    }
+
+    /// Return the items with the specified ids, in order. Items that are
+    /// not stored are skipped.
+    pub async fn get_items(&self, ids: &[impl Into<ItemId> + Copy]) -> Result<Vec<Item>> {
+        let mut items = Vec::with_capacity(ids.len());
+        for id in ids {
+            items.extend(self.try_get_item(*id).await?);
+        }
+        Ok(items)
+    }
+
+    /// Return the user with the specified username.
+    ///
+    /// May return error if username is not stored.
+    pub async fn get_user(&self, username: &str) -> Result<User> {
+        self.try_get_user(username)
+            .await?
+            .ok_or_else(|| HnClientError::UserNotFoundError(username.to_string()))
+    }
+
+    /// Return the user with the specified username.
+    ///
+    /// May return `None` if username is not stored.
+    pub async fn try_get_user(&self, username: &str) -> Result<Option<User>> {
+        let store = self.store.lock().unwrap();
+        match self.time {
+            Some(time) => store.query_one(
+                "SELECT json FROM user_history WHERE id = ?1 AND stored_at <= ?2 \
+                 ORDER BY stored_at DESC LIMIT 1",
+                params![username, as_sql(time.as_secs())],
+            ),
+            None => store.user(username),
+        }
+    }
+
+    /// Return the id of the newest stored item.
+    pub async fn get_max_item_id(&self) -> Result<ItemId> {
+        let store = self.store.lock().unwrap();
+        let max: Option<u32> = match self.time {
+            Some(time) => store.connection.query_row(
+                "SELECT MAX(id) FROM item_history WHERE stored_at <= ?1",
+                params![as_sql(time.as_secs())],
+                |row| row.get(0),
+            ),
+            None => store
+                .connection
+                .query_row("SELECT MAX(id) FROM items", [], |row| row.get(0)),
+        }
+        .map_err(sqlite_error)?;
+        max.map(ItemId)
+            .ok_or_else(|| HnClientError::BackendError("no items stored".into()))
+    }
+}
+
+impl SyncStore for SqliteStore {
+    fn put_item(&mut self, item: Item) -> Result<()> {
+        self.put_item_at(item, Timestamp::now())
+    }
+
+    fn put_user(&mut self, user: User) -> Result<()> {
+        self.put_user_at(user, Timestamp::now())
+    }
+
+    fn load_checkpoint(&mut self) -> Result<Option<Checkpoint>> {
+        self.connection
+            .query_row("SELECT json FROM checkpoint WHERE id = 0", [], |row| {
+                row.get::<_, String>(0)
+            })
+            .optional()
+            .map_err(sqlite_error)?
+            .map(|json| decode(&json))
+            .transpose()
+    }
+
+    fn save_checkpoint(&mut self, checkpoint: &Checkpoint) -> Result<()> {
+        self.connection
+            .execute(
+                "INSERT OR REPLACE INTO checkpoint (id, json) VALUES (0, ?1)",
+                params![encode(checkpoint)?],
+            )
+            .map_err(sqlite_error)?;
+        Ok(())
+    }
+}
+
+/// Return the statement adding `(id, stored_at, json)` to a history table,
+/// unless it equals the previous version. A version stored in the same
+/// second replaces the earlier one.
+fn history_insert(table: &str) -> String {
+    format!(
+        "INSERT OR REPLACE INTO {0} (id, stored_at, json) SELECT ?1, ?2, ?3 \
+         WHERE ?3 IS NOT (SELECT json FROM {0} WHERE id = ?1 AND stored_at <= ?2 \
+         ORDER BY stored_at DESC LIMIT 1)",
+        table
+    )
+}
+
+/// Convert seconds to an SQLite integer, saturating far future times.
+fn as_sql(secs: u64) -> i64 {
+    i64::try_from(secs).unwrap_or(i64::MAX)
+}
+
+fn encode<T: serde::Serialize>(value: &T) -> Result<String> {
+    Ok(serde_json::to_string(value)?)
+}
+
+fn decode<T: serde::de::DeserializeOwned>(json: &str) -> Result<T> {
+    Ok(serde_json::from_str(json)?)
+}
+
+fn sqlite_error(err: rusqlite::Error) -> HnClientError {
+    HnClientError::BackendError(format!("sqlite: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(json: serde_json::Value) -> Item {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_sqlite_store() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        store
+            .put_item(item(serde_json::json!({
+                "by": "a", "id": 1, "score": 10, "time": 100, "title": "t",
+                "url": "https://docs.github.com/x", "type": "story"
+            })))
+            .unwrap();
+        store
+            .put_item(item(serde_json::json!({
+                "by": "b", "id": 2, "score": 20, "time": 200, "title": "t",
+                "url": "https://notgithub.com/x", "type": "story"
+            })))
+            .unwrap();
+        store
+            .put_item(item(serde_json::json!({
+                "by": "a", "id": 3, "parent": 1, "text": "", "time": 300, "type": "comment"
+            })))
+            .unwrap();
+
+        let ids = |items: Vec<Item>| -> Vec<u32> { items.iter().map(|i| i.id().0).collect() };
+        assert_eq!(ids(store.stories_by_domain("github.com").unwrap()), [1]);
+        assert_eq!(ids(store.comments_by_user("a").unwrap()), [3]);
+        let top = store
+            .top_scores_between(Timestamp(0), Timestamp(250), 10)
+            .unwrap();
+        assert_eq!(ids(top), [2, 1]);
+        assert_eq!(store.item(3).unwrap().unwrap().parent(), Some(ItemId(1)));
+        assert!(store.item(4).unwrap().is_none());
+
+        assert_eq!(store.load_checkpoint().unwrap(), None);
+        let checkpoint = Checkpoint {
+            max_item_id: ItemId(3),
+            last_updates: None,
+        };
+        store.save_checkpoint(&checkpoint).unwrap();
+        assert_eq!(store.load_checkpoint().unwrap(), Some(checkpoint));
+    }
+
+    #[tokio::test]
+    async fn test_as_of() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        let story = |id: u32, score: u32| {
+            item(serde_json::json!({
+                "by": "a", "id": id, "score": score, "time": 0, "title": "t", "type": "story"
+            }))
+        };
+        let user = |karma: u32| -> User {
+            serde_json::from_value(
+                serde_json::json!({"id": "a", "created": 0, "karma": karma, "submitted": []}),
+            )
+            .unwrap()
+        };
+        store.put_item_at(story(1, 1), Timestamp(100)).unwrap();
+        store.put_item_at(story(1, 1), Timestamp(150)).unwrap();
+        store.put_item_at(story(1, 5), Timestamp(200)).unwrap();
+        store.put_item_at(story(2, 1), Timestamp(200)).unwrap();
+        store.put_user_at(user(10), Timestamp(100)).unwrap();
+        store.put_user_at(user(20), Timestamp(300)).unwrap();
+        let versions: i64 = store
+            .connection()
+            .query_row("SELECT COUNT(*) FROM item_history", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(versions, 3);
+
+        let client = StoredHnClient::from(store);
+        let score = |time| {
+            let client = client.as_of(Timestamp(time));
+            async move { client.try_get_item(1).await.unwrap().map(|i| i.score()) }
+        };
+        assert_eq!(score(99).await, None);
+        assert_eq!(score(100).await, Some(Some(1)));
+        assert_eq!(score(199).await, Some(Some(1)));
+        assert_eq!(score(200).await, Some(Some(5)));
+        assert_eq!(client.get_item(1).await.unwrap().score(), Some(5));
+
+        let then = client.as_of(Timestamp(150));
+        assert_eq!(then.time(), Some(Timestamp(150)));
+        assert_eq!(then.get_max_item_id().await.unwrap(), ItemId(1));
+        assert_eq!(client.get_max_item_id().await.unwrap(), ItemId(2));
+        assert_eq!(then.get_items(&[2, 1]).await.unwrap().len(), 1);
+        assert!(then.get_item(2).await.unwrap_err().is_not_found());
+        assert!(client.as_of(Timestamp(0)).get_max_item_id().await.is_err());
+
+        assert_eq!(then.get_user("a").await.unwrap().karma, 10);
+        assert_eq!(client.get_user("a").await.unwrap().karma, 20);
+        assert!(then.get_user("b").await.unwrap_err().is_not_found());
+    }
+}
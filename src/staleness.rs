@@ -0,0 +1,71 @@
+//! Markers for data that may be outdated.
+//!
+//! When the API cannot be reached, a client configured to serve stale data
+//! answers reads from the last successful response instead of failing. Such
+//! values are wrapped in [`Tracked`] together with a [`Staleness`] marker, so
+//! that user interfaces can label them as possibly outdated.
+
+use std::time::Duration;
+
+/// Why a value could not be fetched fresh from the API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StaleReason {
+    /// The request to the API failed with the contained error message.
+    RequestFailed(String),
+    /// The request was not sent because the circuit breaker is open.
+    CircuitOpen,
+}
+
+/// Describes how outdated a value is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Staleness {
+    /// Time since the value was last fetched successfully.
+    pub age: Duration,
+    /// Why a fresh value could not be fetched.
+    pub reason: StaleReason,
+}
+
+/// A value that is either fresh or marked as stale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tracked<T> {
+    /// The value itself.
+    pub value: T,
+    /// `None` if the value was just fetched from the API.
+    pub staleness: Option<Staleness>,
+}
+
+impl<T> Tracked<T> {
+    /// Wrap a value that was just fetched from the API.
+    pub fn fresh(value: T) -> Self {
+        Self {
+            value,
+            staleness: None,
+        }
+    }
+
+    /// Wrap a value that was served from a previous response.
+    pub fn stale(value: T, staleness: Staleness) -> Self {
+        Self {
+            value,
+            staleness: Some(staleness),
+        }
+    }
+
+    /// Return whether the value may be outdated.
+    pub fn is_stale(&self) -> bool {
+        self.staleness.is_some()
+    }
+
+    /// Transform the contained value, keeping the staleness marker.
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Tracked<U> {
+        Tracked {
+            value: f(self.value),
+            staleness: self.staleness,
+        }
+    }
+
+    /// Discard the staleness marker and return the value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
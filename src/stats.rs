@@ -0,0 +1,179 @@
+//! Counters describing the traffic of a client.
+//!
+//! ```rust,no_run
+//! use hn_api::nonblocking::HnClient;
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! client.get_top_stories().await?;
+//! let stats = client.stats();
+//! println!("{} requests, {} bytes", stats.requests, stats.bytes_downloaded);
+//! print!("{}", stats.to_prometheus());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex};
+
+use super::{Endpoint, HnClientError};
+
+/// A snapshot of the counters of a client, see
+/// [`HnClient::stats`](crate::nonblocking::HnClient::stats).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientStats {
+    /// HTTP requests sent, including retries.
+    pub requests: u64,
+    /// HTTP requests sent per endpoint, keyed by [`Endpoint::name`].
+    pub requests_by_endpoint: BTreeMap<&'static str, u64>,
+    /// Failed requests per category: `timeout`, `network`, `status`, `decode`
+    /// or `circuit_open`.
+    pub errors: BTreeMap<&'static str, u64>,
+    /// Calls answered by a prefetched or cached response, or by joining an
+    /// identical request in flight.
+    pub cache_hits: u64,
+    /// Calls that needed a new HTTP request.
+    pub cache_misses: u64,
+    /// Throttled requests that were retried.
+    pub retries: u64,
+    /// Response body bytes received.
+    pub bytes_downloaded: u64,
+}
+
+impl ClientStats {
+    /// Render the counters in the Prometheus text exposition format, with
+    /// metric names prefixed by `hn_api_`.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut counter = |name: &str, help: &str, samples: &[(String, u64)]| {
+            let _ = writeln!(out, "# HELP hn_api_{} {}", name, help);
+            let _ = writeln!(out, "# TYPE hn_api_{} counter", name);
+            for (labels, value) in samples {
+                let _ = writeln!(out, "hn_api_{}{} {}", name, labels, value);
+            }
+        };
+        let labelled = |label: &str, values: &BTreeMap<&'static str, u64>| -> Vec<(String, u64)> {
+            values
+                .iter()
+                .map(|(key, value)| (format!("{{{}=\"{}\"}}", label, key), *value))
+                .collect()
+        };
+        counter(
+            "requests_total",
+            "HTTP requests sent, including retries.",
+            &labelled("endpoint", &self.requests_by_endpoint),
+        );
+        counter(
+            "errors_total",
+            "Failed requests by category.",
+            &labelled("category", &self.errors),
+        );
+        counter(
+            "cache_hits_total",
+            "Calls answered without a new HTTP request.",
+            &[(String::new(), self.cache_hits)],
+        );
+        counter(
+            "cache_misses_total",
+            "Calls that needed a new HTTP request.",
+            &[(String::new(), self.cache_misses)],
+        );
+        counter(
+            "retries_total",
+            "Throttled requests that were retried.",
+            &[(String::new(), self.retries)],
+        );
+        counter(
+            "downloaded_bytes_total",
+            "Response body bytes received.",
+            &[(String::new(), self.bytes_downloaded)],
+        );
+        out
+    }
+}
+
+/// Return the category an error is counted under in [`ClientStats::errors`].
+pub(crate) fn error_category(err: &HnClientError) -> &'static str {
+    match err {
+        HnClientError::CircuitOpen => "circuit_open",
+        err if err.is_decode() => "decode",
+        HnClientError::HttpError(err) if err.status.is_some() => "status",
+        err if err.is_timeout() => "timeout",
+        _ => "network",
+    }
+}
+
+/// The counters of a client, shared by all its requests.
+#[derive(Default)]
+pub(crate) struct StatsRecorder {
+    stats: Mutex<ClientStats>,
+}
+
+impl StatsRecorder {
+    pub(crate) fn snapshot(&self) -> ClientStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    pub(crate) fn record_hit(&self) {
+        self.stats.lock().unwrap().cache_hits += 1;
+    }
+
+    pub(crate) fn record_miss(&self) {
+        self.stats.lock().unwrap().cache_misses += 1;
+    }
+
+    pub(crate) fn record_request(&self, url: &str, retry: bool) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.requests += 1;
+        *stats
+            .requests_by_endpoint
+            .entry(Endpoint::from_url(url).name())
+            .or_default() += 1;
+        if retry {
+            stats.retries += 1;
+        }
+    }
+
+    pub(crate) fn record_bytes(&self, bytes: usize) {
+        self.stats.lock().unwrap().bytes_downloaded += bytes as u64;
+    }
+
+    pub(crate) fn record_error(&self, err: &HnClientError) {
+        *self
+            .stats
+            .lock()
+            .unwrap()
+            .errors
+            .entry(error_category(err))
+            .or_default() += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let recorder = StatsRecorder::default();
+        recorder.record_miss();
+        recorder.record_request("https://hacker-news.firebaseio.com/v0/item/1.json", false);
+        recorder.record_request("https://hacker-news.firebaseio.com/v0/item/1.json", true);
+        recorder.record_bytes(10);
+        recorder.record_hit();
+        recorder.record_error(&HnClientError::TimeoutError);
+        recorder.record_error(&HnClientError::CircuitOpen);
+
+        let stats = recorder.snapshot();
+        assert_eq!(stats.requests, 2);
+        assert_eq!(stats.requests_by_endpoint["item"], 2);
+        assert_eq!(stats.retries, 1);
+        assert_eq!((stats.cache_hits, stats.cache_misses), (1, 1));
+        assert_eq!(stats.errors["timeout"], 1);
+        assert_eq!(stats.errors["circuit_open"], 1);
+
+        let text = stats.to_prometheus();
+        assert!(text.contains("# TYPE hn_api_requests_total counter\n"));
+        assert!(text.contains("hn_api_requests_total{endpoint=\"item\"} 2\n"));
+        assert!(text.contains("hn_api_downloaded_bytes_total 10\n"));
+    }
+}
@@ -0,0 +1,321 @@
+//! Keep a local mirror of Hacker News data up to date.
+//!
+//! A [`SyncEngine`] writes items and users into a [`SyncStore`]. It first
+//! bootstraps the store from a set of listings, then polls the updates
+//! endpoint and stores every new item and every changed item and user.
+//! Progress is recorded in a [`Checkpoint`], which the store may persist so a
+//! restarted engine only catches up on the items created in the meantime.
+//!
+//! ```rust,no_run
+//! use hn_api::{archive::Archive, nonblocking::HnClient, sync::SyncEngine};
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let mut engine = SyncEngine::new(&client, Archive::default());
+//! engine.run().await
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use futures::future;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    archive::Archive,
+    nonblocking::HnClient,
+    runtime,
+    types::{Item, ItemFilter, ItemId, StoryList, UpdateEvent, Updates, User},
+    Result,
+};
+
+/// Where a [`SyncEngine`] writes the synchronized data.
+pub trait SyncStore {
+    /// Store an item, replacing any previous version.
+    fn put_item(&mut self, item: Item) -> Result<()>;
+
+    /// Store a user, replacing any previous version.
+    fn put_user(&mut self, user: User) -> Result<()>;
+
+    /// Return the last saved checkpoint, if any.
+    ///
+    /// The default implementation does not persist checkpoints.
+    fn load_checkpoint(&mut self) -> Result<Option<Checkpoint>> {
+        Ok(None)
+    }
+
+    /// Persist a checkpoint, called after every completed sync step.
+    fn save_checkpoint(&mut self, _checkpoint: &Checkpoint) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl SyncStore for Archive {
+    fn put_item(&mut self, item: Item) -> Result<()> {
+        self.add_items(Some(item));
+        Ok(())
+    }
+
+    fn put_user(&mut self, user: User) -> Result<()> {
+        self.add_users(Some(user));
+        Ok(())
+    }
+}
+
+/// The progress of a [`SyncEngine`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Checkpoint {
+    /// The highest item id known to be stored.
+    pub max_item_id: ItemId,
+    /// The last applied updates, to skip changes that were already stored.
+    pub last_updates: Option<Updates>,
+}
+
+/// The number of items and users stored by a sync step.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// The number of stored items.
+    pub items: usize,
+    /// The number of stored users.
+    pub users: usize,
+}
+
+/// Mirrors Hacker News data into a [`SyncStore`].
+pub struct SyncEngine<'a, S> {
+    client: &'a HnClient,
+    store: S,
+    lists: Vec<StoryList>,
+    interval: Duration,
+    retries: u32,
+    retry_delay: Duration,
+    checkpoint: Option<Checkpoint>,
+}
+
+impl<'a, S: SyncStore> SyncEngine<'a, S> {
+    /// Create an engine that bootstraps from the top stories and polls for
+    /// updates every 30 seconds.
+    pub fn new(client: &'a HnClient, store: S) -> Self {
+        Self {
+            client,
+            store,
+            lists: vec![StoryList::Top],
+            interval: Duration::from_secs(30),
+            retries: 3,
+            retry_delay: Duration::from_secs(1),
+            checkpoint: None,
+        }
+    }
+
+    /// Set the listings whose stories are stored when bootstrapping.
+    pub fn lists(mut self, lists: &[StoryList]) -> Self {
+        self.lists = lists.to_vec();
+        self
+    }
+
+    /// Set how often the updates endpoint is polled.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Set how many times a request is retried, waiting `delay` before the
+    /// first retry and doubling it for every further one.
+    ///
+    /// Only errors that are [retryable](crate::HnClientError::is_retryable)
+    /// are retried.
+    pub fn retries(mut self, retries: u32, delay: Duration) -> Self {
+        self.retries = retries;
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Return the store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Consume the engine and return the store.
+    pub fn into_store(self) -> S {
+        self.store
+    }
+
+    /// Return the current checkpoint, if the engine was bootstrapped.
+    pub fn checkpoint(&self) -> Option<&Checkpoint> {
+        self.checkpoint.as_ref()
+    }
+
+    /// Fill the store before polling for updates.
+    ///
+    /// Without a saved checkpoint, the stories of the configured listings are
+    /// stored. Otherwise all items created since the checkpoint are stored.
+    pub async fn bootstrap(&mut self) -> Result<SyncReport> {
+        let max_item_id = self.retry(|client| client.get_max_item_id()).await?;
+        let stored = match self.store.load_checkpoint()? {
+            Some(checkpoint) => {
+                let ids: Vec<ItemId> = (checkpoint.max_item_id.0 + 1..=max_item_id.0)
+                    .map(ItemId)
+                    .collect();
+                self.checkpoint = Some(checkpoint);
+                self.store_items(&ids).await?
+            }
+            None => {
+                let mut ids = Vec::new();
+                for list in self.lists.clone() {
+                    ids.extend(self.retry(|client| client.get_stories(list)).await?);
+                }
+                ids.sort_unstable();
+                ids.dedup();
+                self.store_items(&ids).await?
+            }
+        };
+
+        let checkpoint = self.checkpoint.get_or_insert_with(Checkpoint::default);
+        checkpoint.max_item_id = checkpoint.max_item_id.max(max_item_id);
+        self.store.save_checkpoint(checkpoint)?;
+        Ok(SyncReport {
+            items: stored,
+            users: 0,
+        })
+    }
+
+    /// Poll the updates endpoint once and store the changed items and users,
+    /// along with all items created since the last step.
+    ///
+    /// Bootstraps first if necessary.
+    pub async fn step(&mut self) -> Result<SyncReport> {
+        if self.checkpoint.is_none() {
+            self.bootstrap().await?;
+        }
+        let updates = self.retry(|client| client.get_updates()).await?;
+        let max_item_id = self.retry(|client| client.get_max_item_id()).await?;
+        let checkpoint = self.checkpoint.as_ref().expect("bootstrapped");
+
+        // The updates only list changed items, not every new one.
+        let mut items: Vec<ItemId> = (checkpoint.max_item_id.0 + 1..=max_item_id.0)
+            .map(ItemId)
+            .collect();
+        let mut users = Vec::new();
+        for event in updates.events_since(checkpoint.last_updates.as_ref()) {
+            match event {
+                UpdateEvent::Item(id) => items.push(id),
+                UpdateEvent::Profile(username) => users.push(username),
+            }
+        }
+        items.sort_unstable();
+        items.dedup();
+
+        let mut report = SyncReport {
+            items: self.store_items(&items).await?,
+            users: 0,
+        };
+        for username in &users {
+            if let Some(user) = self.retry(|client| client.try_get_user(username)).await? {
+                self.store.put_user(user)?;
+                report.users += 1;
+            }
+        }
+
+        let checkpoint = self.checkpoint.as_mut().expect("bootstrapped");
+        checkpoint.max_item_id = checkpoint.max_item_id.max(max_item_id);
+        checkpoint.last_updates = Some(updates);
+        self.store.save_checkpoint(checkpoint)?;
+        Ok(report)
+    }
+
+    /// Bootstrap, then apply updates every interval.
+    ///
+    /// Only returns if a request still fails after all retries, the store
+    /// fails, or the client is [shut down](crate::shutdown), after finishing
+    /// the current step.
+    pub async fn run(&mut self) -> Result<()> {
+        let token = self.client.shutdown_token();
+        while !token.is_shutdown() {
+            self.step().await?;
+            let sleep = Box::pin(runtime::sleep(self.interval));
+            future::select(sleep, Box::pin(token.wait())).await;
+        }
+        Ok(())
+    }
+
+    async fn store_items(&mut self, ids: &[ItemId]) -> Result<usize> {
+        let mut stored = 0;
+        for chunk in ids.chunks(self.client.batch_size()) {
+            let items = self
+                .retry(|client| client.get_items_filtered(chunk, ItemFilter::ALL))
+                .await?;
+            for item in items {
+                self.store.put_item(item)?;
+                stored += 1;
+            }
+        }
+        Ok(stored)
+    }
+
+    async fn retry<T, F, Fut>(&self, mut request: F) -> Result<T>
+    where
+        F: FnMut(&'a HnClient) -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        let mut delay = self.retry_delay;
+        let mut attempt = 0;
+        loop {
+            match request(self.client).await {
+                Ok(value) => return Ok(value),
+                Err(err) if attempt < self.retries && err.is_retryable() => {
+                    attempt += 1;
+                    runtime::sleep(delay).await;
+                    delay *= 2;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cassette::Cassette;
+
+    #[test]
+    fn test_archive_store() {
+        let item: Item = serde_json::from_str(
+            r#"{"by": "a", "id": 1, "parent": 0, "text": "", "time": 0, "type": "comment"}"#,
+        )
+        .unwrap();
+        let mut archive = Archive::default();
+        archive.put_item(item.clone()).unwrap();
+        archive.put_item(item).unwrap();
+        assert_eq!(archive.items.len(), 1);
+        assert_eq!(archive.load_checkpoint().unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_step_stores_new_items() {
+        let api = "https://hacker-news.firebaseio.com/v0";
+        let mut cassette = Cassette::new();
+        cassette
+            .push(format!("{}/maxitem.json", api), "3")
+            .push(format!("{}/maxitem.json", api), "6")
+            .push(format!("{}/topstories.json", api), "[1]")
+            .push(
+                format!("{}/updates.json", api),
+                r#"{"items": [2, 6], "profiles": []}"#,
+            );
+        for id in 1..=6 {
+            let item = format!(
+                r#"{{"id": {}, "score": 1, "time": 0, "type": "story"}}"#,
+                id
+            );
+            cassette.push(format!("{}/item/{}.json", api, id), item);
+        }
+        let client = HnClient::builder().replay(cassette).build().unwrap();
+        let mut engine = SyncEngine::new(&client, Archive::default());
+        let report = engine.step().await.unwrap();
+        // Items 4 and 5 are new but not among the updates.
+        assert_eq!(report.items, 4);
+        let ids: Vec<_> = engine.store().items.keys().map(|id| id.0).collect();
+        assert_eq!(ids, [1, 2, 4, 5, 6]);
+        assert_eq!(engine.checkpoint().unwrap().max_item_id, ItemId(6));
+    }
+}
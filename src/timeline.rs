@@ -0,0 +1,182 @@
+//! A user's recent activity as a timeline, e.g. for profile pages.
+//!
+//! ```rust,no_run
+//! use hn_api::{nonblocking::HnClient, timeline::TimelineEvent};
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! for event in client.get_user_timeline("pg", 20).await? {
+//!     match event {
+//!         TimelineEvent::Posted(item) => println!("posted {}", item.title().unwrap_or("?")),
+//!         TimelineEvent::Commented { story, .. } => println!(
+//!             "commented on {}",
+//!             story.as_ref().and_then(|story| story.title()).unwrap_or("?")
+//!         ),
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use super::{
+    ancestry::MAX_DEPTH,
+    nonblocking::HnClient,
+    types::{Item, ItemFilter, ItemId, Timestamp},
+    Result,
+};
+
+/// An entry of a user's timeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimelineEvent {
+    /// The user submitted a story, poll or job.
+    Posted(Item),
+    /// The user commented.
+    Commented {
+        /// The comment.
+        comment: Item,
+        /// The item at the top of the thread, usually a story. `None` if an
+        /// item of the thread is missing.
+        story: Option<Item>,
+    },
+}
+
+impl TimelineEvent {
+    /// Return the item the user submitted.
+    pub fn item(&self) -> &Item {
+        match self {
+            TimelineEvent::Posted(item) => item,
+            TimelineEvent::Commented { comment, .. } => comment,
+        }
+    }
+
+    /// Return when the user submitted the item.
+    pub fn time(&self) -> Timestamp {
+        self.item().time()
+    }
+}
+
+impl HnClient {
+    /// Return the user's `limit` newest submissions as a timeline, oldest
+    /// first.
+    ///
+    /// Comments come with the story they belong to. Dead and deleted
+    /// submissions are skipped.
+    /// May return error if username is invalid.
+    /// Fails if any of the request failed.
+    pub async fn get_user_timeline(
+        &self,
+        username: &str,
+        limit: usize,
+    ) -> Result<Vec<TimelineEvent>> {
+        let user = self.get_user(username).await?;
+        let recent = &user.submitted[..user.submitted.len().min(limit)];
+        let mut items = self.get_items_filtered(recent, ItemFilter::ALIVE).await?;
+        items.sort_by_key(|item| (item.time(), item.id()));
+
+        let comments: Vec<&Item> = items
+            .iter()
+            .filter(|item| matches!(item, Item::Comment(_)))
+            .collect();
+        let mut stories = self.find_stories(&comments).await?;
+        Ok(items
+            .into_iter()
+            .map(|item| match item {
+                Item::Comment(_) => TimelineEvent::Commented {
+                    story: stories.remove(&item.id()),
+                    comment: item,
+                },
+                item => TimelineEvent::Posted(item),
+            })
+            .collect())
+    }
+
+    /// Return the items at the top of the threads of `comments`, by comment
+    /// id, walking up the parents of all comments one level at a time.
+    async fn find_stories(&self, comments: &[&Item]) -> Result<HashMap<ItemId, Item>> {
+        let mut fetched: HashMap<ItemId, Item> = HashMap::new();
+        // The comments whose story is not found yet, with their current
+        // ancestor.
+        let mut pending: Vec<(ItemId, ItemId)> = comments
+            .iter()
+            .filter_map(|comment| Some((comment.id(), comment.parent()?)))
+            .collect();
+        let mut stories = HashMap::new();
+        for _ in 0..MAX_DEPTH {
+            if pending.is_empty() {
+                break;
+            }
+            let mut missing: Vec<ItemId> = pending
+                .iter()
+                .map(|(_, ancestor)| *ancestor)
+                .filter(|id| !fetched.contains_key(id))
+                .collect();
+            missing.sort_unstable();
+            missing.dedup();
+            for item in self.try_get_items(&missing).await?.into_iter().flatten() {
+                self.parent_cache().record(&item);
+                fetched.insert(item.id(), item);
+            }
+            pending.retain_mut(|(comment, ancestor)| match fetched.get(ancestor) {
+                Some(item) => match item.parent() {
+                    Some(parent) if matches!(item, Item::Comment(_)) => {
+                        *ancestor = parent;
+                        true
+                    }
+                    _ => {
+                        stories.insert(*comment, item.clone());
+                        false
+                    }
+                },
+                None => false,
+            });
+        }
+        Ok(stories)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cassette::Cassette;
+
+    #[tokio::test]
+    async fn test_user_timeline() {
+        let api = "https://hacker-news.firebaseio.com/v0";
+        let mut cassette = Cassette::new();
+        cassette
+            .push(
+                format!("{}/user/a.json", api),
+                r#"{"id": "a", "created": 0, "karma": 1, "submitted": [4, 1]}"#,
+            )
+            .push(
+                format!("{}/item/1.json", api),
+                r#"{"by": "a", "id": 1, "score": 1, "time": 10, "title": "t", "type": "story"}"#,
+            )
+            .push(
+                format!("{}/item/2.json", api),
+                r#"{"id": 2, "score": 1, "time": 20, "type": "story"}"#,
+            )
+            .push(
+                format!("{}/item/3.json", api),
+                r#"{"by": "b", "id": 3, "parent": 2, "text": "", "time": 30, "type": "comment"}"#,
+            )
+            .push(
+                format!("{}/item/4.json", api),
+                r#"{"by": "a", "id": 4, "parent": 3, "text": "", "time": 40, "type": "comment"}"#,
+            );
+        let client = HnClient::builder().replay(cassette).build().unwrap();
+
+        let timeline = client.get_user_timeline("a", 10).await.unwrap();
+        assert_eq!(timeline.len(), 2);
+        assert!(matches!(&timeline[0], TimelineEvent::Posted(item) if item.id() == ItemId(1)));
+        match &timeline[1] {
+            TimelineEvent::Commented { comment, story } => {
+                assert_eq!(comment.id(), ItemId(4));
+                assert_eq!(story.as_ref().map(Item::id), Some(ItemId(2)));
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+    }
+}
@@ -0,0 +1,726 @@
+//! Comment trees of stories and other items.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::Duration,
+};
+
+use serde::Serialize;
+
+use super::{
+    html,
+    types::{Item, ItemId},
+};
+
+/// An item together with all of its replies, recursively.
+///
+/// The root is usually a story or poll, every other node is a comment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommentTree {
+    /// The item at this node.
+    pub item: Item,
+    /// The replies to the item, in ranked display order.
+    pub replies: Vec<CommentTree>,
+}
+
+/// A node that differs between two fetches of the same tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeChange {
+    /// The id of the changed item.
+    pub id: ItemId,
+    /// The ids from the root item down to the changed item, inclusive.
+    pub path: Vec<ItemId>,
+}
+
+/// The differences between two fetches of the same tree.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeDiff {
+    /// Nodes only present in the new tree, with their path in the new tree.
+    pub added: Vec<TreeChange>,
+    /// Nodes only present in the old tree, with their path in the old tree.
+    pub removed: Vec<TreeChange>,
+    /// Nodes whose text, title or dead/deleted state changed, with their path
+    /// in the new tree.
+    pub edited: Vec<TreeChange>,
+}
+
+impl TreeDiff {
+    /// Return whether both trees are equal.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.edited.is_empty()
+    }
+}
+
+/// The order in which [`CommentTree::flatten`] lists the items of a tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraversalOrder {
+    /// Every comment is followed by its replies, as displayed on the website.
+    DepthFirst,
+    /// All comments of one level before the comments of the next level.
+    BreadthFirst,
+    /// The newest comments first, regardless of their level.
+    NewestFirst,
+}
+
+/// An item of a flattened tree, see [`CommentTree::flatten`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FlatComment {
+    /// The item.
+    pub item: Item,
+    /// The level of the item, 0 for the root.
+    pub depth: usize,
+    /// The id of the item this is a reply to, `None` for the root.
+    pub parent: Option<ItemId>,
+}
+
+/// Criteria for [`CommentTree::search`]. All of them must match.
+///
+/// ```
+/// use hn_api::tree::CommentQuery;
+///
+/// let query = CommentQuery::new().keyword("rust").author("pg");
+/// let query: CommentQuery = "rust async".into();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommentQuery {
+    keywords: Vec<String>,
+    author: Option<String>,
+}
+
+impl CommentQuery {
+    /// Create a query matching every comment.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only match comments whose text contains `keyword`, ignoring case.
+    pub fn keyword(mut self, keyword: &str) -> Self {
+        self.keywords.push(keyword.to_lowercase());
+        self
+    }
+
+    /// Only match comments by `username`, ignoring case.
+    pub fn author(mut self, username: &str) -> Self {
+        self.author = Some(username.to_string());
+        self
+    }
+
+    /// Return whether the item matches the query.
+    pub fn matches(&self, item: &Item) -> bool {
+        if let Some(author) = &self.author {
+            if !item
+                .author()
+                .is_some_and(|by| by.eq_ignore_ascii_case(author))
+            {
+                return false;
+            }
+        }
+        if self.keywords.is_empty() {
+            return true;
+        }
+        let text = html::plain_text(item.text().unwrap_or_default()).to_lowercase();
+        self.keywords.iter().all(|keyword| text.contains(keyword))
+    }
+}
+
+impl From<&str> for CommentQuery {
+    /// Match comments containing all whitespace-separated words.
+    fn from(keywords: &str) -> Self {
+        keywords
+            .split_whitespace()
+            .fold(CommentQuery::new(), CommentQuery::keyword)
+    }
+}
+
+/// A comment found by [`CommentTree::search`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommentMatch<'a> {
+    /// The matching comment.
+    pub item: &'a Item,
+    /// The ids from the root item down to the comment, inclusive.
+    pub path: Vec<ItemId>,
+}
+
+/// Limits for fetching large trees, see
+/// [`HnClient::get_comment_tree_limited`].
+///
+/// The tree is fetched level by level, in display order, until a limit is
+/// reached. No limit is set by default.
+///
+/// [`HnClient::get_comment_tree_limited`]: crate::nonblocking::HnClient::get_comment_tree_limited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TreeLimits {
+    /// The number of levels of replies to fetch below the root.
+    pub max_depth: Option<usize>,
+    /// The number of comments to fetch.
+    pub max_comments: Option<usize>,
+    /// The time fetching may take. Comments still outstanding when it is
+    /// over are left out.
+    pub max_duration: Option<Duration>,
+}
+
+/// Replies that were left out of a partial tree, to be fetched later with
+/// [`HnClient::resume_comment_tree`].
+///
+/// [`HnClient::resume_comment_tree`]: crate::nonblocking::HnClient::resume_comment_tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Continuation {
+    /// The item the replies belong to.
+    pub parent: ItemId,
+    /// The ids of the missing replies, in display order.
+    pub ids: Vec<ItemId>,
+}
+
+/// A tree that may be missing some replies because of [`TreeLimits`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialTree {
+    /// The fetched part of the tree.
+    pub tree: CommentTree,
+    /// The replies left out, in display order of the tree.
+    pub continuations: Vec<Continuation>,
+}
+
+impl PartialTree {
+    /// Return whether no replies were left out.
+    pub fn is_complete(&self) -> bool {
+        self.continuations.is_empty()
+    }
+
+    /// Insert a tree fetched from one of the continuations, and replace
+    /// the continuation by the ones of `other`.
+    ///
+    /// Returns `false`, leaving the tree unchanged, if the root of `other`
+    /// is not part of this tree.
+    pub fn merge(&mut self, other: PartialTree) -> bool {
+        let root = other.tree.item.id();
+        let node = match self.tree.find_mut(root) {
+            Some(node) => node,
+            None => return false,
+        };
+        let known: HashSet<_> = node.replies.iter().map(|reply| reply.item.id()).collect();
+        node.replies.extend(
+            other
+                .tree
+                .replies
+                .into_iter()
+                .filter(|reply| !known.contains(&reply.item.id())),
+        );
+        let kids = node.item.kids().to_vec();
+        let rank = |id: ItemId| kids.iter().position(|kid| *kid == id);
+        node.replies
+            .sort_by_key(|reply| (rank(reply.item.id()).unwrap_or(usize::MAX), reply.item.id()));
+        let merged: HashSet<_> = self.tree.iter().map(Item::id).collect();
+        for continuation in &mut self.continuations {
+            continuation.ids.retain(|id| !merged.contains(id));
+        }
+        self.continuations
+            .retain(|continuation| continuation.parent != root && !continuation.ids.is_empty());
+        self.continuations.extend(other.continuations);
+        true
+    }
+}
+
+/// The ids already reached while fetching one or several trees.
+///
+/// Every id is claimed once: a comment listed as a reply by several items,
+/// including by one of its own replies in malformed data, is only fetched
+/// and placed in the first tree that reaches it.
+#[derive(Debug, Default)]
+#[cfg_attr(not(feature = "nonblocking"), allow(dead_code))]
+pub(crate) struct VisitedIds(Mutex<HashSet<ItemId>>);
+
+#[cfg_attr(not(feature = "nonblocking"), allow(dead_code))]
+impl VisitedIds {
+    /// Return the ids that were not reached yet, in order and without
+    /// duplicates, and mark them as reached.
+    pub(crate) fn claim(&self, ids: &[ItemId]) -> Vec<ItemId> {
+        let mut visited = self.0.lock().unwrap();
+        ids.iter()
+            .copied()
+            .filter(|id| visited.insert(*id))
+            .collect()
+    }
+}
+
+/// A comment ranked by [`CommentTree::top_comments`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoredComment<'a> {
+    /// The comment.
+    pub item: &'a Item,
+    /// The level of the comment, 1 for top-level comments.
+    pub depth: usize,
+    /// The heuristic score, higher is better.
+    pub score: f64,
+}
+
+/// Statistics about the comments of a tree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TreeStats {
+    /// The number of comments that are neither dead nor deleted.
+    pub comments: usize,
+    /// The number of dead or deleted comments.
+    pub hidden: usize,
+    /// The number of levels of replies below the root.
+    pub max_depth: usize,
+    /// The number of distinct authors of the comments.
+    pub commenters: usize,
+    /// The comment count reported by the root item, if it has one.
+    pub reported_descendants: Option<u32>,
+}
+
+impl TreeStats {
+    /// Return how many more comments the root item reports than the tree
+    /// contains, e.g. because of comments missing from the tree. Negative if
+    /// the tree contains more.
+    pub fn discrepancy(&self) -> Option<i64> {
+        self.reported_descendants
+            .map(|reported| i64::from(reported) - self.comments as i64)
+    }
+}
+
+impl CommentTree {
+    /// Return the number of items in the tree, including the root.
+    pub fn len(&self) -> usize {
+        1 + self.replies.iter().map(CommentTree::len).sum::<usize>()
+    }
+
+    /// Return whether the tree only consists of the root item.
+    pub fn is_empty(&self) -> bool {
+        self.replies.is_empty()
+    }
+
+    /// Return an iterator over all items in the tree, depth-first in display
+    /// order, starting with the root.
+    pub fn iter(&self) -> impl Iterator<Item = &Item> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.replies.iter().rev());
+            Some(&node.item)
+        })
+    }
+
+    /// Return all items of the tree annotated with their depth, in the
+    /// specified order. The root always comes first.
+    pub fn flatten(&self, order: TraversalOrder) -> Vec<FlatComment> {
+        fn node(tree: &CommentTree, depth: usize, parent: Option<ItemId>) -> FlatComment {
+            FlatComment {
+                item: tree.item.clone(),
+                depth,
+                parent,
+            }
+        }
+        let mut out = vec![node(self, 0, None)];
+        match order {
+            TraversalOrder::BreadthFirst => {
+                let mut level = vec![self];
+                let mut depth = 1;
+                while !level.is_empty() {
+                    let mut next = Vec::new();
+                    for tree in level {
+                        for reply in &tree.replies {
+                            out.push(node(reply, depth, Some(tree.item.id())));
+                            next.push(reply);
+                        }
+                    }
+                    level = next;
+                    depth += 1;
+                }
+            }
+            TraversalOrder::DepthFirst | TraversalOrder::NewestFirst => {
+                let mut stack: Vec<_> = self.replies.iter().rev().map(|r| (r, 1, self)).collect();
+                while let Some((tree, depth, parent)) = stack.pop() {
+                    out.push(node(tree, depth, Some(parent.item.id())));
+                    stack.extend(tree.replies.iter().rev().map(|r| (r, depth + 1, tree)));
+                }
+                if order == TraversalOrder::NewestFirst {
+                    out[1..].sort_by(|a, b| {
+                        (b.item.time(), b.item.id()).cmp(&(a.item.time(), a.item.id()))
+                    });
+                }
+            }
+        }
+        out
+    }
+
+    /// Rebuild the subtree rooted at the item with the specified id from a
+    /// flattened tree, in any order.
+    ///
+    /// Replies are ordered like the kids of their parent. Returns `None` if
+    /// the item is not part of `nodes`.
+    pub fn expand(nodes: &[FlatComment], id: impl Into<ItemId>) -> Option<CommentTree> {
+        fn build(item: &Item, replies: &HashMap<ItemId, Vec<&Item>>) -> CommentTree {
+            let mut children = replies.get(&item.id()).cloned().unwrap_or_default();
+            let rank = |child: &Item| item.kids().iter().position(|kid| *kid == child.id());
+            children.sort_by_key(|child| (rank(child).unwrap_or(usize::MAX), child.id()));
+            CommentTree {
+                item: item.clone(),
+                replies: children.into_iter().map(|c| build(c, replies)).collect(),
+            }
+        }
+        let id = id.into();
+        let root = nodes.iter().find(|node| node.item.id() == id)?;
+        let mut replies: HashMap<ItemId, Vec<&Item>> = HashMap::new();
+        for node in nodes {
+            if let Some(parent) = node.parent {
+                replies.entry(parent).or_default().push(&node.item);
+            }
+        }
+        Some(build(&root.item, &replies))
+    }
+
+    /// Count the comments, depth and commenters of the tree.
+    pub fn stats(&self) -> TreeStats {
+        let mut stats = TreeStats {
+            reported_descendants: self.item.descendants(),
+            ..TreeStats::default()
+        };
+        let mut commenters = HashSet::new();
+        let mut stack: Vec<_> = self.replies.iter().map(|reply| (reply, 1)).collect();
+        while let Some((node, depth)) = stack.pop() {
+            if node.item.is_alive() {
+                stats.comments += 1;
+            } else {
+                stats.hidden += 1;
+            }
+            if let Some(author) = node.item.author() {
+                commenters.insert(author);
+            }
+            stats.max_depth = stats.max_depth.max(depth);
+            stack.extend(node.replies.iter().map(|reply| (reply, depth + 1)));
+        }
+        stats.commenters = commenters.len();
+        stats
+    }
+
+    /// Report the nodes that were added, removed or edited between two
+    /// fetches of the same tree.
+    pub fn diff(old: &CommentTree, new: &CommentTree) -> TreeDiff {
+        let old_nodes = old.paths();
+        let new_nodes = new.paths();
+        let old_index: HashMap<ItemId, &Item> = old_nodes
+            .iter()
+            .map(|(_, item)| (item.id(), *item))
+            .collect();
+        let new_ids: HashMap<ItemId, ()> =
+            new_nodes.iter().map(|(_, item)| (item.id(), ())).collect();
+
+        let mut diff = TreeDiff::default();
+        for (path, item) in &new_nodes {
+            let change = TreeChange {
+                id: item.id(),
+                path: path.clone(),
+            };
+            match old_index.get(&item.id()) {
+                None => diff.added.push(change),
+                Some(old_item) if is_edited(old_item, item) => diff.edited.push(change),
+                Some(_) => {}
+            }
+        }
+        diff.removed = old_nodes
+            .into_iter()
+            .filter(|(_, item)| !new_ids.contains_key(&item.id()))
+            .map(|(path, item)| TreeChange {
+                id: item.id(),
+                path,
+            })
+            .collect();
+        diff
+    }
+
+    /// Return the comments matching `query`, depth-first in display order.
+    /// The root item is not searched.
+    pub fn search(&self, query: impl Into<CommentQuery>) -> Vec<CommentMatch<'_>> {
+        let query = query.into();
+        self.find_comments(|item| query.matches(item))
+    }
+
+    /// Return the comments for which `predicate` returns `true`, depth-first
+    /// in display order. The root item is not searched.
+    pub fn find_comments<F>(&self, mut predicate: F) -> Vec<CommentMatch<'_>>
+    where
+        F: FnMut(&Item) -> bool,
+    {
+        self.paths()
+            .into_iter()
+            .skip(1)
+            .filter(|(_, item)| predicate(item))
+            .map(|(path, item)| CommentMatch { item, path })
+            .collect()
+    }
+
+    /// Return the `n` comments most likely to be the best of the tree, best
+    /// first.
+    ///
+    /// The API does not expose comment scores, so this is a heuristic: a
+    /// comment scores higher the further up it is ranked among its siblings,
+    /// the closer it is to the root, the more replies it got and the longer
+    /// it is, up to a few paragraphs. Dead and deleted comments are skipped.
+    pub fn top_comments(&self, n: usize) -> Vec<ScoredComment<'_>> {
+        const FULL_LENGTH: f64 = 600.0;
+        let mut scored = Vec::new();
+        let mut stack: Vec<_> = self
+            .replies
+            .iter()
+            .enumerate()
+            .map(|(i, r)| (r, i, 1))
+            .collect();
+        while let Some((node, position, depth)) = stack.pop() {
+            stack.extend(
+                node.replies
+                    .iter()
+                    .enumerate()
+                    .map(|(i, reply)| (reply, i, depth + 1)),
+            );
+            if !node.item.is_alive() {
+                continue;
+            }
+            let length = html::plain_text(node.item.text().unwrap_or_default())
+                .chars()
+                .count() as f64;
+            let replies = (node.len() - 1) as f64;
+            let score = (1.0 / (1 + position) as f64)
+                * (1.0 / depth as f64)
+                * (1.0 + replies.ln_1p())
+                * (0.5 + 0.5 * (length / FULL_LENGTH).min(1.0));
+            scored.push(ScoredComment {
+                item: &node.item,
+                depth,
+                score,
+            });
+        }
+        scored.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.item.id().cmp(&b.item.id()))
+        });
+        scored.truncate(n);
+        scored
+    }
+
+    /// Return the node of the item with the specified id.
+    fn find_mut(&mut self, id: ItemId) -> Option<&mut CommentTree> {
+        if self.item.id() == id {
+            return Some(self);
+        }
+        self.replies.iter_mut().find_map(|reply| reply.find_mut(id))
+    }
+
+    /// Return every item with its path from the root, depth-first.
+    fn paths(&self) -> Vec<(Vec<ItemId>, &Item)> {
+        fn walk<'a>(
+            tree: &'a CommentTree,
+            path: &mut Vec<ItemId>,
+            out: &mut Vec<(Vec<ItemId>, &'a Item)>,
+        ) {
+            path.push(tree.item.id());
+            out.push((path.clone(), &tree.item));
+            for reply in &tree.replies {
+                walk(reply, path, out);
+            }
+            path.pop();
+        }
+        let mut out = Vec::new();
+        walk(self, &mut Vec::new(), &mut out);
+        out
+    }
+}
+
+fn is_edited(old: &Item, new: &Item) -> bool {
+    old.text() != new.text()
+        || old.title() != new.title()
+        || old.is_dead() != new.is_dead()
+        || old.is_deleted() != new.is_deleted()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(id: u32, text: &str, replies: Vec<CommentTree>) -> CommentTree {
+        let kids: Vec<_> = replies.iter().map(|reply| reply.item.id().0).collect();
+        let json = format!(
+            r#"{{"by": "a", "id": {}, "kids": {:?}, "parent": 0, "text": "{}", "time": {}, "type": "comment"}}"#,
+            id, kids, text, id
+        );
+        CommentTree {
+            item: serde_json::from_str(&json).unwrap(),
+            replies,
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = comment(1, "", vec![comment(2, "a", vec![comment(3, "b", vec![])])]);
+        let new = comment(
+            1,
+            "",
+            vec![comment(2, "edited", vec![]), comment(4, "c", vec![])],
+        );
+        let diff = CommentTree::diff(&old, &new);
+        assert_eq!(
+            diff.added,
+            vec![TreeChange {
+                id: ItemId(4),
+                path: vec![ItemId(1), ItemId(4)]
+            }]
+        );
+        assert_eq!(
+            diff.removed,
+            vec![TreeChange {
+                id: ItemId(3),
+                path: vec![ItemId(1), ItemId(2), ItemId(3)]
+            }]
+        );
+        assert_eq!(
+            diff.edited,
+            vec![TreeChange {
+                id: ItemId(2),
+                path: vec![ItemId(1), ItemId(2)]
+            }]
+        );
+        assert!(CommentTree::diff(&new, &new).is_empty());
+        assert_eq!(new.len(), 3);
+        assert_eq!(old.iter().map(Item::id).collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_search() {
+        let tree = comment(
+            1,
+            "Rust",
+            vec![comment(
+                2,
+                "I like &lt;Rust&gt;",
+                vec![comment(3, "rust is fun", vec![])],
+            )],
+        );
+        let paths = |matches: Vec<CommentMatch<'_>>| -> Vec<Vec<u32>> {
+            matches
+                .iter()
+                .map(|m| m.path.iter().map(|id| id.0).collect())
+                .collect()
+        };
+        assert_eq!(paths(tree.search("RUST")), vec![vec![1, 2], vec![1, 2, 3]]);
+        assert_eq!(paths(tree.search("<rust> like")), vec![vec![1, 2]]);
+        assert!(tree.search(CommentQuery::new().author("b")).is_empty());
+        assert_eq!(tree.search(CommentQuery::new().author("A")).len(), 2);
+        let found = tree.find_comments(|item| item.id() == 3);
+        assert_eq!(found[0].item.text(), Some("rust is fun"));
+    }
+
+    #[test]
+    fn test_top_comments() {
+        let long = "x".repeat(600);
+        let tree = comment(
+            1,
+            "",
+            vec![
+                comment(2, "short", vec![comment(3, "short", vec![])]),
+                comment(4, &long, vec![]),
+                comment(5, "short", vec![]),
+            ],
+        );
+        let ids: Vec<_> = tree
+            .top_comments(3)
+            .iter()
+            .map(|scored| scored.item.id().0)
+            .collect();
+        assert_eq!(ids, vec![2, 4, 3]);
+        assert_eq!(tree.top_comments(10).len(), 4);
+    }
+
+    #[test]
+    fn test_visited_ids() {
+        let visited = VisitedIds::default();
+        let ids = |ids: &[u32]| -> Vec<ItemId> { ids.iter().copied().map(ItemId).collect() };
+        assert_eq!(visited.claim(&ids(&[1, 2, 1])), ids(&[1, 2]));
+        assert_eq!(visited.claim(&ids(&[3, 2])), ids(&[3]));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut partial = PartialTree {
+            tree: comment(1, "", vec![comment(4, "", vec![])]),
+            continuations: vec![Continuation {
+                parent: ItemId(1),
+                ids: vec![ItemId(2)],
+            }],
+        };
+        // The kids of the root are [4], extend them so that 2 ranks first.
+        if let Item::Comment(root) = &mut partial.tree.item {
+            root.kids = Some(vec![ItemId(2), ItemId(4)]);
+        }
+        let resumed = PartialTree {
+            tree: CommentTree {
+                item: partial.tree.item.clone(),
+                replies: vec![comment(2, "", vec![])],
+            },
+            continuations: vec![Continuation {
+                parent: ItemId(2),
+                ids: vec![ItemId(3)],
+            }],
+        };
+        assert!(partial.merge(resumed));
+        let ids: Vec<_> = partial.tree.iter().map(|item| item.id().0).collect();
+        assert_eq!(ids, vec![1, 2, 4]);
+        assert_eq!(partial.continuations[0].parent, ItemId(2));
+        assert!(!partial.is_complete());
+        assert!(!partial.merge(PartialTree {
+            tree: comment(9, "", vec![]),
+            continuations: Vec::new(),
+        }));
+    }
+
+    #[test]
+    fn test_stats() {
+        let tree = comment(1, "", vec![comment(2, "a", vec![comment(3, "b", vec![])])]);
+        let stats = tree.stats();
+        assert_eq!(stats.comments, 2);
+        assert_eq!(stats.hidden, 0);
+        assert_eq!(stats.max_depth, 2);
+        assert_eq!(stats.commenters, 1);
+        assert_eq!(stats.discrepancy(), None);
+        let stats = TreeStats {
+            reported_descendants: Some(5),
+            ..stats
+        };
+        assert_eq!(stats.discrepancy(), Some(3));
+    }
+
+    #[test]
+    fn test_flatten_and_expand() {
+        let tree = comment(
+            1,
+            "",
+            vec![
+                comment(4, "", vec![comment(5, "", vec![])]),
+                comment(2, "", vec![comment(3, "", vec![])]),
+            ],
+        );
+        let ids = |order| {
+            tree.flatten(order)
+                .iter()
+                .map(|node| (node.item.id().0, node.depth))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ids(TraversalOrder::DepthFirst),
+            vec![(1, 0), (4, 1), (5, 2), (2, 1), (3, 2)]
+        );
+        assert_eq!(
+            ids(TraversalOrder::BreadthFirst),
+            vec![(1, 0), (4, 1), (2, 1), (5, 2), (3, 2)]
+        );
+        assert_eq!(
+            ids(TraversalOrder::NewestFirst),
+            vec![(1, 0), (5, 2), (4, 1), (3, 2), (2, 1)]
+        );
+
+        let flat = tree.flatten(TraversalOrder::NewestFirst);
+        assert_eq!(CommentTree::expand(&flat, 1), Some(tree.clone()));
+        assert_eq!(CommentTree::expand(&flat, 2), Some(tree.replies[1].clone()));
+        assert_eq!(CommentTree::expand(&flat, 9), None);
+    }
+}
@@ -1,9 +1,272 @@
 //! Item types returned by the API.
 
-use serde::Deserialize;
+use std::{
+    borrow::Borrow,
+    fmt,
+    num::ParseIntError,
+    ops::Deref,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// A point in time as returned by the API, in Unix Time.
+///
+/// Convert it into the representation you need with [`Timestamp::as_secs`],
+/// [`Timestamp::to_system_time`] or, with the `chrono` feature enabled,
+/// [`Timestamp::to_datetime`]. It compares with `u64` seconds, so code that
+/// read times as `u64` mostly keeps compiling.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize,
+)]
+#[serde(transparent)]
+pub struct Timestamp(pub u64);
+
+impl Timestamp {
+    /// Return the current time.
+    pub fn now() -> Self {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|age| Timestamp(age.as_secs()))
+            .unwrap_or_default()
+    }
+
+    /// Return the number of seconds since the Unix epoch.
+    pub fn as_secs(self) -> u64 {
+        self.0
+    }
+
+    /// Return the timestamp as a `SystemTime`.
+    pub fn to_system_time(self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.0)
+    }
+
+    /// Return the timestamp as a UTC `chrono::DateTime`.
+    #[cfg(feature = "chrono")]
+    pub fn to_datetime(self) -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::from_timestamp(self.0 as i64, 0).unwrap_or_default()
+    }
+}
+
+impl From<u64> for Timestamp {
+    fn from(secs: u64) -> Self {
+        Timestamp(secs)
+    }
+}
+
+impl From<Timestamp> for u64 {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.0
+    }
+}
+
+impl PartialEq<u64> for Timestamp {
+    fn eq(&self, other: &u64) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialOrd<u64> for Timestamp {
+    fn partial_cmp(&self, other: &u64) -> Option<std::cmp::Ordering> {
+        self.0.partial_cmp(other)
+    }
+}
+
+impl From<Timestamp> for SystemTime {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.to_system_time()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<Timestamp> for chrono::DateTime<chrono::Utc> {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.to_datetime()
+    }
+}
+
+/// The unique id of an item.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize,
+)]
+#[serde(transparent)]
+pub struct ItemId(pub u32);
+
+impl From<u32> for ItemId {
+    fn from(id: u32) -> Self {
+        ItemId(id)
+    }
+}
+
+impl From<ItemId> for u32 {
+    fn from(id: ItemId) -> Self {
+        id.0
+    }
+}
+
+impl PartialEq<u32> for ItemId {
+    fn eq(&self, other: &u32) -> bool {
+        self.0 == *other
+    }
+}
+
+impl fmt::Display for ItemId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for ItemId {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(ItemId)
+    }
+}
+
+/// The unique username of a user. Case-sensitive.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Username(pub String);
+
+impl Username {
+    /// Return the username as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Username {
+    fn from(username: String) -> Self {
+        Username(username)
+    }
+}
+
+impl From<&str> for Username {
+    fn from(username: &str) -> Self {
+        Username(username.to_string())
+    }
+}
+
+impl From<Username> for String {
+    fn from(username: Username) -> Self {
+        username.0
+    }
+}
+
+impl Deref for Username {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Username {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Username {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for Username {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Username {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl fmt::Display for Username {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// One of the story listings published by the API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub enum StoryList {
+    /// The top stories, as shown on the front page.
+    Top,
+    /// The newest stories.
+    New,
+    /// The best stories.
+    Best,
+    /// The latest Ask HN stories.
+    Ask,
+    /// The latest Show HN stories.
+    Show,
+    /// The latest job stories.
+    Job,
+}
+
+impl StoryList {
+    /// All listings, in the order they appear in the site navigation.
+    pub const ALL: [StoryList; 6] = [
+        StoryList::Top,
+        StoryList::New,
+        StoryList::Best,
+        StoryList::Ask,
+        StoryList::Show,
+        StoryList::Job,
+    ];
+
+    /// Return the name of the API endpoint of the listing, e.g.
+    /// `topstories`.
+    pub fn api_name(self) -> &'static str {
+        match self {
+            StoryList::Top => "topstories",
+            StoryList::New => "newstories",
+            StoryList::Best => "beststories",
+            StoryList::Ask => "askstories",
+            StoryList::Show => "showstories",
+            StoryList::Job => "jobstories",
+        }
+    }
+}
+
+/// The kind of a story, see [`Item::story_category`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub enum StoryCategory {
+    /// A question to the community, titled `Ask HN: ...`.
+    AskHn,
+    /// A project submitted by its author, titled `Show HN: ...`.
+    ShowHn,
+    /// The launch of a YC company, titled `Launch HN: ...`.
+    LaunchHn,
+    /// A job posting.
+    Job,
+    /// Any other story or poll.
+    Regular,
+}
+
+/// How far an item was hidden by flags or moderators, see
+/// [`Item::moderation_status`]. Ordered from visible to deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub enum ModerationStatus {
+    /// Shown to everyone.
+    Visible,
+    /// Flagged by users, shown as `[flagged]` on the website. The API doesn't
+    /// report flags.
+    Flagged,
+    /// Killed by flags or moderators, or posted by a banned account.
+    Dead,
+    /// Deleted by its author.
+    Deleted,
+}
 
 /// An API item, for example a story or a comment.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(tag = "type")]
 #[serde(rename_all = "lowercase")]
 pub enum Item {
@@ -21,7 +284,7 @@ pub enum Item {
 
 impl Item {
     /// Return the id of this item.
-    pub fn id(&self) -> u32 {
+    pub fn id(&self) -> ItemId {
         match self {
             Item::Story(story) => story.id,
             Item::Comment(comment) => comment.id,
@@ -31,6 +294,121 @@ impl Item {
         }
     }
 
+    /// Return the creation date of this item.
+    pub fn time(&self) -> Timestamp {
+        match self {
+            Item::Story(story) => story.time,
+            Item::Comment(comment) => comment.time,
+            Item::Job(job) => job.time,
+            Item::Poll(poll) => poll.time,
+            Item::Pollopt(pollopt) => pollopt.time,
+        }
+    }
+
+    /// Return the fields of this item that this crate does not know about.
+    #[cfg(feature = "extra-fields")]
+    pub fn extra(&self) -> &serde_json::Map<String, serde_json::Value> {
+        match self {
+            Item::Story(story) => &story.extra,
+            Item::Comment(comment) => &comment.extra,
+            Item::Job(job) => &job.extra,
+            Item::Poll(poll) => &poll.extra,
+            Item::Pollopt(pollopt) => &pollopt.extra,
+        }
+    }
+
+    /// Return the type of this item as named by the API, e.g. `"story"`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Item::Story(_) => "story",
+            Item::Comment(_) => "comment",
+            Item::Job(_) => "job",
+            Item::Poll(_) => "poll",
+            Item::Pollopt(_) => "pollopt",
+        }
+    }
+
+    /// Return the score of this item, if available.
+    pub fn score(&self) -> Option<u32> {
+        match self {
+            Item::Story(story) => Some(story.score),
+            Item::Job(job) => Some(job.score),
+            Item::Poll(poll) => Some(poll.score),
+            Item::Pollopt(pollopt) => Some(pollopt.score),
+            _ => None,
+        }
+    }
+
+    /// Return the URL of this item, if available.
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            Item::Story(story) => story.url.as_deref(),
+            Item::Job(job) => job.url.as_deref(),
+            _ => None,
+        }
+        .filter(|url| !url.is_empty())
+    }
+
+    /// Return the domain of the story or job URL, e.g. `github.com`.
+    ///
+    /// With the `public-suffix` feature this is the registrable domain, so
+    /// `gist.github.com` becomes `github.com`. Otherwise it is the host
+    /// without a `www.` prefix.
+    pub fn domain(&self) -> Option<String> {
+        let url = url::Url::parse(self.url()?).ok()?;
+        let host = url.host_str()?.to_ascii_lowercase();
+        #[cfg(feature = "public-suffix")]
+        if let Some(domain) = psl::domain_str(&host) {
+            return Some(domain.to_string());
+        }
+        Some(host.strip_prefix("www.").unwrap_or(&host).to_string())
+    }
+
+    /// Return the links of this item: the story or job URL, followed by the
+    /// links in the text, without duplicates.
+    ///
+    /// Links relative to the website, e.g. `item?id=8863`, are resolved.
+    /// Links that are not valid URLs are skipped.
+    pub fn extract_links(&self) -> Vec<url::Url> {
+        let base = url::Url::parse(crate::permalink::SITE_BASE_URL).expect("valid base url");
+        let mut links: Vec<url::Url> = Vec::new();
+        let text_links = self
+            .text()
+            .map(crate::html::link_targets)
+            .unwrap_or_default();
+        for link in self
+            .url()
+            .into_iter()
+            .chain(text_links.iter().map(String::as_str))
+        {
+            if let Ok(url) = base.join(link.trim()) {
+                if !links.contains(&url) {
+                    links.push(url);
+                }
+            }
+        }
+        links
+    }
+
+    /// Return the parent of this item: the parent item of a comment, or the
+    /// poll of a poll option.
+    pub fn parent(&self) -> Option<ItemId> {
+        match self {
+            Item::Comment(comment) => Some(comment.parent),
+            Item::Pollopt(pollopt) => Some(pollopt.poll),
+            _ => None,
+        }
+    }
+
+    /// Return the total comment count of this item, if available.
+    pub fn descendants(&self) -> Option<u32> {
+        match self {
+            Item::Story(story) => Some(story.descendants),
+            Item::Poll(poll) => Some(poll.descendants),
+            _ => None,
+        }
+    }
+
     /// Return the title of this item, if available.
     pub fn title(&self) -> Option<&str> {
         match self {
@@ -43,120 +421,307 @@ impl Item {
 
     /// Return the author of this item, if available.
     pub fn author(&self) -> Option<&str> {
+        let by = match self {
+            Item::Story(story) => &story.by,
+            Item::Comment(comment) => &comment.by,
+            Item::Poll(poll) => &poll.by,
+            Item::Pollopt(pollopt) => &pollopt.by,
+            _ => return None,
+        };
+        Some(by.as_str()).filter(|by| !by.is_empty())
+    }
+
+    /// Return the text of this item, if available. HTML.
+    pub fn text(&self) -> Option<&str> {
         match self {
-            Item::Story(story) => Some(&story.by),
-            Item::Comment(comment) => Some(&comment.by),
-            Item::Poll(poll) => Some(&poll.by),
-            Item::Pollopt(pollopt) => Some(&pollopt.by),
-            _ => None,
+            Item::Story(story) => story.text.as_deref(),
+            Item::Comment(comment) => Some(comment.text.as_str()).filter(|text| !text.is_empty()),
+            Item::Job(job) => job.text.as_deref(),
+            Item::Poll(poll) => poll.text.as_deref(),
+            Item::Pollopt(pollopt) => pollopt.text.as_deref(),
         }
     }
+
+    /// Return the ids of this item's comments, in ranked display order.
+    pub fn kids(&self) -> &[ItemId] {
+        let kids = match self {
+            Item::Story(story) => &story.kids,
+            Item::Comment(comment) => &comment.kids,
+            Item::Poll(poll) => &poll.kids,
+            _ => return &[],
+        };
+        kids.as_deref().unwrap_or_default()
+    }
+
+    /// Return whether this item was deleted.
+    pub fn is_deleted(&self) -> bool {
+        match self {
+            Item::Story(story) => story.deleted,
+            Item::Comment(comment) => comment.deleted,
+            Item::Job(job) => job.deleted,
+            Item::Poll(poll) => poll.deleted,
+            Item::Pollopt(pollopt) => pollopt.deleted,
+        }
+    }
+
+    /// Return whether this item is dead, i.e. killed by flags or moderators.
+    pub fn is_dead(&self) -> bool {
+        match self {
+            Item::Story(story) => story.dead,
+            Item::Comment(comment) => comment.dead,
+            Item::Job(job) => job.dead,
+            Item::Poll(poll) => poll.dead,
+            Item::Pollopt(pollopt) => pollopt.dead,
+        }
+    }
+
+    /// Return whether this item is neither deleted nor dead.
+    pub fn is_alive(&self) -> bool {
+        !self.is_deleted() && !self.is_dead()
+    }
+
+    /// Return the category of this story, poll or job, based on its type and
+    /// the prefix of its title, ignoring case. `None` for comments and poll
+    /// options.
+    pub fn story_category(&self) -> Option<StoryCategory> {
+        let title = match self {
+            Item::Job(_) => return Some(StoryCategory::Job),
+            Item::Story(_) | Item::Poll(_) => self.title().unwrap_or_default().trim_start(),
+            Item::Comment(_) | Item::Pollopt(_) => return None,
+        };
+        let prefixes = [
+            ("ask hn:", StoryCategory::AskHn),
+            ("show hn:", StoryCategory::ShowHn),
+            ("launch hn:", StoryCategory::LaunchHn),
+        ];
+        let category = prefixes.iter().find(|(prefix, _)| {
+            title
+                .get(..prefix.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+        });
+        Some(category.map_or(StoryCategory::Regular, |(_, category)| *category))
+    }
+
+    /// Return the moderation status of this item as reported by the API.
+    ///
+    /// The API doesn't report flags, so this is never
+    /// [`ModerationStatus::Flagged`]. With the `scrape` feature,
+    /// `HnClient::item_moderation_status` also checks the website.
+    pub fn moderation_status(&self) -> ModerationStatus {
+        if self.is_deleted() {
+            ModerationStatus::Deleted
+        } else if self.is_dead() {
+            ModerationStatus::Dead
+        } else {
+            ModerationStatus::Visible
+        }
+    }
+}
+
+/// Selects which items to skip in batch methods.
+///
+/// The default filter keeps all items. Skipped items can still be recognized
+/// through [`Item::is_deleted`] and [`Item::is_dead`] when they are kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ItemFilter {
+    /// Skip deleted items.
+    pub skip_deleted: bool,
+    /// Skip dead items.
+    pub skip_dead: bool,
+}
+
+impl ItemFilter {
+    /// A filter that keeps all items.
+    pub const ALL: ItemFilter = ItemFilter {
+        skip_deleted: false,
+        skip_dead: false,
+    };
+
+    /// A filter that only keeps items that are neither deleted nor dead.
+    pub const ALIVE: ItemFilter = ItemFilter {
+        skip_deleted: true,
+        skip_dead: true,
+    };
+
+    /// Return whether the filter keeps `item`.
+    pub fn matches(&self, item: &Item) -> bool {
+        !(self.skip_deleted && item.is_deleted() || self.skip_dead && item.is_dead())
+    }
 }
 
 /// A story.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Story {
     /// The item's unique id.
-    pub id: u32,
+    pub id: ItemId,
+    /// Whether the item was deleted. Deleted items lack most other fields.
+    #[serde(default)]
+    pub deleted: bool,
+    /// Whether the item is dead, i.e. killed by flags or moderators.
+    #[serde(default)]
+    pub dead: bool,
     /// The total comment count.
+    #[serde(default)]
     pub descendants: u32,
     /// The username of the item's author.
-    pub by: String,
+    #[serde(default)]
+    pub by: Username,
     /// The ids of the item's comments, in ranked display order.
-    pub kids: Option<Vec<u32>>,
+    pub kids: Option<Vec<ItemId>>,
     /// The story's score.
+    #[serde(default)]
     pub score: u32,
     /// The title of the story.
+    #[serde(default)]
     pub title: String,
     /// The URL of the story.
     pub url: Option<String>,
     /// The story text. HTML.
     pub text: Option<String>,
-    /// Creation date of the item, in Unix Time.
-    pub time: u64,
+    /// Creation date of the item.
+    pub time: Timestamp,
+    /// Fields returned by the API that this crate does not know about. Only
+    /// with the `extra-fields` feature.
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// A comment.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Comment {
     /// The item's unique id.
-    pub id: u32,
+    pub id: ItemId,
+    /// Whether the item was deleted. Deleted items lack most other fields.
+    #[serde(default)]
+    pub deleted: bool,
+    /// Whether the item is dead, i.e. killed by flags or moderators.
+    #[serde(default)]
+    pub dead: bool,
     /// The username of the item's author.
-    pub by: String,
+    #[serde(default)]
+    pub by: Username,
     /// The ids of the item's comments, in ranked display order.
-    pub kids: Option<Vec<u32>>,
+    pub kids: Option<Vec<ItemId>>,
     /// The comment's parent: either another comment or the relevant story.
-    pub parent: u32,
+    pub parent: ItemId,
     /// The comment text. HTML.
+    #[serde(default)]
     pub text: String,
-    /// Creation date of the item, in Unix Time.
-    pub time: u64,
+    /// Creation date of the item.
+    pub time: Timestamp,
+    /// Fields returned by the API that this crate does not know about. Only
+    /// with the `extra-fields` feature.
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// A job.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Job {
     /// The item's unique id.
-    pub id: u32,
+    pub id: ItemId,
+    /// Whether the item was deleted. Deleted items lack most other fields.
+    #[serde(default)]
+    pub deleted: bool,
+    /// Whether the item is dead, i.e. killed by flags or moderators.
+    #[serde(default)]
+    pub dead: bool,
     /// The story's score, or the votes for a pollopt.
+    #[serde(default)]
     pub score: u32,
     /// The job text. HTML.
     pub text: Option<String>,
-    /// Creation date of the item, in Unix Time.
-    pub time: u64,
+    /// Creation date of the item.
+    pub time: Timestamp,
     /// The title of the job.
+    #[serde(default)]
     pub title: String,
     /// The URL of the story.
     pub url: Option<String>,
+    /// Fields returned by the API that this crate does not know about. Only
+    /// with the `extra-fields` feature.
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// A poll.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Poll {
     /// The item's unique id.
-    pub id: u32,
+    pub id: ItemId,
+    /// Whether the item was deleted. Deleted items lack most other fields.
+    #[serde(default)]
+    pub deleted: bool,
+    /// Whether the item is dead, i.e. killed by flags or moderators.
+    #[serde(default)]
+    pub dead: bool,
     /// The username of the item's author.
-    pub by: String,
+    #[serde(default)]
+    pub by: Username,
     /// The total comment count.
+    #[serde(default)]
     pub descendants: u32,
     /// The ids of the item's comments, in ranked display order.
-    pub kids: Option<Vec<u32>>,
+    pub kids: Option<Vec<ItemId>>,
     /// A list of related pollopts, in display order.
-    pub parts: Option<Vec<u32>>,
+    pub parts: Option<Vec<ItemId>>,
     /// The story's score.
+    #[serde(default)]
     pub score: u32,
     /// The title of the story.
+    #[serde(default)]
     pub title: String,
     /// The story text. HTML.
     pub text: Option<String>,
-    /// Creation date of the item, in Unix Time.
-    pub time: u64,
+    /// Creation date of the item.
+    pub time: Timestamp,
+    /// Fields returned by the API that this crate does not know about. Only
+    /// with the `extra-fields` feature.
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// A poll option belonging to a poll.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Pollopt {
     /// The item's unique id.
-    pub id: u32,
+    pub id: ItemId,
+    /// Whether the item was deleted. Deleted items lack most other fields.
+    #[serde(default)]
+    pub deleted: bool,
+    /// Whether the item is dead, i.e. killed by flags or moderators.
+    #[serde(default)]
+    pub dead: bool,
     /// The username of the item's author.
-    pub by: String,
+    #[serde(default)]
+    pub by: Username,
     /// The pollopt's associated poll.
-    pub poll: u32,
+    pub poll: ItemId,
     /// The votes for a pollopt.
+    #[serde(default)]
     pub score: u32,
     /// The story text. HTML.
     pub text: Option<String>,
-    /// Creation date of the item, in Unix Time.
-    pub time: u64,
+    /// Creation date of the item.
+    pub time: Timestamp,
+    /// Fields returned by the API that this crate does not know about. Only
+    /// with the `extra-fields` feature.
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// A user profile.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct User {
     /// The user's unique username. Case-sensitive.
-    pub id: String,
-    /// Creation date of the user, in Unix Time.
-    pub created: u64,
+    pub id: Username,
+    /// Creation date of the user.
+    pub created: Timestamp,
     /// The user's karma.
     pub karma: u32,
     /// Delay in minutes between a comment's creation and its visibility to
@@ -165,22 +730,114 @@ pub struct User {
     /// The user's optional self-description. HTML.
     pub about: Option<String>,
     /// List of the user's stories, polls and comments.
-    pub submitted: Vec<u32>,
+    pub submitted: Vec<ItemId>,
+    /// Fields returned by the API that this crate does not know about. Only
+    /// with the `extra-fields` feature.
+    #[cfg(feature = "extra-fields")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// A list of recently updated items and users.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Updates {
     /// A list of recently changed items.
-    pub items: Vec<u32>,
+    pub items: Vec<ItemId>,
     /// A list of recently changed usernames.
-    pub profiles: Vec<String>,
+    pub profiles: Vec<Username>,
+}
+
+impl Updates {
+    /// Return the changes in this snapshot that are not part of `previous`.
+    ///
+    /// If there is no previous snapshot, all changes are returned.
+    pub fn events_since(&self, previous: Option<&Updates>) -> Vec<UpdateEvent> {
+        let items = self
+            .items
+            .iter()
+            .filter(|id| !previous.is_some_and(|previous| previous.items.contains(id)))
+            .map(|id| UpdateEvent::Item(*id));
+        let profiles = self
+            .profiles
+            .iter()
+            .filter(|name| !previous.is_some_and(|previous| previous.profiles.contains(name)))
+            .map(|name| UpdateEvent::Profile(name.clone()));
+        items.chain(profiles).collect()
+    }
+}
+
+/// A change reported by the updates endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum UpdateEvent {
+    /// The item with the contained id changed.
+    Item(ItemId),
+    /// The profile of the user with the contained username changed.
+    Profile(Username),
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_timestamp_conversions() {
+        let timestamp: Timestamp = serde_json::from_str("1175714200").unwrap();
+        assert_eq!(timestamp.as_secs(), 1175714200);
+        assert_eq!(u64::from(timestamp), 1175714200);
+        assert_eq!(timestamp, 1175714200);
+        assert!(timestamp > 1175714199 && timestamp < 1175714201);
+        assert_eq!(
+            SystemTime::from(timestamp),
+            UNIX_EPOCH + Duration::from_secs(1175714200)
+        );
+        #[cfg(feature = "chrono")]
+        assert_eq!(timestamp.to_datetime().timestamp(), 1175714200);
+    }
+
+    #[test]
+    fn test_newtypes() {
+        let id: ItemId = serde_json::from_str("8863").unwrap();
+        assert_eq!(id, 8863);
+        assert_eq!(u32::from(id), 8863);
+        assert_eq!("8863".parse(), Ok(id));
+        let username: Username = serde_json::from_str(r#""pg""#).unwrap();
+        assert_eq!(username, "pg");
+        assert_eq!(username.len(), 2);
+        assert_eq!(serde_json::to_string(&username).unwrap(), r#""pg""#);
+    }
+
+    #[test]
+    fn test_update_events() {
+        let previous = Updates {
+            items: vec![ItemId(1), ItemId(2)],
+            profiles: vec!["pg".into()],
+        };
+        let current = Updates {
+            items: vec![ItemId(2), ItemId(3)],
+            profiles: vec!["pg".into(), "dang".into()],
+        };
+        assert_eq!(
+            current.events_since(Some(&previous)),
+            vec![
+                UpdateEvent::Item(ItemId(3)),
+                UpdateEvent::Profile("dang".into())
+            ]
+        );
+        assert_eq!(current.events_since(None).len(), 4);
+    }
+
+    #[cfg(feature = "extra-fields")]
+    #[test]
+    fn test_extra_fields() {
+        let json = r#"{"by": "a", "id": 1, "parent": 0, "text": "", "time": 0, "type": "comment", "flair": "new"}"#;
+        let item: Item = serde_json::from_str(json).unwrap();
+        assert_eq!(item.extra().len(), 1);
+        assert_eq!(item.extra()["flair"], "new");
+        let roundtrip: serde_json::Value = serde_json::to_value(&item).unwrap();
+        assert_eq!(roundtrip["flair"], "new");
+        assert_eq!(roundtrip["type"], "comment");
+    }
+
     #[test]
     fn test_story() {
         let json = r#"
@@ -195,8 +852,8 @@ mod tests {
           "type" : "story",
           "url" : "http://www.getdropbox.com/u/2/screencast.html"
         }"#;
-        let _story: Story = serde_json::from_str(&json).unwrap();
-        let _item: Item = serde_json::from_str(&json).unwrap();
+        let _story: Story = serde_json::from_str(json).unwrap();
+        let _item: Item = serde_json::from_str(json).unwrap();
     }
 
     #[test]
@@ -213,8 +870,9 @@ mod tests {
           "title" : "Ask HN: The Arc Effect",
           "type" : "story"
         }"#;
-        let _story: Story = serde_json::from_str(&json).unwrap();
-        let _item: Item = serde_json::from_str(&json).unwrap();
+        let _story: Story = serde_json::from_str(json).unwrap();
+        let item: Item = serde_json::from_str(json).unwrap();
+        assert_eq!(item.story_category(), Some(StoryCategory::AskHn));
     }
 
     #[test]
@@ -229,8 +887,66 @@ mod tests {
           "time" : 1314211127,
           "type" : "comment"
         }"#;
-        let _comment: Comment = serde_json::from_str(&json).unwrap();
-        let _item: Item = serde_json::from_str(&json).unwrap();
+        let _comment: Comment = serde_json::from_str(json).unwrap();
+        let _item: Item = serde_json::from_str(json).unwrap();
+    }
+
+    #[test]
+    fn test_extract_links() {
+        let json = r#"
+        {
+          "by" : "a",
+          "id" : 1,
+          "score" : 1,
+          "text" : "<a href=\"https:&#x2F;&#x2F;example.com&#x2F;\" rel=\"nofollow\">https:&#x2F;&#x2F;example.com&#x2F;</a> and <a href=\"item?id=8863\">this</a>",
+          "time" : 0,
+          "title" : "Links",
+          "type" : "story",
+          "url" : "https://example.com/"
+        }"#;
+        let item: Item = serde_json::from_str(json).unwrap();
+        let links: Vec<_> = item.extract_links().into_iter().map(String::from).collect();
+        assert_eq!(
+            links,
+            vec![
+                "https://example.com/",
+                "https://news.ycombinator.com/item?id=8863"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deleted_comment() {
+        let json = r#"
+        {
+          "deleted" : true,
+          "id" : 2921984,
+          "parent" : 2921506,
+          "time" : 1314211128,
+          "type" : "comment"
+        }"#;
+        let item: Item = serde_json::from_str(json).unwrap();
+        assert!(item.is_deleted());
+        assert_eq!(item.author(), None);
+        assert_eq!(item.moderation_status(), ModerationStatus::Deleted);
+    }
+
+    #[test]
+    fn test_item_filter() {
+        let dead: Item = serde_json::from_str(
+            r#"{"by": "a", "dead": true, "id": 1, "parent": 0, "text": "", "time": 0, "type": "comment"}"#,
+        )
+        .unwrap();
+        assert!(dead.is_dead());
+        assert!(!dead.is_alive());
+        assert_eq!(dead.moderation_status(), ModerationStatus::Dead);
+        assert!(ItemFilter::ALL.matches(&dead));
+        assert!(!ItemFilter::ALIVE.matches(&dead));
+        let filter = ItemFilter {
+            skip_deleted: true,
+            ..ItemFilter::default()
+        };
+        assert!(filter.matches(&dead));
     }
 
     #[test]
@@ -246,8 +962,8 @@ mod tests {
           "type" : "job",
           "url" : ""
         }"#;
-        let _job: Job = serde_json::from_str(&json).unwrap();
-        let _item: Item = serde_json::from_str(&json).unwrap();
+        let _job: Job = serde_json::from_str(json).unwrap();
+        let _item: Item = serde_json::from_str(json).unwrap();
     }
 
     #[test]
@@ -264,8 +980,8 @@ mod tests {
           "title" : "Poll: What would happen if News.YC had explicit support for polls?",
           "type" : "poll"
         }"#;
-        let _poll: Poll = serde_json::from_str(&json).unwrap();
-        let _item: Item = serde_json::from_str(&json).unwrap();
+        let _poll: Poll = serde_json::from_str(json).unwrap();
+        let _item: Item = serde_json::from_str(json).unwrap();
     }
 
     #[test]
@@ -280,7 +996,7 @@ mod tests {
           "time" : 1207886576,
           "type" : "pollopt"
         }"#;
-        let _pollopt: Pollopt = serde_json::from_str(&json).unwrap();
-        let _item: Item = serde_json::from_str(&json).unwrap();
+        let _pollopt: Pollopt = serde_json::from_str(json).unwrap();
+        let _item: Item = serde_json::from_str(json).unwrap();
     }
 }
@@ -0,0 +1,151 @@
+//! Invariant checks for items, used by clients in strict mode.
+//!
+//! The API is permissive: missing fields are filled with defaults, so
+//! historical oddities like stories without a title still deserialize. In
+//! strict mode, see [`HnClientBuilder::strict`], such items are rejected with
+//! a [`ValidationError`] instead.
+//!
+//! [`HnClientBuilder::strict`]: crate::nonblocking::HnClientBuilder::strict
+
+use std::fmt;
+
+use super::types::{Item, ItemId};
+
+/// A broken invariant of an item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// A story, job or poll has no title.
+    MissingTitle,
+    /// A story, comment, poll or pollopt has no author.
+    MissingAuthor,
+    /// A comment has no text.
+    MissingText,
+    /// A comment or pollopt refers to itself or a newer item as its parent.
+    InvalidParent(ItemId),
+    /// A poll has no options.
+    MissingPollOptions,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::MissingTitle => write!(f, "missing title"),
+            Violation::MissingAuthor => write!(f, "missing author"),
+            Violation::MissingText => write!(f, "missing text"),
+            Violation::InvalidParent(parent) => write!(f, "invalid parent {}", parent),
+            Violation::MissingPollOptions => write!(f, "missing poll options"),
+        }
+    }
+}
+
+/// An item that broke one or more invariants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// The id of the item.
+    pub id: ItemId,
+    /// All broken invariants.
+    pub violations: Vec<Violation>,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "item {} is invalid: ", self.id)?;
+        for (i, violation) in self.violations.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Return the invariants the item breaks. Deleted items lack most fields
+/// and are always valid.
+pub fn violations(item: &Item) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    if item.is_deleted() {
+        return violations;
+    }
+    let check_title = |violations: &mut Vec<_>, title: &str| {
+        if title.trim().is_empty() {
+            violations.push(Violation::MissingTitle);
+        }
+    };
+    let check_parent = |violations: &mut Vec<_>, parent: ItemId| {
+        if parent >= item.id() {
+            violations.push(Violation::InvalidParent(parent));
+        }
+    };
+    match item {
+        Item::Story(story) => check_title(&mut violations, &story.title),
+        Item::Job(job) => check_title(&mut violations, &job.title),
+        Item::Poll(poll) => {
+            check_title(&mut violations, &poll.title);
+            if poll.parts.as_deref().unwrap_or_default().is_empty() {
+                violations.push(Violation::MissingPollOptions);
+            }
+        }
+        Item::Comment(comment) => {
+            check_parent(&mut violations, comment.parent);
+            if comment.text.is_empty() && !comment.dead {
+                violations.push(Violation::MissingText);
+            }
+        }
+        Item::Pollopt(pollopt) => check_parent(&mut violations, pollopt.poll),
+    }
+    if item.author().is_none() && !matches!(item, Item::Job(_)) {
+        violations.push(Violation::MissingAuthor);
+    }
+    violations
+}
+
+/// Check the invariants of an item.
+///
+/// Fails with the list of broken invariants, if any.
+pub fn validate(item: &Item) -> Result<(), ValidationError> {
+    let violations = violations(item);
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationError {
+            id: item.id(),
+            violations,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_violations() {
+        let item = |json: &str| -> Item { serde_json::from_str(json).unwrap() };
+
+        let story = item(r#"{"by": "a", "id": 1, "title": "t", "time": 0, "type": "story"}"#);
+        assert_eq!(validate(&story), Ok(()));
+
+        let story = item(r#"{"id": 1, "time": 0, "type": "story"}"#);
+        assert_eq!(
+            violations(&story),
+            vec![Violation::MissingTitle, Violation::MissingAuthor]
+        );
+
+        let deleted = item(r#"{"id": 1, "deleted": true, "time": 0, "type": "story"}"#);
+        assert_eq!(validate(&deleted), Ok(()));
+
+        let comment = item(r#"{"by": "a", "id": 2, "parent": 3, "time": 0, "type": "comment"}"#);
+        let err = validate(&comment).unwrap_err();
+        assert_eq!(
+            err.violations,
+            vec![Violation::InvalidParent(ItemId(3)), Violation::MissingText]
+        );
+        assert_eq!(
+            err.to_string(),
+            "item 2 is invalid: invalid parent 3, missing text"
+        );
+    }
+}
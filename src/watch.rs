@@ -0,0 +1,539 @@
+//! Track changes of single items, users and the front page over time.
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use futures::StreamExt;
+//! use hn_api::nonblocking::HnClient;
+//!
+//! # async fn run() -> hn_api::Result<()> {
+//! let client = HnClient::init()?;
+//! let changes = client.watch_item(8863, Duration::from_secs(60));
+//! futures::pin_mut!(changes);
+//! while let Some(change) = changes.next().await {
+//!     println!("{:?}", change?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::HashSet, time::Duration};
+
+use futures::stream::{self, Stream, StreamExt};
+
+use super::{
+    listing::ListingDiff,
+    nonblocking::HnClient,
+    runtime,
+    tree::{CommentTree, FlatComment, TraversalOrder},
+    types::{Item, ItemFilter, ItemId, StoryList, Timestamp, User},
+    Result,
+};
+
+/// A difference between two fetches of the same item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ItemChange {
+    /// The score changed.
+    Score {
+        /// The previous score.
+        from: u32,
+        /// The current score.
+        to: u32,
+    },
+    /// The comment count changed.
+    Descendants {
+        /// The previous comment count.
+        from: u32,
+        /// The current comment count.
+        to: u32,
+    },
+    /// The title was edited.
+    Title {
+        /// The previous title.
+        from: String,
+        /// The current title.
+        to: String,
+    },
+    /// The text was edited.
+    Text,
+    /// The item was killed by flags or moderators.
+    Killed,
+    /// The item is no longer dead.
+    Revived,
+    /// The item was deleted.
+    Deleted,
+}
+
+impl ItemChange {
+    /// Return the changes between two fetches of the same item.
+    pub fn between(old: &Item, new: &Item) -> Vec<ItemChange> {
+        let mut changes = Vec::new();
+        if let (Some(from), Some(to)) = (old.score(), new.score()) {
+            if from != to {
+                changes.push(ItemChange::Score { from, to });
+            }
+        }
+        if let (Some(from), Some(to)) = (old.descendants(), new.descendants()) {
+            if from != to {
+                changes.push(ItemChange::Descendants { from, to });
+            }
+        }
+        if let (Some(from), Some(to)) = (old.title(), new.title()) {
+            if from != to {
+                changes.push(ItemChange::Title {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                });
+            }
+        }
+        if old.text() != new.text() && !new.is_deleted() {
+            changes.push(ItemChange::Text);
+        }
+        match (old.is_dead(), new.is_dead()) {
+            (false, true) => changes.push(ItemChange::Killed),
+            (true, false) => changes.push(ItemChange::Revived),
+            _ => {}
+        }
+        if !old.is_deleted() && new.is_deleted() {
+            changes.push(ItemChange::Deleted);
+        }
+        changes
+    }
+
+    /// Return the change of the score, if this is a score change.
+    pub fn score_delta(&self) -> Option<i64> {
+        match self {
+            ItemChange::Score { from, to } => Some(i64::from(*to) - i64::from(*from)),
+            _ => None,
+        }
+    }
+}
+
+/// A difference between two fetches of the same user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserChange {
+    /// The karma changed.
+    Karma {
+        /// The previous karma.
+        from: u32,
+        /// The current karma.
+        to: u32,
+    },
+    /// The self-description was edited.
+    About,
+    /// The user submitted new stories, comments or polls.
+    Submitted {
+        /// The ids of the new submissions, newest first.
+        ids: Vec<ItemId>,
+        /// The new submissions, if the watch hydrates them. Missing items
+        /// are skipped.
+        items: Vec<Item>,
+    },
+}
+
+impl UserChange {
+    /// Return the changes between two fetches of the same user. New
+    /// submissions are not hydrated.
+    pub fn between(old: &User, new: &User) -> Vec<UserChange> {
+        let mut changes = Vec::new();
+        if old.karma != new.karma {
+            changes.push(UserChange::Karma {
+                from: old.karma,
+                to: new.karma,
+            });
+        }
+        if old.about != new.about {
+            changes.push(UserChange::About);
+        }
+        let known: HashSet<_> = old.submitted.iter().collect();
+        let ids: Vec<_> = new
+            .submitted
+            .iter()
+            .filter(|id| !known.contains(id))
+            .copied()
+            .collect();
+        if !ids.is_empty() {
+            changes.push(UserChange::Submitted {
+                ids,
+                items: Vec::new(),
+            });
+        }
+        changes
+    }
+}
+
+/// The number of stories on the front page.
+pub const FRONT_PAGE_SIZE: usize = 30;
+
+/// A change of the front page between two snapshots. Ranks start at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RankEvent {
+    /// The story reached the front page.
+    Entered {
+        /// The id of the story.
+        id: ItemId,
+        /// The current rank.
+        rank: usize,
+    },
+    /// The story fell off the front page.
+    Dropped {
+        /// The id of the story.
+        id: ItemId,
+        /// The last rank on the front page.
+        rank: usize,
+    },
+    /// The story moved on the front page.
+    Moved {
+        /// The id of the story.
+        id: ItemId,
+        /// The previous rank.
+        from: usize,
+        /// The current rank.
+        to: usize,
+    },
+}
+
+impl RankEvent {
+    /// Return the events turning the `old` front page into the `new` one:
+    /// first stories that dropped off, then stories that entered or moved,
+    /// in the order of `new`, see [`ListingDiff`].
+    pub fn between(old: &[ItemId], new: &[ItemId]) -> Vec<RankEvent> {
+        ListingDiff::between(old, new).events()
+    }
+
+    /// Return how many places the story moved up, negative if it moved
+    /// down, if this is a move.
+    pub fn places(&self) -> Option<i64> {
+        match self {
+            RankEvent::Moved { from, to, .. } => Some(*from as i64 - *to as i64),
+            _ => None,
+        }
+    }
+}
+
+/// The front page at one point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RankSnapshot {
+    /// When the front page was fetched.
+    pub time: Timestamp,
+    /// The ids of the stories on the front page, in rank order.
+    pub ids: Vec<ItemId>,
+    /// The changes since the previous snapshot, empty for the first one.
+    pub events: Vec<RankEvent>,
+}
+
+impl RankSnapshot {
+    /// Return the rank of the story, or `None` if it is not on the front
+    /// page.
+    pub fn rank(&self, id: impl Into<ItemId>) -> Option<usize> {
+        let id = id.into();
+        self.ids
+            .iter()
+            .position(|other| *other == id)
+            .map(|i| i + 1)
+    }
+}
+
+impl HnClient {
+    /// Return a stream of snapshots of the front page, the first 30 top
+    /// stories, fetching them every `interval`.
+    ///
+    /// A failed fetch yields an error without ending the stream.
+    pub fn track_front_page(
+        &self,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<RankSnapshot>> + '_ {
+        let stream = stream::unfold(
+            (None, false),
+            move |(previous, started): (Option<Vec<ItemId>>, bool)| async move {
+                if started {
+                    runtime::sleep(interval).await;
+                }
+                match self.get_stories(StoryList::Top).await {
+                    Ok(mut ids) => {
+                        ids.truncate(FRONT_PAGE_SIZE);
+                        let events = match &previous {
+                            Some(previous) => RankEvent::between(previous, &ids),
+                            None => Vec::new(),
+                        };
+                        let snapshot = RankSnapshot {
+                            time: Timestamp::now(),
+                            ids: ids.clone(),
+                            events,
+                        };
+                        Some((Ok(snapshot), (Some(ids), true)))
+                    }
+                    Err(err) => Some((Err(err), (previous, true))),
+                }
+            },
+        );
+        self.until_shutdown(stream)
+    }
+
+    /// Return a stream of changes of the item with the specified id,
+    /// fetching it every `interval`.
+    ///
+    /// The first fetch only records the initial state. A failed fetch yields
+    /// an error without ending the stream.
+    pub fn watch_item(
+        &self,
+        id: impl Into<ItemId>,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<ItemChange>> + '_ {
+        let id = id.into();
+        let stream = stream::unfold(
+            (None, false),
+            move |(previous, started): (Option<Item>, bool)| async move {
+                if started {
+                    runtime::sleep(interval).await;
+                }
+                let (changes, previous) = match self.get_item(id).await {
+                    Ok(current) => {
+                        let changes = match &previous {
+                            Some(previous) => ItemChange::between(previous, &current),
+                            None => Vec::new(),
+                        };
+                        (changes.into_iter().map(Ok).collect(), Some(current))
+                    }
+                    Err(err) => (vec![Err(err)], previous),
+                };
+                Some((stream::iter(changes), (previous, true)))
+            },
+        )
+        .flatten();
+        self.until_shutdown(stream)
+    }
+
+    /// Return a stream of the comments added to the story with the
+    /// specified id, fetching its comment tree every `interval`.
+    ///
+    /// Every fetch is compared to the previous one with
+    /// [`CommentTree::diff`], and the added comments are yielded with their
+    /// depth and the id of their parent, oldest first within one fetch. The
+    /// first fetch only records the existing comments. Each fetch requests
+    /// the whole tree, so long intervals suit large discussions better. A
+    /// failed fetch yields an error without ending the stream.
+    pub fn story_comment_stream(
+        &self,
+        story_id: impl Into<ItemId>,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<FlatComment>> + '_ {
+        let id = story_id.into();
+        let stream = stream::unfold(
+            (None, false),
+            move |(previous, started): (Option<CommentTree>, bool)| async move {
+                if started {
+                    runtime::sleep(interval).await;
+                }
+                let (comments, previous) = match self.get_comment_tree(id).await {
+                    Ok(tree) => {
+                        let added: HashSet<_> = match &previous {
+                            Some(previous) => CommentTree::diff(previous, &tree)
+                                .added
+                                .into_iter()
+                                .map(|change| change.id)
+                                .collect(),
+                            None => HashSet::new(),
+                        };
+                        let mut comments: Vec<_> = tree
+                            .flatten(TraversalOrder::DepthFirst)
+                            .into_iter()
+                            .filter(|comment| added.contains(&comment.item.id()))
+                            .collect();
+                        comments.sort_by_key(|comment| (comment.item.time(), comment.item.id()));
+                        (comments.into_iter().map(Ok).collect(), Some(tree))
+                    }
+                    Err(err) => (vec![Err(err)], previous),
+                };
+                Some((stream::iter(comments), (previous, true)))
+            },
+        )
+        .flatten();
+        self.until_shutdown(stream)
+    }
+
+    /// Return a stream of changes of the user with the specified username,
+    /// fetching the profile every `interval`.
+    ///
+    /// The first fetch only records the initial state. A failed fetch yields
+    /// an error without ending the stream.
+    pub fn watch_user<'a>(
+        &'a self,
+        username: &'a str,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<UserChange>> + 'a {
+        self.watch_user_impl(username, interval, false)
+    }
+
+    /// Like [`HnClient::watch_user`], but also fetch the items of new
+    /// submissions.
+    pub fn watch_user_hydrated<'a>(
+        &'a self,
+        username: &'a str,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<UserChange>> + 'a {
+        self.watch_user_impl(username, interval, true)
+    }
+
+    fn watch_user_impl<'a>(
+        &'a self,
+        username: &'a str,
+        interval: Duration,
+        hydrate: bool,
+    ) -> impl Stream<Item = Result<UserChange>> + 'a {
+        let stream = stream::unfold(
+            (None, false),
+            move |(previous, started): (Option<User>, bool)| async move {
+                if started {
+                    runtime::sleep(interval).await;
+                }
+                let failed = |err, previous| Some((stream::iter(vec![Err(err)]), (previous, true)));
+                let current = match self.get_user(username).await {
+                    Ok(current) => current,
+                    Err(err) => return failed(err, previous),
+                };
+                let mut changes = match &previous {
+                    Some(previous) => UserChange::between(previous, &current),
+                    None => Vec::new(),
+                };
+                for change in &mut changes {
+                    if let UserChange::Submitted { ids, items } = change {
+                        if hydrate {
+                            match self.get_items_filtered(ids, ItemFilter::ALL).await {
+                                Ok(fetched) => *items = fetched,
+                                Err(err) => return failed(err, previous),
+                            }
+                        }
+                    }
+                }
+                let changes: Vec<_> = changes.into_iter().map(Ok).collect();
+                Some((stream::iter(changes), (Some(current), true)))
+            },
+        )
+        .flatten();
+        self.until_shutdown(stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cassette::Cassette;
+
+    fn story(score: u32, title: &str, dead: bool) -> Item {
+        serde_json::from_str(&format!(
+            r#"{{"by": "a", "id": 1, "score": {}, "title": "{}", "dead": {}, "descendants": 0, "time": 0, "type": "story"}}"#,
+            score, title, dead
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_user_changes() {
+        let user = |karma: u32, submitted: &[u32]| -> User {
+            serde_json::from_str(&format!(
+                r#"{{"id": "a", "created": 0, "karma": {}, "submitted": {:?}}}"#,
+                karma, submitted
+            ))
+            .unwrap()
+        };
+        let changes = UserChange::between(&user(1, &[1]), &user(3, &[3, 2, 1]));
+        assert_eq!(
+            changes,
+            vec![
+                UserChange::Karma { from: 1, to: 3 },
+                UserChange::Submitted {
+                    ids: vec![ItemId(3), ItemId(2)],
+                    items: Vec::new()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rank_events() {
+        let ids = |ids: &[u32]| -> Vec<ItemId> { ids.iter().copied().map(ItemId).collect() };
+        let events = RankEvent::between(&ids(&[1, 2, 3]), &ids(&[3, 1, 4]));
+        assert_eq!(
+            events,
+            vec![
+                RankEvent::Dropped {
+                    id: ItemId(2),
+                    rank: 2
+                },
+                RankEvent::Moved {
+                    id: ItemId(3),
+                    from: 3,
+                    to: 1
+                },
+                RankEvent::Moved {
+                    id: ItemId(1),
+                    from: 1,
+                    to: 2
+                },
+                RankEvent::Entered {
+                    id: ItemId(4),
+                    rank: 3
+                },
+            ]
+        );
+        assert_eq!(events[1].places(), Some(2));
+        assert_eq!(events[2].places(), Some(-1));
+    }
+
+    #[test]
+    fn test_item_changes() {
+        let old = story(10, "a", false);
+        assert!(ItemChange::between(&old, &old).is_empty());
+        let changes = ItemChange::between(&old, &story(15, "b", true));
+        assert_eq!(
+            changes,
+            vec![
+                ItemChange::Score { from: 10, to: 15 },
+                ItemChange::Title {
+                    from: "a".into(),
+                    to: "b".into()
+                },
+                ItemChange::Killed,
+            ]
+        );
+        assert_eq!(changes[0].score_delta(), Some(5));
+    }
+
+    #[tokio::test]
+    async fn test_story_comment_stream() {
+        let api = "https://hacker-news.firebaseio.com/v0";
+        let comment = |id: u32, time: u32| {
+            format!(
+                r#"{{"by": "a", "id": {}, "parent": 1, "text": "", "time": {}, "type": "comment"}}"#,
+                id, time
+            )
+        };
+        let mut cassette = Cassette::new();
+        cassette
+            .push(
+                format!("{}/item/1.json", api),
+                r#"{"id": 1, "kids": [2], "score": 1, "time": 0, "type": "story"}"#,
+            )
+            .push(
+                format!("{}/item/1.json", api),
+                r#"{"id": 1, "kids": [3, 2], "score": 1, "time": 0, "type": "story"}"#,
+            )
+            .push(
+                format!("{}/item/1.json", api),
+                r#"{"id": 1, "kids": [4, 3], "score": 1, "time": 0, "type": "story"}"#,
+            )
+            .push(format!("{}/item/2.json", api), comment(2, 1))
+            .push(format!("{}/item/3.json", api), comment(3, 2))
+            .push(format!("{}/item/4.json", api), comment(4, 3));
+        let client = HnClient::builder().replay(cassette).build().unwrap();
+        let comments = client.story_comment_stream(1, Duration::from_millis(1));
+        futures::pin_mut!(comments);
+        let comment = comments.next().await.unwrap().unwrap();
+        assert_eq!(comment.item.id(), ItemId(3));
+        assert_eq!(comment.parent, Some(ItemId(1)));
+        assert_eq!(comment.depth, 1);
+        let comment = comments.next().await.unwrap().unwrap();
+        assert_eq!(comment.item.id(), ItemId(4));
+    }
+}